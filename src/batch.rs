@@ -0,0 +1,109 @@
+use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::{AbciQueryRequest, AbciQueryResponse};
+use cosmwasm_std::{Binary, Deps, DepsMut, Env, Event, IbcPacket, MessageInfo, Response, StdResult, Storage};
+
+use crate::contract::{build_query_request, PacketOutcome, pinned_height, prepare_icq_packet};
+use crate::error::ContractError;
+use crate::msg::QueryBatchMsg;
+use crate::requests::request_id_from_packet;
+use crate::state::{BATCH_RESULTS, BatchResponse, BatchResult, PENDING_REQUESTS, RESPONSE_HEIGHTS};
+
+/// Bounds the size of the packet and the gas of its ack.
+const MAX_BATCH_SIZE: usize = 20;
+
+/// Sends the queries in a single packet, pinned to `height` when given. The
+/// packet is never answered from the cache nor coalesced, so every response
+/// comes back from the host together.
+pub fn send_query_batch(mut deps: DepsMut, env: Env, info: MessageInfo, msg: QueryBatchMsg) -> Result<Response, ContractError> {
+    if msg.queries.len() < 2 || msg.queries.len() > MAX_BATCH_SIZE {
+        return Err(ContractError::InvalidBatch {
+            reason: format!("a batch takes 2 to {MAX_BATCH_SIZE} queries, got {}", msg.queries.len()),
+        });
+    }
+    let height = pinned_height(msg.height)?;
+    let requests = msg
+        .queries
+        .iter()
+        .map(|spec| {
+            let mut req = build_query_request(deps.as_ref(), &env, &msg.channel, spec)?;
+            req.height = height;
+            Ok(req)
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+
+    let PacketOutcome::Sent { request_id, msg: packet } = prepare_icq_packet(deps.branch(), &env, &info.sender, &msg.channel, requests)? else {
+        unreachable!("packets of several requests are always sent")
+    };
+    let mut pending = PENDING_REQUESTS.load(deps.storage, request_id)?;
+    pending.batch = true;
+    PENDING_REQUESTS.save(deps.storage, request_id, &pending)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "send_query_batch")
+        .add_attribute("channel", msg.channel)
+        .add_attribute("request_id", request_id.to_string())
+        .add_message(packet))
+}
+
+/// Records the height of every response of an acked packet, and the full
+/// result of a batch. A batch is consistent when the host answered every
+/// request at the same height, the pinned one if any. Returns a
+/// `batch_inconsistent` event otherwise.
+pub fn record_response_heights(
+    storage: &mut dyn Storage,
+    env: &Env,
+    packet: &IbcPacket,
+    requests: &[AbciQueryRequest],
+    responses: &[AbciQueryResponse],
+) -> StdResult<Option<Event>> {
+    let heights: Vec<u64> = responses.iter().map(|response| response.height.max(0) as u64).collect();
+    RESPONSE_HEIGHTS.save(storage, packet.sequence, &heights)?;
+
+    let Some(id) = request_id_from_packet(packet) else {
+        return Ok(None);
+    };
+    if !PENDING_REQUESTS.may_load(storage, id)?.is_some_and(|pending| pending.batch) {
+        return Ok(None);
+    }
+
+    // every request of a batch carries the same height
+    let pinned = requests.first().map(|request| request.height.max(0) as u64).filter(|height| *height != 0);
+    let consistent = responses.len() == requests.len()
+        && heights.iter().all(|height| *height != 0 && *height == heights[0])
+        && pinned.is_none_or(|pinned| heights[0] == pinned);
+
+    let result = BatchResult {
+        channel: packet.src.channel_id.clone(),
+        height: pinned,
+        responses: requests
+            .iter()
+            .zip(responses)
+            .map(|(request, response)| BatchResponse {
+                path: request.path.clone(),
+                height: response.height.max(0) as u64,
+                code: response.code,
+                value: Binary::from(response.value.clone()),
+            })
+            .collect(),
+        consistent,
+        time: env.block.time,
+    };
+    BATCH_RESULTS.save(storage, packet.sequence, &result)?;
+
+    if consistent {
+        return Ok(None);
+    }
+    let heights: Vec<String> = heights.iter().map(u64::to_string).collect();
+    Ok(Some(
+        Event::new("batch_inconsistent")
+            .add_attribute("sequence", packet.sequence.to_string())
+            .add_attribute("channel", result.channel)
+            .add_attribute("pinned_height", pinned.unwrap_or_default().to_string())
+            .add_attribute("heights", heights.join(",")),
+    ))
+}
+
+pub fn query_batch_result(deps: Deps, sequence: u64) -> Result<BatchResult, ContractError> {
+    BATCH_RESULTS
+        .may_load(deps.storage, sequence)?
+        .ok_or(ContractError::NoSuchBatchResult { sequence })
+}
@@ -1,11 +1,12 @@
 use cosmwasm_schema::write_api;
 
-use icq_sender::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use icq_sender::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, SudoMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         execute: ExecuteMsg,
         query: QueryMsg,
+        sudo: SudoMsg,
     }
 }
@@ -0,0 +1,202 @@
+use cosmwasm_std::{Addr, BankMsg, CosmosMsg, Deps, DepsMut, Empty, Env, Event, from_json, MessageInfo, Order, Reply, Response, StdResult, Storage, SubMsg, SubMsgResult, to_json_binary};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::msg::AddConditionMsg;
+use crate::price::derived_price;
+use crate::state::{Comparison, Condition, CONDITION_CURSOR, CONDITIONS, ConditionStatus, CONFIG, NEXT_CONDITION_ID, PENDING_CONDITION_COUNTS, PENDING_CONDITIONS};
+
+pub const CONDITION_REPLY_ID: u64 = 2;
+
+/// Pending conditions a single owner may have, so that nobody can crowd out
+/// the conditions of others.
+const MAX_PENDING_CONDITIONS_PER_OWNER: u64 = 10;
+
+/// Conditions evaluated per TWAP ack, the rest waiting for the next ones, so
+/// the gas of an ack stays bounded however many conditions are pending.
+const CONDITIONS_PER_EVALUATION: usize = 50;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn add_condition(deps: DepsMut, info: MessageInfo, msg: AddConditionMsg) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if msg.base == msg.quote {
+        return Err(ContractError::IdenticalTwapAssets { denom: msg.base });
+    }
+    if msg.threshold.is_zero() {
+        return Err(ContractError::InvalidCondition { reason: "zero threshold".to_string() });
+    }
+    // any other message would run with the contract as its sender, so it
+    // could act on funds and permissions that are not the sender's
+    if info.sender != config.admin {
+        match &msg.msg {
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount }) if !amount.is_empty() && *amount == info.funds => {
+                deps.api.addr_validate(to_address)?;
+            }
+            _ => {
+                return Err(ContractError::InvalidCondition {
+                    reason: "only a bank send of exactly the attached funds is allowed".to_string(),
+                })
+            }
+        }
+    }
+
+    let pending = PENDING_CONDITION_COUNTS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    if pending >= MAX_PENDING_CONDITIONS_PER_OWNER {
+        return Err(ContractError::TooManyConditions { max: MAX_PENDING_CONDITIONS_PER_OWNER });
+    }
+    PENDING_CONDITION_COUNTS.save(deps.storage, &info.sender, &(pending + 1))?;
+
+    let id = NEXT_CONDITION_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_CONDITION_ID.save(deps.storage, &(id + 1))?;
+
+    CONDITIONS.save(deps.storage, id, &Condition {
+        owner: info.sender.clone(),
+        base: msg.base,
+        quote: msg.quote,
+        comparison: msg.comparison,
+        threshold: msg.threshold,
+        msg: msg.msg,
+        escrow: info.funds,
+        status: ConditionStatus::Pending,
+    })?;
+    PENDING_CONDITIONS.save(deps.storage, id, &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("method", "add_condition")
+        .add_attribute("condition_id", id.to_string())
+        .add_attribute("owner", info.sender))
+}
+
+/// Refunds the escrow of a pending condition. Owner or admin only.
+pub fn cancel_condition(deps: DepsMut, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    let mut condition = CONDITIONS.may_load(deps.storage, id)?.ok_or(ContractError::NoSuchCondition { id })?;
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != condition.owner && info.sender != config.admin {
+        return Err(ContractError::Unauthorized);
+    }
+    if condition.status != ConditionStatus::Pending {
+        return Err(ContractError::ConditionNotPending { id });
+    }
+
+    condition.status = ConditionStatus::Cancelled;
+    CONDITIONS.save(deps.storage, id, &condition)?;
+    remove_pending(deps.storage, id, &condition.owner)?;
+
+    let mut res = Response::new()
+        .add_attribute("method", "cancel_condition")
+        .add_attribute("condition_id", id.to_string());
+    if !condition.escrow.is_empty() {
+        res = res.add_message(BankMsg::Send { to_address: condition.owner.into_string(), amount: condition.escrow });
+    }
+    Ok(res)
+}
+
+fn remove_pending(storage: &mut dyn Storage, id: u64, owner: &Addr) -> StdResult<()> {
+    PENDING_CONDITIONS.remove(storage, id);
+    let pending = PENDING_CONDITION_COUNTS.may_load(storage, owner)?.unwrap_or_default();
+    match pending.saturating_sub(1) {
+        0 => PENDING_CONDITION_COUNTS.remove(storage, owner),
+        pending => PENDING_CONDITION_COUNTS.save(storage, owner, &pending)?,
+    }
+    Ok(())
+}
+
+/// Executes the pending conditions whose price has crossed their threshold,
+/// looking at up to `CONDITIONS_PER_EVALUATION` of them after the ones the
+/// previous call stopped at. Conditions without a price recent enough for
+/// GetPrice are left pending.
+pub fn evaluate_conditions(storage: &mut dyn Storage, env: &Env) -> StdResult<(Vec<Event>, Vec<SubMsg>)> {
+    let config = CONFIG.load(storage)?;
+    let now = env.block.time;
+    let cursor = CONDITION_CURSOR.may_load(storage)?;
+    let mut ids: Vec<u64> = PENDING_CONDITIONS
+        .keys(storage, cursor.map(Bound::exclusive), None, Order::Ascending)
+        .take(CONDITIONS_PER_EVALUATION)
+        .collect::<StdResult<_>>()?;
+    if ids.len() < CONDITIONS_PER_EVALUATION {
+        // wrap around to the conditions up to the cursor
+        let wrapped: Vec<u64> = PENDING_CONDITIONS
+            .keys(storage, None, cursor.map(Bound::inclusive), Order::Ascending)
+            .take(CONDITIONS_PER_EVALUATION - ids.len())
+            .collect::<StdResult<_>>()?;
+        ids.extend(wrapped);
+    }
+    if let Some(last) = ids.last() {
+        CONDITION_CURSOR.save(storage, last)?;
+    }
+
+    let mut events = vec![];
+    let mut msgs = vec![];
+    for id in ids {
+        let mut condition = CONDITIONS.load(storage, id)?;
+        let Ok(derived) = derived_price(storage, &condition.base, &condition.quote, now) else {
+            continue;
+        };
+        if derived.oldest_publish_time.plus_seconds(config.max_price_age_seconds) < now {
+            continue;
+        }
+        let met = match condition.comparison {
+            Comparison::Below => derived.price < condition.threshold,
+            Comparison::Above => derived.price > condition.threshold,
+        };
+        if !met {
+            continue;
+        }
+
+        condition.status = ConditionStatus::Executed { price: derived.price, time: now };
+        CONDITIONS.save(storage, id, &condition)?;
+        remove_pending(storage, id, &condition.owner)?;
+
+        msgs.push(SubMsg::reply_on_error(condition.msg, CONDITION_REPLY_ID).with_payload(to_json_binary(&id)?));
+        events.push(
+            Event::new("condition_executed")
+                .add_attribute("condition_id", id.to_string())
+                .add_attribute("base", condition.base)
+                .add_attribute("quote", condition.quote)
+                .add_attribute("price", derived.price.to_string())
+                .add_attribute("threshold", condition.threshold.to_string()),
+        );
+    }
+    Ok((events, msgs))
+}
+
+/// Only failed messages are replied to. The failure is recorded on the
+/// condition and its escrow refunded, the ack itself still succeeds.
+pub fn handle_condition_reply(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
+    let id: u64 = from_json(&msg.payload)?;
+    let error = match msg.result {
+        SubMsgResult::Err(error) => error,
+        SubMsgResult::Ok(_) => return Ok(Response::new()),
+    };
+
+    let mut condition = CONDITIONS.load(deps.storage, id)?;
+    let ConditionStatus::Executed { price, time } = condition.status else {
+        return Err(ContractError::ConditionNotPending { id });
+    };
+    condition.status = ConditionStatus::Failed { price, time, error: error.clone() };
+    CONDITIONS.save(deps.storage, id, &condition)?;
+
+    let mut res = Response::new().add_event(
+        Event::new("condition_failed")
+            .add_attribute("condition_id", id.to_string())
+            .add_attribute("error", error),
+    );
+    if !condition.escrow.is_empty() {
+        res = res.add_message(BankMsg::Send { to_address: condition.owner.into_string(), amount: condition.escrow });
+    }
+    Ok(res)
+}
+
+pub fn query_condition(deps: Deps, id: u64) -> Result<Condition, ContractError> {
+    CONDITIONS.may_load(deps.storage, id)?.ok_or(ContractError::NoSuchCondition { id })
+}
+
+pub fn query_conditions(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<(u64, Condition)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    CONDITIONS
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
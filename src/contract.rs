@@ -1,177 +1,696 @@
-use cosmos_sdk_proto::cosmos::bank::v1beta1::QueryBalanceRequest;
+use cosmos_sdk_proto::cosmos::auth::v1beta1::QueryAccountRequest;
+use cosmos_sdk_proto::cosmos::authz::v1beta1::{QueryGranterGrantsRequest, QueryGrantsRequest};
+use cosmos_sdk_proto::cosmos::bank::v1beta1::{QueryBalanceRequest, QueryDenomMetadataRequest, QuerySupplyOfRequest};
+use cosmos_sdk_proto::cosmos::base::query::v1beta1::PageRequest;
 use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::AbciQueryRequest;
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, IbcMsg, MessageInfo, Response, StdResult, to_json_binary};
+use cosmos_sdk_proto::cosmos::feegrant::v1beta1::QueryAllowanceRequest;
+use cosmos_sdk_proto::cosmos::gov::v1::{QueryProposalRequest, QueryProposalsRequest};
+use cosmos_sdk_proto::cosmos::slashing::v1beta1::QuerySigningInfoRequest;
+use cosmos_sdk_proto::cosmos::staking::v1beta1::QueryValidatorRequest;
+use cosmos_sdk_proto::cosmwasm::wasm::v1::{QueryContractInfoRequest, QueryRawContractStateRequest, QuerySmartContractStateRequest};
+use cosmos_sdk_proto::Timestamp as ProtoTimestamp;
+use cosmwasm_std::{Addr, Binary, Deps, DepsMut, Empty, Env, IbcMsg, MessageInfo, Reply, Response, StdResult, Timestamp as BlockTimestamp, to_json_binary};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cw2::set_contract_version;
 use prost::Message;
 
+use crate::batch::{query_batch_result, send_query_batch};
+use crate::conditions::{add_condition, cancel_condition, CONDITION_REPLY_ID, handle_condition_reply, query_condition, query_conditions};
+use crate::contract_watch::{CONTRACT_WATCH_REPLY_ID, handle_contract_callback_reply, query_contract_watch, query_contract_watches, unwatch_contract, watch_contract};
+use crate::denom_trace::{denom_trace, query_denom_traces, resolve_denom, resolve_denom_hash};
 use crate::error::ContractError;
-use crate::msg::{ArithmeticTwapToNowRequest, CosmosQuery, ExecuteMsg, InstantiateMsg, InterchainQueryPacketData, ProtoCoin, QueryBalanceMsg, QueryMsg, QueryTwapMsg, Timestamp};
-use crate::state::{CHANNEL_INFO, ICQ_ERRORS, ICQ_PRICE_RESPONSES, ICQ_RESPONSES, LAST_SEQUENCE_ACKNOWLEDGMENT};
+use crate::msg::{ArithmeticTwapToNowRequest, AUTH_ACCOUNT_QUERY_PATH, AUTHZ_GRANTER_GRANTS_QUERY_PATH, AUTHZ_GRANTS_QUERY_PATH, BALANCE_QUERY_PATH, CosmosQuery, DENOM_HASH_QUERY_PATH, DENOM_METADATA_QUERY_PATH, DENOM_TRACE_QUERY_PATH, ExecuteMsg, FEE_ALLOWANCE_QUERY_PATH, GOV_PROPOSAL_QUERY_PATH, GOV_PROPOSALS_QUERY_PATH, InstantiateMsg, InterchainQueryPacketData, ProtoCoin, QueryBalanceMsg, QueryCodeInfoRequest, QueryDenomHashRequest, QueryDenomTraceRequest, QueryMsg, QueryContractInfoMsg, QueryProvenMsg, QuerySpec, QueryRawMsg, QueryTwapMsg, QueryWasmRawMsg, QueryWasmSmartMsg, SLASHING_SIGNING_INFO_QUERY_PATH, STAKING_VALIDATOR_QUERY_PATH, SudoMsg, SUPPLY_OF_QUERY_PATH, Timestamp, TWAP_QUERY_PATH, TWAP_WINDOW_SECONDS, UpdateConfigMsg, WASM_CODE_INFO_QUERY_PATH, WASM_CONTRACT_INFO_QUERY_PATH, WASM_RAW_QUERY_PATH, WASM_SMART_QUERY_PATH};
+use crate::state::{ALLOWED_QUERY_PATHS, APP_HASHES, CachedResult, CHANNEL_INFO, CHANNEL_PREFIXES, CONFIG, Config, ICQ_ERRORS, ICQ_PRICE_RESPONSES, ICQ_RAW_RESPONSES, ICQ_RESPONSES, LAST_SEQUENCE_ACKNOWLEDGMENT, PENDING_REQUESTS, PendingRequest, PriceBounds, RESPONSE_HEIGHTS};
+use crate::feeds::{query_aggregated_price, query_feeds, remove_feed, set_feed};
+use crate::fees::{collect_fees, debit_credits, deposit, packet_fee, query_credits, query_fee_schedule, set_fee, withdraw};
+use crate::gov::{proposal_status_code, PROPOSALS_PAGE_LIMIT, query_proposal, query_proposals, send_query_proposal, send_query_proposals};
+use crate::grants::{GRANTER_GRANTS_PAGE_LIMIT, query_authz_grants, query_expiring_grants, query_fee_allowance, query_remote_account, send_query_account, send_query_fee_allowance, send_query_grants};
+use crate::host::{allow_host_query_paths, disallow_host_query_paths, query_host_query_paths};
+use crate::pause::{ensure_not_paused, pause, query_paused, unpause};
+use crate::portfolio::{query_balance_delta, query_chain_aliases, query_portfolio, query_portfolio_value, refresh_portfolio, remove_portfolio, set_chain_alias, set_portfolio};
+use crate::price::{derived_price, query_price, query_prices, query_quarantined_prices, release_quarantined_price};
+use crate::proof::{proven_query_path, query_proven_result, query_proven_results, submit_app_hash, verify_proof};
+use crate::rate_limit::{check_rate_limits, consume_rate_limits, query_channel_quota, query_sender_quota, set_channel_rate_limit, set_sender_rate_limit};
+use crate::requests::{fresh_cached_result, join_in_flight, register_request, request_memo, single_request};
+use crate::reconcile::{query_reconciliation, query_reconciliations, reconcile_escrow};
+use crate::schedule::{fund_bounty_pool, poke, query_bounty_pool, query_subscription, query_subscriptions, subscribe, unsubscribe, withdraw_bounty_pool};
+use crate::validation::{validate_denom, validate_remote_address, validate_smart_query, validate_store_name, validate_twap_query};
+use crate::wasm::{query_wasm_result, query_wasm_results};
+use crate::validator_watch::{query_validator_watch, query_validator_watches, unwatch_validator, watch_validator};
+use crate::watch::{add_watch, handle_callback_reply, query_watch, query_watches, remove_watch, WATCH_CALLBACK_REPLY_ID};
 
 const CONTRACT_NAME: &str = "crates.io:cw-ibc-example";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+const DEFAULT_POKE_BATCH_SIZE: u32 = 10;
+const DEFAULT_MAX_PRICE_AGE_SECONDS: u64 = 3600;
+const DEFAULT_GRANT_EXPIRY_WARNING_SECONDS: u64 = 3 * 24 * 3600;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
-    _msg: InstantiateMsg,
+    info: MessageInfo,
+    msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(Response::new().add_attribute("method", "instantiate"))
+
+    let admin = match msg.admin {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => info.sender,
+    };
+    CONFIG.save(deps.storage, &Config {
+        admin: admin.clone(),
+        cache_ttl_seconds: msg.cache_ttl_seconds.unwrap_or_default(),
+        poke_batch_size: msg.poke_batch_size.unwrap_or(DEFAULT_POKE_BATCH_SIZE),
+        poke_bounty: msg.poke_bounty.filter(|bounty| !bounty.amount.is_zero()),
+        fee_denom: msg.fee_denom,
+        refund_on_failure: msg.refund_on_failure.unwrap_or_default(),
+        sender_rate_limit: None,
+        guardian: None,
+        price_bounds: PriceBounds::default(),
+        intermediate_asset: None,
+        light_client: None,
+        quote_denom: None,
+        max_price_age_seconds: DEFAULT_MAX_PRICE_AGE_SECONDS,
+        grant_expiry_warning_seconds: DEFAULT_GRANT_EXPIRY_WARNING_SECONDS,
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("admin", admin))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::SendQueryBalance(msg) => send_query_balance(deps, env, msg),
-        ExecuteMsg::SendQueryTwap(msg) => send_query_twap(deps, env, msg),
+        ExecuteMsg::SendQueryBalance(msg) => send_query_balance(deps, env, info, msg),
+        ExecuteMsg::SendQueryTwap(msg) => send_query_twap(deps, env, info, msg),
+        ExecuteMsg::SendQueryRaw(msg) => send_query_raw(deps, env, info, msg),
+        ExecuteMsg::SendQueryProven(msg) => send_query_proven(deps, env, info, msg),
+        ExecuteMsg::SendQueryWasmSmart(msg) => send_query_wasm_smart(deps, env, info, msg),
+        ExecuteMsg::SendQueryWasmRaw(msg) => send_query_wasm_raw(deps, env, info, msg),
+        ExecuteMsg::SendQueryContractInfo(msg) => send_query_contract_info(deps, env, info, msg),
+        ExecuteMsg::ResolveDenom { channel, denom } => resolve_denom(deps, env, info, channel, denom),
+        ExecuteMsg::ResolveDenomHash { channel, trace } => resolve_denom_hash(deps, env, info, channel, trace),
+        ExecuteMsg::SendQueryProposal { channel, proposal_id } => send_query_proposal(deps, env, info, channel, proposal_id),
+        ExecuteMsg::SendQueryProposals { channel, status } => send_query_proposals(deps, env, info, channel, status),
+        ExecuteMsg::SendQueryAccount { channel, address } => send_query_account(deps, env, info, channel, address),
+        ExecuteMsg::SendQueryGrants { channel, granter, grantee } => send_query_grants(deps, env, info, channel, granter, grantee),
+        ExecuteMsg::SendQueryFeeAllowance { channel, granter, grantee } => send_query_fee_allowance(deps, env, info, channel, granter, grantee),
+        ExecuteMsg::ReconcileEscrow(msg) => reconcile_escrow(deps, env, info, msg),
+        ExecuteMsg::SendQueryBatch(msg) => send_query_batch(deps, env, info, msg),
+        ExecuteMsg::SubmitAppHash { channel, height, app_hash } => submit_app_hash(deps, info, channel, height, app_hash),
+        ExecuteMsg::VerifyProof { sequence } => verify_proof(deps, sequence),
+        ExecuteMsg::UpdateConfig(msg) => update_config(deps, info, msg),
+        ExecuteMsg::SetFee { kind, amount } => set_fee(deps, info, kind, amount),
+        ExecuteMsg::Deposit {} => deposit(deps, info),
+        ExecuteMsg::Withdraw { amount } => withdraw(deps, info, amount),
+        ExecuteMsg::CollectFees { to } => collect_fees(deps, info, to),
+        ExecuteMsg::SetSenderRateLimit { limit } => set_sender_rate_limit(deps, info, limit),
+        ExecuteMsg::SetChannelRateLimit { channel, limit } => set_channel_rate_limit(deps, info, channel, limit),
+        ExecuteMsg::Pause(scope) => pause(deps, info, scope),
+        ExecuteMsg::Unpause(scope) => unpause(deps, info, scope),
+        ExecuteMsg::ReleaseQuarantinedPrice { sequence } => release_quarantined_price(deps, env, info, sequence),
+        ExecuteMsg::SetFeed(feed) => set_feed(deps, info, feed),
+        ExecuteMsg::RemoveFeed { base, quote } => remove_feed(deps, info, base, quote),
+        ExecuteMsg::SetChainAlias { alias, channel } => set_chain_alias(deps, info, alias, channel),
+        ExecuteMsg::SetPortfolio { name, holdings } => set_portfolio(deps, env, info, name, holdings),
+        ExecuteMsg::RemovePortfolio { name } => remove_portfolio(deps, info, name),
+        ExecuteMsg::RefreshPortfolio { name } => refresh_portfolio(deps, env, info, name),
+        ExecuteMsg::AddWatch(msg) => add_watch(deps, env, info, msg),
+        ExecuteMsg::RemoveWatch { id } => remove_watch(deps, info, id),
+        ExecuteMsg::WatchContract(msg) => watch_contract(deps, env, info, msg),
+        ExecuteMsg::UnwatchContract { id } => unwatch_contract(deps, info, id),
+        ExecuteMsg::WatchValidator(msg) => watch_validator(deps, env, info, msg),
+        ExecuteMsg::UnwatchValidator { chain, operator_address } => unwatch_validator(deps, info, chain, operator_address),
+        ExecuteMsg::AddCondition(msg) => add_condition(deps, info, msg),
+        ExecuteMsg::CancelCondition { id } => cancel_condition(deps, info, id),
+        ExecuteMsg::Subscribe { channel, query, interval_seconds } => subscribe(deps, env, info, channel, query, interval_seconds),
+        ExecuteMsg::Unsubscribe { id } => unsubscribe(deps, info, id),
+        ExecuteMsg::Poke {} => poke(deps, env, Some(info.sender)),
+        ExecuteMsg::FundBountyPool {} => fund_bounty_pool(deps, info),
+        ExecuteMsg::WithdrawBountyPool { amount } => withdraw_bounty_pool(deps, info, amount),
+        ExecuteMsg::AllowQueryPaths { channel, paths } => allow_query_paths(deps, info, channel, paths),
+        ExecuteMsg::AllowHostQueryPaths { paths } => allow_host_query_paths(deps, info, paths),
+        ExecuteMsg::DisallowHostQueryPaths { paths } => disallow_host_query_paths(deps, info, paths),
+        ExecuteMsg::DisallowQueryPaths { channel, paths } => disallow_query_paths(deps, info, channel, paths),
+        ExecuteMsg::SetChannelPrefix { channel, prefix } => set_channel_prefix(deps, info, channel, prefix),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::Poke {} | SudoMsg::ClockEndBlock {} => poke(deps, env, None),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        WATCH_CALLBACK_REPLY_ID => handle_callback_reply(msg),
+        CONDITION_REPLY_ID => handle_condition_reply(deps, msg),
+        CONTRACT_WATCH_REPLY_ID => handle_contract_callback_reply(msg),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+pub fn ensure_admin(deps: Deps, info: &MessageInfo) -> Result<(), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized);
     }
+    Ok(())
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    msg: UpdateConfigMsg,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    if let Some(admin) = msg.admin {
+        config.admin = deps.api.addr_validate(&admin)?;
+    }
+    if let Some(cache_ttl_seconds) = msg.cache_ttl_seconds {
+        config.cache_ttl_seconds = cache_ttl_seconds;
+    }
+    if let Some(poke_batch_size) = msg.poke_batch_size {
+        config.poke_batch_size = poke_batch_size;
+    }
+    if let Some(poke_bounty) = msg.poke_bounty {
+        config.poke_bounty = Some(poke_bounty).filter(|bounty| !bounty.amount.is_zero());
+    }
+    if let Some(fee_denom) = msg.fee_denom {
+        // credits and collected fees are amounts of the current fee denom
+        if let Some(current) = config.fee_denom.as_ref().filter(|current| **current != fee_denom) {
+            return Err(ContractError::FeeDenomAlreadySet { denom: current.clone() });
+        }
+        config.fee_denom = Some(fee_denom);
+    }
+    if let Some(refund_on_failure) = msg.refund_on_failure {
+        config.refund_on_failure = refund_on_failure;
+    }
+    if let Some(guardian) = msg.guardian {
+        config.guardian = Some(deps.api.addr_validate(&guardian)?);
+    }
+    if let Some(price_bounds) = msg.price_bounds {
+        config.price_bounds = price_bounds;
+    }
+    if let Some(intermediate_asset) = msg.intermediate_asset {
+        validate_denom(&intermediate_asset)?;
+        config.intermediate_asset = Some(intermediate_asset);
+    }
+    if let Some(light_client) = msg.light_client {
+        config.light_client = Some(deps.api.addr_validate(&light_client)?);
+    }
+    if let Some(quote_denom) = msg.quote_denom {
+        validate_denom(&quote_denom)?;
+        config.quote_denom = Some(quote_denom);
+    }
+    if let Some(max_price_age_seconds) = msg.max_price_age_seconds {
+        config.max_price_age_seconds = max_price_age_seconds;
+    }
+    if let Some(grant_expiry_warning_seconds) = msg.grant_expiry_warning_seconds {
+        config.grant_expiry_warning_seconds = grant_expiry_warning_seconds;
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("method", "update_config"))
+}
+
+pub fn set_channel_prefix(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel: String,
+    prefix: Option<String>,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+
+    match &prefix {
+        Some(prefix) => {
+            if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()) {
+                return Err(ContractError::InvalidPrefix { prefix: prefix.clone() });
+            }
+            CHANNEL_PREFIXES.save(deps.storage, &channel, prefix)?;
+        }
+        None => CHANNEL_PREFIXES.remove(deps.storage, &channel),
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "set_channel_prefix")
+        .add_attribute("channel", channel)
+        .add_attribute("prefix", prefix.unwrap_or_default()))
 }
 
 pub fn send_query_balance(
     deps: DepsMut,
     env: Env,
+    info: MessageInfo,
     msg: QueryBalanceMsg,
 ) -> Result<Response, ContractError> {
-    // ensure the requested channel is registered
-    if !CHANNEL_INFO.has(deps.storage, &msg.channel) {
-        return Err(ContractError::NoSuchChannel { id: msg.channel });
-    }
+    let spec = QuerySpec::Balance { address: msg.address, denom: msg.denom };
+    let mut req = build_query_request(deps.as_ref(), &env, &msg.channel, &spec)?;
+    req.height = pinned_height(msg.height)?;
+    send_icq_packet(deps, env, info.sender, msg.channel, vec![req], "send_query_balance")
+}
 
-    let query_balance_request: QueryBalanceRequest = QueryBalanceRequest {
-        address: msg.address,
-        denom: msg.denom,
-    };
+pub fn send_query_twap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: QueryTwapMsg,
+) -> Result<Response, ContractError> {
+    let spec = QuerySpec::Twap { pool_id: msg.pool_id, base_asset: msg.base_asset, quote_asset: msg.quote_asset };
+    let mut req = build_query_request(deps.as_ref(), &env, &msg.channel, &spec)?;
+    req.height = pinned_height(msg.height)?;
+    send_icq_packet(deps, env, info.sender, msg.channel, vec![req], "send_query_twap")
+}
 
-    let req: AbciQueryRequest = AbciQueryRequest {
-        data: query_balance_request.encode_to_vec(),
-        path: "/cosmos.bank.v1beta1.Query/Balance".to_string(),
-        height: 0,
-        prove: false,
-    };
+pub fn send_query_raw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: QueryRawMsg,
+) -> Result<Response, ContractError> {
+    let spec = QuerySpec::Raw { path: msg.path, data: msg.data };
+    let mut req = build_query_request(deps.as_ref(), &env, &msg.channel, &spec)?;
+    req.height = pinned_height(msg.height)?;
+    send_icq_packet(deps, env, info.sender, msg.channel, vec![req], "send_query_raw")
+}
 
-    let cosmos_query: CosmosQuery = CosmosQuery {
-        requests: vec![req]
-    };
+pub fn send_query_proven(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: QueryProvenMsg,
+) -> Result<Response, ContractError> {
+    let spec = QuerySpec::Proven { store: msg.store, key: msg.key };
+    let mut req = build_query_request(deps.as_ref(), &env, &msg.channel, &spec)?;
+    req.height = pinned_height(msg.height)?;
+    send_icq_packet(deps, env, info.sender, msg.channel, vec![req], "send_query_proven")
+}
 
-    let packet_data: InterchainQueryPacketData = InterchainQueryPacketData {
-        data: cosmos_query.encode_to_vec(),
-        memo: "test icq request".to_string(),
-    };
+pub fn send_query_wasm_smart(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: QueryWasmSmartMsg,
+) -> Result<Response, ContractError> {
+    let spec = QuerySpec::WasmSmart { contract: msg.contract, msg: msg.msg };
+    let mut req = build_query_request(deps.as_ref(), &env, &msg.channel, &spec)?;
+    req.height = pinned_height(msg.height)?;
+    send_icq_packet(deps, env, info.sender, msg.channel, vec![req], "send_query_wasm_smart")
+}
 
-    // timeout is in nanoseconds
-    let timeout = env.block.time.plus_seconds(120);
+pub fn send_query_wasm_raw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: QueryWasmRawMsg,
+) -> Result<Response, ContractError> {
+    let spec = QuerySpec::WasmRaw { contract: msg.contract, key: msg.key };
+    let mut req = build_query_request(deps.as_ref(), &env, &msg.channel, &spec)?;
+    req.height = pinned_height(msg.height)?;
+    send_icq_packet(deps, env, info.sender, msg.channel, vec![req], "send_query_wasm_raw")
+}
 
-    // Borrow `msg.channel` as a reference
-    let channel_id = &msg.channel;
+pub fn send_query_contract_info(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: QueryContractInfoMsg,
+) -> Result<Response, ContractError> {
+    let spec = QuerySpec::WasmContractInfo { contract: msg.contract };
+    let mut req = build_query_request(deps.as_ref(), &env, &msg.channel, &spec)?;
+    req.height = pinned_height(msg.height)?;
+    send_icq_packet(deps, env, info.sender, msg.channel, vec![req], "send_query_contract_info")
+}
 
-    // prepare ibc message
-    let ibc_msg = IbcMsg::SendPacket {
-        channel_id: channel_id.clone(),
-        data: to_json_binary(&packet_data)?,
-        timeout: timeout.into(),
-    };
+/// Height to set on a request, 0 meaning the latest height of the host.
+pub fn pinned_height(height: Option<u64>) -> Result<i64, ContractError> {
+    let height = height.unwrap_or_default();
+    i64::try_from(height).map_err(|_| ContractError::InvalidHeight { height })
+}
 
-    // Get the current sequence number from storage
-    // let sequence = get_next_sequence_send(&mut deps)?;
+/// A timestamp answered by the host, times before the epoch read as the epoch.
+/// `None` for a time too far ahead to hold in nanoseconds, such as the end of
+/// a tombstoned validator's jail or a grant that never expires.
+pub fn block_time(time: &ProtoTimestamp) -> Option<BlockTimestamp> {
+    let nanos = (time.seconds.max(0) as u64).checked_mul(1_000_000_000)?.checked_add(time.nanos.max(0) as u64)?;
+    Some(BlockTimestamp::from_nanos(nanos))
+}
 
-    // save_icq_request(deps, sequence, &query_balance_request);
-    // ICQ_REQUESTS.save(deps.storage, sequence, &query_balance_request)?;
+/// Validates `spec` for `channel` and encodes it as an ABCI query request.
+pub fn build_query_request(
+    deps: Deps,
+    env: &Env,
+    channel: &str,
+    spec: &QuerySpec,
+) -> Result<AbciQueryRequest, ContractError> {
+    let (path, data) = match spec {
+        QuerySpec::Balance { address, denom } => {
+            let prefix = CHANNEL_PREFIXES.may_load(deps.storage, channel)?;
+            validate_remote_address(address, prefix.as_deref())?;
+            validate_denom(denom)?;
+
+            let query_balance_request: QueryBalanceRequest = QueryBalanceRequest {
+                address: address.clone(),
+                denom: denom.clone(),
+            };
+            (BALANCE_QUERY_PATH.to_string(), query_balance_request.encode_to_vec())
+        }
+        QuerySpec::Twap { pool_id, base_asset, quote_asset } => {
+            validate_twap_query(*pool_id, base_asset, quote_asset)?;
+
+            let block_time = env.block.time;  // Get the current block time
+
+            // Subtract the TWAP window from the block time, aligned to the minute so
+            // that identical TWAP queries within the same minute share their request bytes
+            let new_time = (block_time.seconds() - TWAP_WINDOW_SECONDS) / 60 * 60;
+
+            // Create the prost::Timestamp struct
+            let timestamp = Timestamp {
+                seconds: new_time as i64,
+                nanos: 0,
+            };
+
+            let query_twap_request: ArithmeticTwapToNowRequest = ArithmeticTwapToNowRequest {
+                pool_id: *pool_id,
+                base_asset: base_asset.clone(),
+                quote_asset: quote_asset.clone(),
+                start_time: Some(timestamp),
+            };
+            (TWAP_QUERY_PATH.to_string(), query_twap_request.encode_to_vec())
+        }
+        QuerySpec::DenomMetadata { denom } => {
+            validate_denom(denom)?;
 
+            let query_denom_metadata_request = QueryDenomMetadataRequest { denom: denom.clone() };
+            (DENOM_METADATA_QUERY_PATH.to_string(), query_denom_metadata_request.encode_to_vec())
+        }
+        QuerySpec::SupplyOf { denom } => {
+            validate_denom(denom)?;
 
-    Ok(Response::new()
-        .add_attribute("method", "send_query_balance")
-        .add_attribute("channel", channel_id)
-        // .add_attribute("sequence", sequence.to_string())
-        // outbound IBC message, where packet is then received on other chain
-        .add_message(ibc_msg))
+            let query_supply_of_request = QuerySupplyOfRequest { denom: denom.clone() };
+            (SUPPLY_OF_QUERY_PATH.to_string(), query_supply_of_request.encode_to_vec())
+        }
+        QuerySpec::WasmSmart { contract, msg } => {
+            let prefix = CHANNEL_PREFIXES.may_load(deps.storage, channel)?;
+            validate_remote_address(contract, prefix.as_deref())?;
+            validate_smart_query(msg)?;
+
+            let query_smart_request = QuerySmartContractStateRequest { address: contract.clone(), query_data: msg.to_vec() };
+            (WASM_SMART_QUERY_PATH.to_string(), query_smart_request.encode_to_vec())
+        }
+        QuerySpec::WasmRaw { contract, key } => {
+            let prefix = CHANNEL_PREFIXES.may_load(deps.storage, channel)?;
+            validate_remote_address(contract, prefix.as_deref())?;
+
+            let query_raw_request = QueryRawContractStateRequest { address: contract.clone(), query_data: key.to_vec() };
+            (WASM_RAW_QUERY_PATH.to_string(), query_raw_request.encode_to_vec())
+        }
+        QuerySpec::WasmContractInfo { contract } => {
+            let prefix = CHANNEL_PREFIXES.may_load(deps.storage, channel)?;
+            validate_remote_address(contract, prefix.as_deref())?;
+
+            let query_contract_info_request = QueryContractInfoRequest { address: contract.clone() };
+            (WASM_CONTRACT_INFO_QUERY_PATH.to_string(), query_contract_info_request.encode_to_vec())
+        }
+        QuerySpec::WasmCodeInfo { code_id } => {
+            let query_code_info_request = QueryCodeInfoRequest { code_id: *code_id };
+            (WASM_CODE_INFO_QUERY_PATH.to_string(), query_code_info_request.encode_to_vec())
+        }
+        QuerySpec::DenomTrace { denom } => {
+            validate_denom(denom)?;
+            let hash = denom.strip_prefix("ibc/").ok_or(ContractError::InvalidDenom {
+                denom: denom.clone(),
+                reason: "only ibc/ denoms have a trace".to_string(),
+            })?;
+
+            let query_denom_trace_request = QueryDenomTraceRequest { hash: hash.to_string() };
+            (DENOM_TRACE_QUERY_PATH.to_string(), query_denom_trace_request.encode_to_vec())
+        }
+        QuerySpec::DenomHash { trace } => {
+            validate_denom(trace)?;
+
+            let query_denom_hash_request = QueryDenomHashRequest { trace: trace.clone() };
+            (DENOM_HASH_QUERY_PATH.to_string(), query_denom_hash_request.encode_to_vec())
+        }
+        QuerySpec::Account { address } => {
+            let prefix = CHANNEL_PREFIXES.may_load(deps.storage, channel)?;
+            validate_remote_address(address, prefix.as_deref())?;
+
+            let query_account_request = QueryAccountRequest { address: address.clone() };
+            (AUTH_ACCOUNT_QUERY_PATH.to_string(), query_account_request.encode_to_vec())
+        }
+        QuerySpec::AuthzGrants { granter, grantee } => {
+            let prefix = CHANNEL_PREFIXES.may_load(deps.storage, channel)?;
+            validate_remote_address(granter, prefix.as_deref())?;
+            validate_remote_address(grantee, prefix.as_deref())?;
+
+            let query_grants_request = QueryGrantsRequest {
+                granter: granter.clone(),
+                grantee: grantee.clone(),
+                msg_type_url: String::new(),
+                pagination: None,
+            };
+            (AUTHZ_GRANTS_QUERY_PATH.to_string(), query_grants_request.encode_to_vec())
+        }
+        QuerySpec::AuthzGranterGrants { granter } => {
+            let prefix = CHANNEL_PREFIXES.may_load(deps.storage, channel)?;
+            validate_remote_address(granter, prefix.as_deref())?;
+
+            let query_granter_grants_request = QueryGranterGrantsRequest {
+                granter: granter.clone(),
+                pagination: Some(PageRequest {
+                    key: vec![],
+                    offset: 0,
+                    limit: GRANTER_GRANTS_PAGE_LIMIT,
+                    count_total: false,
+                    reverse: false,
+                }),
+            };
+            (AUTHZ_GRANTER_GRANTS_QUERY_PATH.to_string(), query_granter_grants_request.encode_to_vec())
+        }
+        QuerySpec::FeeAllowance { granter, grantee } => {
+            let prefix = CHANNEL_PREFIXES.may_load(deps.storage, channel)?;
+            validate_remote_address(granter, prefix.as_deref())?;
+            validate_remote_address(grantee, prefix.as_deref())?;
+
+            let query_allowance_request = QueryAllowanceRequest { granter: granter.clone(), grantee: grantee.clone() };
+            (FEE_ALLOWANCE_QUERY_PATH.to_string(), query_allowance_request.encode_to_vec())
+        }
+        QuerySpec::StakingValidator { operator_address } => {
+            let prefix = CHANNEL_PREFIXES.may_load(deps.storage, channel)?;
+            validate_remote_address(operator_address, prefix.map(|prefix| format!("{prefix}valoper")).as_deref())?;
+
+            let query_validator_request = QueryValidatorRequest { validator_addr: operator_address.clone() };
+            (STAKING_VALIDATOR_QUERY_PATH.to_string(), query_validator_request.encode_to_vec())
+        }
+        QuerySpec::SigningInfo { consensus_address } => {
+            let prefix = CHANNEL_PREFIXES.may_load(deps.storage, channel)?;
+            validate_remote_address(consensus_address, prefix.map(|prefix| format!("{prefix}valcons")).as_deref())?;
+
+            let query_signing_info_request = QuerySigningInfoRequest { cons_address: consensus_address.clone() };
+            (SLASHING_SIGNING_INFO_QUERY_PATH.to_string(), query_signing_info_request.encode_to_vec())
+        }
+        QuerySpec::GovProposal { proposal_id } => {
+            let query_proposal_request = QueryProposalRequest { proposal_id: *proposal_id };
+            (GOV_PROPOSAL_QUERY_PATH.to_string(), query_proposal_request.encode_to_vec())
+        }
+        QuerySpec::GovProposals { status } => {
+            let query_proposals_request = QueryProposalsRequest {
+                proposal_status: status.map(proposal_status_code).unwrap_or_default(),
+                voter: String::new(),
+                depositor: String::new(),
+                pagination: Some(PageRequest {
+                    key: vec![],
+                    offset: 0,
+                    limit: PROPOSALS_PAGE_LIMIT,
+                    count_total: false,
+                    reverse: true,
+                }),
+            };
+            (GOV_PROPOSALS_QUERY_PATH.to_string(), query_proposals_request.encode_to_vec())
+        }
+        QuerySpec::Proven { store, key } => {
+            validate_store_name(store)?;
+            (proven_query_path(store), key.to_vec())
+        }
+        QuerySpec::Raw { path, data } => {
+            // a TWAP is only taken as a price over the window built above
+            if path == TWAP_QUERY_PATH {
+                return Err(ContractError::RawQueryPathNotAllowed { path: path.clone() });
+            }
+            (path.clone(), data.to_vec())
+        }
+    };
+
+    Ok(AbciQueryRequest {
+        data,
+        path,
+        height: 0,
+        prove: matches!(spec, QuerySpec::Proven { .. }),
+    })
 }
 
-pub fn send_query_twap(
+/// What became of a query handed to `prepare_icq_packet`.
+pub enum PacketOutcome {
+    /// answered from the result cache, no packet needed
+    Cached(CachedResult),
+    /// joined an identical request that is already in flight
+    Coalesced { request_id: u64 },
+    /// a new packet has to be sent with `msg`
+    Sent { request_id: u64, msg: IbcMsg },
+}
+
+/// Wraps the requests into a single ICQ packet on `channel`. Every send path
+/// goes through here so the channel and allowlist checks happen before any
+/// packet is emitted. A single request is answered from the result cache while
+/// fresh, or joins an identical request that is still in flight.
+pub fn prepare_icq_packet(
     deps: DepsMut,
-    env: Env,
-    msg: QueryTwapMsg,
-) -> Result<Response, ContractError> {
+    env: &Env,
+    sender: &Addr,
+    channel: &str,
+    requests: Vec<AbciQueryRequest>,
+) -> Result<PacketOutcome, ContractError> {
     // ensure the requested channel is registered
-    if !CHANNEL_INFO.has(deps.storage, &msg.channel) {
-        return Err(ContractError::NoSuchChannel { id: msg.channel });
+    if !CHANNEL_INFO.has(deps.storage, channel) {
+        return Err(ContractError::NoSuchChannel { id: channel.to_string() });
     }
 
-    let block_time = env.block.time;  // Get the current block time
-    let four_hours = 4 * 3600;        // 4 hours in seconds
+    for req in &requests {
+        if !ALLOWED_QUERY_PATHS.has(deps.storage, (channel, &req.path)) {
+            return Err(ContractError::QueryPathNotAllowed { channel: channel.to_string(), path: req.path.clone() });
+        }
+    }
 
-    // Subtract 4 hours from the block time
-    let new_time = block_time.seconds() - four_hours;
+    ensure_not_paused(deps.storage, channel, &requests)?;
 
-    // Create the prost::Timestamp struct
-    let timestamp = Timestamp {
-        seconds: new_time as i64,
-        nanos: 0,
-    };
+    if let Some(req) = single_request(&requests) {
+        let config = CONFIG.load(deps.storage)?;
+        if let Some(cached) = fresh_cached_result(deps.storage, channel, req, env.block.time, config.cache_ttl_seconds)? {
+            return Ok(PacketOutcome::Cached(cached));
+        }
 
-    let query_twap_request: ArithmeticTwapToNowRequest = ArithmeticTwapToNowRequest {
-        pool_id: msg.pool_id,
-        base_asset: msg.base_asset,
-        quote_asset: msg.quote_asset,
-        start_time: Some(timestamp),
-    };
+        if let Some(request_id) = join_in_flight(deps.storage, channel, req, sender)? {
+            return Ok(PacketOutcome::Coalesced { request_id });
+        }
+    }
 
-    let req: AbciQueryRequest = AbciQueryRequest {
-        data: query_twap_request.encode_to_vec(),
-        path: "/osmosis.twap.v1beta1.Query/ArithmeticTwapToNow".to_string(),
-        height: 0,
-        prove: false,
-    };
+    // `deps.branch()` does not roll anything back, so every check that can
+    // reject the send comes before the first write: callers that carry on
+    // after a rejected send, like `poke`, find storage as it was. Only storage
+    // or serialization errors can follow a write.
+    let rate_limits = check_rate_limits(deps.storage, sender, channel, env.block.height)?;
 
-    let cosmos_query: CosmosQuery = CosmosQuery {
-        requests: vec![req]
-    };
+    // the sender pays for every packet that actually goes out
+    let fee = packet_fee(deps.storage, &requests)?;
+    debit_credits(deps.storage, sender, fee)?;
+    consume_rate_limits(deps.storage, sender, channel, rate_limits)?;
+
+    let request_id = register_request(deps.storage, channel, &requests, sender, fee, env.block.time)?;
+
+    let cosmos_query: CosmosQuery = CosmosQuery { requests };
 
     let packet_data: InterchainQueryPacketData = InterchainQueryPacketData {
         data: cosmos_query.encode_to_vec(),
-        memo: "test icq request".to_string(),
+        memo: request_memo(request_id),
     };
 
     // timeout is in nanoseconds
     let timeout = env.block.time.plus_seconds(120);
 
-    // Borrow `msg.channel` as a reference
-    let channel_id = &msg.channel;
-
     // prepare ibc message
-    let ibc_msg = IbcMsg::SendPacket {
-        channel_id: channel_id.clone(),
+    let msg = IbcMsg::SendPacket {
+        channel_id: channel.to_string(),
         data: to_json_binary(&packet_data)?,
         timeout: timeout.into(),
     };
 
-    // Get the current sequence number from storage
-    // let sequence = get_next_sequence_send(&mut deps)?;
+    Ok(PacketOutcome::Sent { request_id, msg })
+}
+
+pub fn send_icq_packet(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    channel: String,
+    requests: Vec<AbciQueryRequest>,
+    method: &str,
+) -> Result<Response, ContractError> {
+    let res = Response::new()
+        .add_attribute("method", method)
+        .add_attribute("channel", &channel);
+
+    match prepare_icq_packet(deps, &env, &sender, &channel, requests)? {
+        PacketOutcome::Cached(cached) => Ok(res
+            .add_attribute("cache_hit", "true")
+            .add_attribute("cached_at", cached.time.to_string())
+            .set_data(cached.value)),
+        PacketOutcome::Coalesced { request_id } => Ok(res
+            .add_attribute("coalesced", "true")
+            .add_attribute("request_id", request_id.to_string())),
+        PacketOutcome::Sent { request_id, msg } => Ok(res
+            .add_attribute("request_id", request_id.to_string())
+            // outbound IBC message, where packet is then received on other chain
+            .add_message(msg)),
+    }
+}
+
+pub fn allow_query_paths(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel: String,
+    paths: Vec<String>,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+
+    for path in &paths {
+        if !path.starts_with('/') {
+            return Err(ContractError::InvalidQueryPath { path: path.clone() });
+        }
+        ALLOWED_QUERY_PATHS.save(deps.storage, (&channel, path), &Empty {})?;
+    }
 
-    // save_icq_request(deps, sequence, &query_balance_request);
-    // ICQ_REQUESTS.save(deps.storage, sequence, &query_balance_request)?;
+    Ok(Response::new()
+        .add_attribute("method", "allow_query_paths")
+        .add_attribute("channel", channel)
+        .add_attribute("paths", paths.join(",")))
+}
 
+pub fn disallow_query_paths(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel: String,
+    paths: Vec<String>,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+
+    for path in &paths {
+        ALLOWED_QUERY_PATHS.remove(deps.storage, (&channel, path));
+    }
 
     Ok(Response::new()
-        .add_attribute("method", "send_query_balance")
-        .add_attribute("channel", channel_id)
-        // .add_attribute("sequence", sequence.to_string())
-        // outbound IBC message, where packet is then received on other chain
-        .add_message(ibc_msg))
+        .add_attribute("method", "disallow_query_paths")
+        .add_attribute("channel", channel)
+        .add_attribute("paths", paths.join(",")))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    let binary = match msg {
         QueryMsg::AllBalances {} => to_json_binary(&query_all_balances(deps)?),
         QueryMsg::AllPriceFeeds {} => to_json_binary(&query_all_price_feed(deps)?),
         QueryMsg::AllErrors {} => to_json_binary(&query_all_errors(deps)?),
@@ -179,7 +698,57 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             let result = LAST_SEQUENCE_ACKNOWLEDGMENT.load(deps.storage)?;
             to_json_binary(&result)
         }
-    }
+        QueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::ChannelPrefix { channel } => to_json_binary(&CHANNEL_PREFIXES.may_load(deps.storage, &channel)?),
+        QueryMsg::AllowedQueryPaths { channel } => to_json_binary(&query_allowed_query_paths(deps, channel)?),
+        QueryMsg::HostQueryPaths {} => to_json_binary(&query_host_query_paths(deps)?),
+        QueryMsg::AllRawResponses {} => to_json_binary(&query_all_raw_responses(deps)?),
+        QueryMsg::PendingRequests {} => to_json_binary(&query_pending_requests(deps)?),
+        QueryMsg::Subscription { id } => to_json_binary(&query_subscription(deps, id)?),
+        QueryMsg::Subscriptions { start_after, limit } => to_json_binary(&query_subscriptions(deps, start_after, limit)?),
+        QueryMsg::BountyPool {} => to_json_binary(&query_bounty_pool(deps)?),
+        QueryMsg::Credits { address } => to_json_binary(&query_credits(deps, address)?),
+        QueryMsg::FeeSchedule {} => to_json_binary(&query_fee_schedule(deps)?),
+        QueryMsg::SenderQuota { sender } => to_json_binary(&query_sender_quota(deps, env, sender)?),
+        QueryMsg::ChannelQuota { channel } => to_json_binary(&query_channel_quota(deps, env, channel)?),
+        QueryMsg::Paused {} => to_json_binary(&query_paused(deps)?),
+        QueryMsg::Prices { base, quote } => to_json_binary(&query_prices(deps, base, quote)?),
+        QueryMsg::QuarantinedPrices { start_after, limit } => to_json_binary(&query_quarantined_prices(deps, start_after, limit)?),
+        QueryMsg::DerivedPrice { base, quote } => to_json_binary(&derived_price(deps.storage, &base, &quote, env.block.time)?),
+        QueryMsg::AggregatedPrice { base, quote } => to_json_binary(&query_aggregated_price(deps, env.block.time, base, quote)?),
+        QueryMsg::Feeds {} => to_json_binary(&query_feeds(deps)?),
+        QueryMsg::GetPrice { denom } => to_json_binary(&query_price(deps, env.block.time, denom)?),
+        QueryMsg::ChainAliases {} => to_json_binary(&query_chain_aliases(deps)?),
+        QueryMsg::Portfolio { name } => to_json_binary(&query_portfolio(deps, name)?),
+        QueryMsg::PortfolioValue { name } => to_json_binary(&query_portfolio_value(deps, env.block.time, name)?),
+        QueryMsg::BalanceDelta { chain, address, denom, from, to } => to_json_binary(&query_balance_delta(deps, chain, address, denom, from, to)?),
+        QueryMsg::ContractWatch { id } => to_json_binary(&query_contract_watch(deps, id)?),
+        QueryMsg::ContractWatches { start_after, limit } => to_json_binary(&query_contract_watches(deps, start_after, limit)?),
+        QueryMsg::ValidatorWatch { chain, operator_address } => to_json_binary(&query_validator_watch(deps, chain, operator_address)?),
+        QueryMsg::ValidatorWatches { chain, start_after, limit } => to_json_binary(&query_validator_watches(deps, chain, start_after, limit)?),
+        QueryMsg::Watch { id } => to_json_binary(&query_watch(deps, id)?),
+        QueryMsg::Watches { start_after, limit } => to_json_binary(&query_watches(deps, start_after, limit)?),
+        QueryMsg::ResponseHeights { sequence } => to_json_binary(&RESPONSE_HEIGHTS.may_load(deps.storage, sequence)?.unwrap_or_default()),
+        QueryMsg::WasmResult { sequence } => to_json_binary(&query_wasm_result(deps, sequence)?),
+        QueryMsg::WasmResults { start_after, limit } => to_json_binary(&query_wasm_results(deps, start_after, limit)?),
+        QueryMsg::DenomTrace { channel, denom } => to_json_binary(&denom_trace(deps.storage, &channel, &denom)?),
+        QueryMsg::DenomTraces { channel } => to_json_binary(&query_denom_traces(deps, channel)?),
+        QueryMsg::Proposal { chain, proposal_id } => to_json_binary(&query_proposal(deps, chain, proposal_id)?),
+        QueryMsg::Proposals { chain, status, start_after, limit } => to_json_binary(&query_proposals(deps, chain, status, start_after, limit)?),
+        QueryMsg::RemoteAccount { chain, address } => to_json_binary(&query_remote_account(deps, chain, address)?),
+        QueryMsg::AuthzGrants { chain, granter, grantee } => to_json_binary(&query_authz_grants(deps, chain, granter, grantee)?),
+        QueryMsg::FeeAllowance { chain, granter, grantee } => to_json_binary(&query_fee_allowance(deps, chain, granter, grantee)?),
+        QueryMsg::ExpiringGrants { chain, within_seconds } => to_json_binary(&query_expiring_grants(deps, env, chain, within_seconds)?),
+        QueryMsg::Reconciliation { channel, voucher_denom } => to_json_binary(&query_reconciliation(deps, channel, voucher_denom)?),
+        QueryMsg::Reconciliations {} => to_json_binary(&query_reconciliations(deps)?),
+        QueryMsg::BatchResult { sequence } => to_json_binary(&query_batch_result(deps, sequence)?),
+        QueryMsg::ProvenResult { sequence } => to_json_binary(&query_proven_result(deps, sequence)?),
+        QueryMsg::ProvenResults { start_after, limit } => to_json_binary(&query_proven_results(deps, start_after, limit)?),
+        QueryMsg::AppHash { channel, height } => to_json_binary(&APP_HASHES.may_load(deps.storage, (&channel, height))?),
+        QueryMsg::Condition { id } => to_json_binary(&query_condition(deps, id)?),
+        QueryMsg::Conditions { start_after, limit } => to_json_binary(&query_conditions(deps, start_after, limit)?),
+    };
+    Ok(binary?)
 }
 
 fn query_all_balances(deps: Deps) -> StdResult<Vec<(u64, ProtoCoin)>> {
@@ -207,4 +776,22 @@ fn query_all_errors(deps: Deps) -> StdResult<Vec<(u64, String)>> {
 
     // Convert the result to binary
     balances
-}
\ No newline at end of file
+}
+fn query_allowed_query_paths(deps: Deps, channel: String) -> StdResult<Vec<String>> {
+    ALLOWED_QUERY_PATHS
+        .prefix(&channel)
+        .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect()
+}
+
+fn query_all_raw_responses(deps: Deps) -> StdResult<Vec<(u64, Binary)>> {
+    ICQ_RAW_RESPONSES
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect()
+}
+
+fn query_pending_requests(deps: Deps) -> StdResult<Vec<(u64, PendingRequest)>> {
+    PENDING_REQUESTS
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect()
+}
@@ -0,0 +1,266 @@
+use cosmwasm_std::{Deps, DepsMut, Env, Event, from_json, HexBinary, MessageInfo, Order, Reply, Response, StdResult, Storage, SubMsg, SubMsgResult, to_json_binary, WasmMsg};
+use cw_storage_plus::Bound;
+use prost::Message;
+
+use crate::contract::{build_query_request, ensure_admin, PacketOutcome, prepare_icq_packet};
+use crate::error::ContractError;
+use crate::msg::{QueryCodeInfoResponse, QuerySpec, WatchContractMsg};
+use crate::portfolio::resolve_chain;
+use crate::schedule::{add_subscription, remove_subscription};
+use crate::state::{CONTRACT_WATCHES, CONTRACT_WATCHES_BY_ADDRESS, CONTRACT_WATCHES_BY_CODE, ContractWatch, NEXT_CONTRACT_WATCH_ID, RemoteContractInfo, SUBSCRIPTIONS};
+
+pub const CONTRACT_WATCH_REPLY_ID: u64 = 3;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn watch_contract(deps: DepsMut, env: Env, info: MessageInfo, msg: WatchContractMsg) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+    let WatchContractMsg { chain, contract, interval_seconds, callback } = msg;
+
+    if interval_seconds == 0 {
+        return Err(ContractError::InvalidInterval {});
+    }
+    let channel = resolve_chain(deps.storage, &chain)?;
+    let spec = QuerySpec::WasmContractInfo { contract: contract.clone() };
+    build_query_request(deps.as_ref(), &env, &channel, &spec)?;
+    if let Some(callback) = &callback {
+        deps.api.addr_validate(&callback.contract)?;
+    }
+
+    let subscription_id = add_subscription(deps.storage, &env, info.sender.clone(), channel.clone(), spec, interval_seconds)?;
+
+    let id = NEXT_CONTRACT_WATCH_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_CONTRACT_WATCH_ID.save(deps.storage, &(id + 1))?;
+
+    CONTRACT_WATCHES_BY_ADDRESS.update(deps.storage, (&channel, &contract), |ids| -> StdResult<_> {
+        let mut ids = ids.unwrap_or_default();
+        ids.push(id);
+        Ok(ids)
+    })?;
+    CONTRACT_WATCHES.save(deps.storage, id, &ContractWatch {
+        chain,
+        channel,
+        contract,
+        owner: info.sender,
+        subscription_id,
+        callback,
+        info: None,
+        checksum: None,
+        checked_at: None,
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("method", "watch_contract")
+        .add_attribute("watch_id", id.to_string())
+        .add_attribute("subscription_id", subscription_id.to_string()))
+}
+
+pub fn unwatch_contract(deps: DepsMut, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+
+    let watch = CONTRACT_WATCHES.may_load(deps.storage, id)?.ok_or(ContractError::NoSuchContractWatch { id })?;
+    CONTRACT_WATCHES.remove(deps.storage, id);
+    if let Some(subscription) = SUBSCRIPTIONS.may_load(deps.storage, watch.subscription_id)? {
+        remove_subscription(deps.storage, watch.subscription_id, &subscription);
+    }
+    let key = (watch.channel.as_str(), watch.contract.as_str());
+    let mut ids = CONTRACT_WATCHES_BY_ADDRESS.may_load(deps.storage, key)?.unwrap_or_default();
+    ids.retain(|watch_id| *watch_id != id);
+    if ids.is_empty() {
+        CONTRACT_WATCHES_BY_ADDRESS.remove(deps.storage, key);
+    } else {
+        CONTRACT_WATCHES_BY_ADDRESS.save(deps.storage, key, &ids)?;
+    }
+    if let Some(info) = &watch.info {
+        unindex_code(deps.storage, &watch.channel, info.code_id, id)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "unwatch_contract")
+        .add_attribute("watch_id", id.to_string()))
+}
+
+fn index_code(storage: &mut dyn Storage, channel: &str, code_id: u64, id: u64) -> StdResult<()> {
+    CONTRACT_WATCHES_BY_CODE.update(storage, (channel, code_id), |ids| -> StdResult<_> {
+        let mut ids = ids.unwrap_or_default();
+        ids.push(id);
+        Ok(ids)
+    })?;
+    Ok(())
+}
+
+fn unindex_code(storage: &mut dyn Storage, channel: &str, code_id: u64, id: u64) -> StdResult<()> {
+    let mut ids = CONTRACT_WATCHES_BY_CODE.may_load(storage, (channel, code_id))?.unwrap_or_default();
+    ids.retain(|watch_id| *watch_id != id);
+    if ids.is_empty() {
+        CONTRACT_WATCHES_BY_CODE.remove(storage, (channel, code_id));
+    } else {
+        CONTRACT_WATCHES_BY_CODE.save(storage, (channel, code_id), &ids)?;
+    }
+    Ok(())
+}
+
+/// Event and callback of a watch whose contract changed, `changes` holding
+/// the (field, old, new) values that differ.
+fn changed(id: u64, watch: &ContractWatch, changes: Vec<(&str, String, String)>) -> StdResult<(Event, Option<SubMsg>)> {
+    let mut event = Event::new("contract_changed")
+        .add_attribute("watch_id", id.to_string())
+        .add_attribute("chain", &watch.chain)
+        .add_attribute("contract", &watch.contract);
+    for (field, old, new) in changes {
+        event = event
+            .add_attribute(format!("old_{field}"), old)
+            .add_attribute(format!("new_{field}"), new);
+    }
+    let callback = match &watch.callback {
+        Some(callback) => {
+            let msg = WasmMsg::Execute {
+                contract_addr: callback.contract.clone(),
+                msg: callback.msg.clone(),
+                funds: vec![],
+            };
+            Some(SubMsg::reply_on_error(msg, CONTRACT_WATCH_REPLY_ID).with_payload(to_json_binary(&id)?))
+        }
+        None => None,
+    };
+    Ok((event, callback))
+}
+
+fn watch_failed(id: u64, error: String) -> Event {
+    Event::new("contract_watch_failed")
+        .add_attribute("watch_id", id.to_string())
+        .add_attribute("error", error)
+}
+
+/// Compares the contract info that just arrived with the last known one of
+/// every watch of the contract. The code info of a code id not checked yet is
+/// then queried, paid by the owner of the watch. Returns the events to emit,
+/// and the callbacks along with the packets of these queries.
+pub fn evaluate_contract_info(
+    mut deps: DepsMut,
+    env: &Env,
+    channel: &str,
+    contract: &str,
+    info: &RemoteContractInfo,
+) -> Result<(Vec<Event>, Vec<SubMsg>), ContractError> {
+    let ids = CONTRACT_WATCHES_BY_ADDRESS.may_load(deps.storage, (channel, contract))?.unwrap_or_default();
+
+    let mut events = vec![];
+    let mut msgs = vec![];
+    for id in ids {
+        let mut watch = CONTRACT_WATCHES.load(deps.storage, id)?;
+        // the checksum of the previous code is kept to compare the new one to
+        let check_code = watch.checksum.is_none() || watch.info.as_ref().is_some_and(|old| old.code_id != info.code_id);
+        let mut changes = vec![];
+        if let Some(old) = &watch.info {
+            if old.code_id != info.code_id {
+                changes.push(("code_id", old.code_id.to_string(), info.code_id.to_string()));
+            }
+            if old.admin != info.admin {
+                changes.push(("admin", old.admin.clone(), info.admin.clone()));
+            }
+            if old.label != info.label {
+                changes.push(("label", old.label.clone(), info.label.clone()));
+            }
+        }
+        match &watch.info {
+            Some(old) if old.code_id == info.code_id => {}
+            Some(old) => {
+                unindex_code(deps.storage, channel, old.code_id, id)?;
+                index_code(deps.storage, channel, info.code_id, id)?;
+            }
+            None => index_code(deps.storage, channel, info.code_id, id)?,
+        }
+        watch.info = Some(info.clone());
+        watch.checked_at = Some(env.block.time);
+        CONTRACT_WATCHES.save(deps.storage, id, &watch)?;
+
+        if !changes.is_empty() {
+            let (event, callback) = changed(id, &watch, changes)?;
+            events.push(event);
+            msgs.extend(callback);
+        }
+        if !check_code {
+            continue;
+        }
+
+        let spec = QuerySpec::WasmCodeInfo { code_id: info.code_id };
+        let outcome = build_query_request(deps.as_ref(), env, channel, &spec)
+            .and_then(|req| prepare_icq_packet(deps.branch(), env, &watch.owner, channel, vec![req]));
+        match outcome {
+            Ok(PacketOutcome::Sent { msg, .. }) => msgs.push(SubMsg::new(msg)),
+            Ok(PacketOutcome::Cached(cached)) => match QueryCodeInfoResponse::decode(cached.value.as_slice()) {
+                Ok(code_info) => {
+                    let (code_events, code_callbacks) = evaluate_code_info(deps.storage, env, channel, &code_info)?;
+                    events.extend(code_events);
+                    msgs.extend(code_callbacks);
+                }
+                // the contract info is already stored, so it must not be failed
+                Err(err) => events.push(watch_failed(id, err.to_string())),
+            },
+            Ok(PacketOutcome::Coalesced { .. }) => {}
+            Err(err) => events.push(watch_failed(id, err.to_string())),
+        }
+    }
+    Ok((events, msgs))
+}
+
+/// Records the checksum of the code of every watched contract running
+/// `code_info.code_id`, reporting those whose checksum changed.
+pub fn evaluate_code_info(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel: &str,
+    code_info: &QueryCodeInfoResponse,
+) -> StdResult<(Vec<Event>, Vec<SubMsg>)> {
+    let ids = CONTRACT_WATCHES_BY_CODE.may_load(storage, (channel, code_info.code_id))?.unwrap_or_default();
+    let watches = ids
+        .into_iter()
+        .map(|id| Ok((id, CONTRACT_WATCHES.load(storage, id)?)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let checksum = HexBinary::from(code_info.checksum.as_slice());
+    let mut events = vec![];
+    let mut callbacks = vec![];
+    for (id, mut watch) in watches {
+        let old = watch.checksum.replace(checksum.clone());
+        watch.checked_at = Some(env.block.time);
+        CONTRACT_WATCHES.save(storage, id, &watch)?;
+
+        if let Some(old) = old.filter(|old| *old != checksum) {
+            let (event, callback) = changed(id, &watch, vec![("checksum", old.to_hex(), checksum.to_hex())])?;
+            events.push(event);
+            callbacks.extend(callback);
+        }
+    }
+    Ok((events, callbacks))
+}
+
+/// Only failed callbacks are replied to. The failure is reported instead of
+/// failing the ack that detected the change.
+pub fn handle_contract_callback_reply(msg: Reply) -> Result<Response, ContractError> {
+    let id: u64 = from_json(&msg.payload)?;
+    let error = match msg.result {
+        SubMsgResult::Err(error) => error,
+        SubMsgResult::Ok(_) => return Ok(Response::new()),
+    };
+
+    Ok(Response::new().add_event(
+        Event::new("contract_watch_callback_failed")
+            .add_attribute("watch_id", id.to_string())
+            .add_attribute("error", error),
+    ))
+}
+
+pub fn query_contract_watch(deps: Deps, id: u64) -> Result<ContractWatch, ContractError> {
+    CONTRACT_WATCHES.may_load(deps.storage, id)?.ok_or(ContractError::NoSuchContractWatch { id })
+}
+
+pub fn query_contract_watches(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<(u64, ContractWatch)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    CONTRACT_WATCHES
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
@@ -0,0 +1,102 @@
+use cosmwasm_std::{Deps, DepsMut, Env, HexBinary, MessageInfo, Order, Response, StdResult, Storage};
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+use crate::contract::{build_query_request, PacketOutcome, prepare_icq_packet};
+use crate::error::ContractError;
+use crate::msg::{QueryDenomHashRequest, QueryDenomHashResponse, QueryDenomTraceRequest, QueryDenomTraceResponse, QuerySpec};
+use crate::state::{DENOM_TRACES, DenomTrace};
+
+/// Splits a full trace into its port/channel hops and base denom, as ibc-go
+/// does for hops over `channel-N` identifiers.
+pub fn parse_trace(trace: &str) -> DenomTrace {
+    let parts: Vec<&str> = trace.split('/').collect();
+    let mut hops = 0;
+    while hops * 2 + 1 < parts.len() - 1 && parts[hops * 2 + 1].starts_with("channel-") {
+        hops += 1;
+    }
+    DenomTrace {
+        path: parts[..hops * 2].join("/"),
+        base_denom: parts[hops * 2..].join("/"),
+    }
+}
+
+/// The `ibc/` denom a trace is known by, or the base denom of a native one.
+pub fn ibc_denom(trace: &DenomTrace) -> String {
+    if trace.path.is_empty() {
+        return trace.base_denom.clone();
+    }
+    let hash = Sha256::digest(format!("{}/{}", trace.path, trace.base_denom));
+    format!("ibc/{}", HexBinary::from(hash.as_slice()).to_hex().to_uppercase())
+}
+
+fn send_resolution(mut deps: DepsMut, env: Env, info: MessageInfo, channel: String, spec: QuerySpec, method: &str) -> Result<Response, ContractError> {
+    let req = build_query_request(deps.as_ref(), &env, &channel, &spec)?;
+    let res = Response::new()
+        .add_attribute("method", method)
+        .add_attribute("channel", &channel);
+
+    match prepare_icq_packet(deps.branch(), &env, &info.sender, &channel, vec![req.clone()])? {
+        // an answer still in the cache may never have been recorded
+        PacketOutcome::Cached(cached) => {
+            match spec {
+                QuerySpec::DenomTrace { .. } => record_denom_trace(deps.storage, &channel, &req.data, &cached.value)?,
+                _ => record_denom_hash(deps.storage, &channel, &req.data, &cached.value)?,
+            }
+            Ok(res.add_attribute("cache_hit", "true"))
+        }
+        PacketOutcome::Coalesced { request_id } => Ok(res
+            .add_attribute("coalesced", "true")
+            .add_attribute("request_id", request_id.to_string())),
+        PacketOutcome::Sent { request_id, msg } => Ok(res
+            .add_attribute("request_id", request_id.to_string())
+            .add_message(msg)),
+    }
+}
+
+pub fn resolve_denom(deps: DepsMut, env: Env, info: MessageInfo, channel: String, denom: String) -> Result<Response, ContractError> {
+    send_resolution(deps, env, info, channel, QuerySpec::DenomTrace { denom }, "resolve_denom")
+}
+
+pub fn resolve_denom_hash(deps: DepsMut, env: Env, info: MessageInfo, channel: String, trace: String) -> Result<Response, ContractError> {
+    send_resolution(deps, env, info, channel, QuerySpec::DenomHash { trace }, "resolve_denom_hash")
+}
+
+/// Stores the trace the host returned for an `ibc/` denom.
+pub fn record_denom_trace(storage: &mut dyn Storage, channel: &str, request: &[u8], response: &[u8]) -> Result<(), ContractError> {
+    let request = QueryDenomTraceRequest::decode(request)?;
+    let response = QueryDenomTraceResponse::decode(response)?;
+    if let Some(trace) = response.denom_trace {
+        let denom = format!("ibc/{}", request.hash.to_uppercase());
+        DENOM_TRACES.save(storage, (channel, &denom), &DenomTrace { path: trace.path, base_denom: trace.base_denom })?;
+    }
+    Ok(())
+}
+
+/// Stores the trace a hash was requested for under the `ibc/` denom the host
+/// returned. A trace whose split into path and base denom does not hash to
+/// that denom is ambiguous and left unresolved.
+pub fn record_denom_hash(storage: &mut dyn Storage, channel: &str, request: &[u8], response: &[u8]) -> Result<(), ContractError> {
+    let request = QueryDenomHashRequest::decode(request)?;
+    let response = QueryDenomHashResponse::decode(response)?;
+    let denom = format!("ibc/{}", response.hash.to_uppercase());
+    let trace = parse_trace(&request.trace);
+    if ibc_denom(&trace) == denom {
+        DENOM_TRACES.save(storage, (channel, &denom), &trace)?;
+    }
+    Ok(())
+}
+
+pub fn denom_trace(storage: &dyn Storage, channel: &str, denom: &str) -> StdResult<Option<DenomTrace>> {
+    if !denom.starts_with("ibc/") {
+        return Ok(None);
+    }
+    DENOM_TRACES.may_load(storage, (channel, denom))
+}
+
+pub fn query_denom_traces(deps: Deps, channel: String) -> StdResult<Vec<(String, DenomTrace)>> {
+    DENOM_TRACES
+        .prefix(&channel)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect()
+}
@@ -1,7 +1,7 @@
 use std::num::TryFromIntError;
 use std::string::FromUtf8Error;
 use std::time::SystemTimeError;
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
 /// Never is a placeholder to ensure we don't return any errors
@@ -63,6 +63,168 @@ pub enum ContractError {
 
     #[error("invalid IBC channel version. Got ({actual}), expected ({expected})")]
     InvalidIbcVersion { actual: String, expected: String },
+
+    #[error("Invalid remote address {address}: {reason}")]
+    InvalidAddress { address: String, reason: String },
+
+    #[error("Address {address} has prefix {actual}, channel expects {expected}")]
+    AddressPrefixMismatch { address: String, expected: String, actual: String },
+
+    #[error("Invalid bech32 prefix: {prefix}")]
+    InvalidPrefix { prefix: String },
+
+    #[error("Query path {path} is not allowed on channel {channel}")]
+    QueryPathNotAllowed { channel: String, path: String },
+
+    #[error("Invalid ABCI query path: {path}")]
+    InvalidQueryPath { path: String },
+
+    #[error("Query path {path} can only be sent through its own message")]
+    RawQueryPathNotAllowed { path: String },
+
+    #[error("Subscription interval must be greater than zero")]
+    InvalidInterval {},
+
+    #[error("Subscription doesn't exist: {id}")]
+    NoSuchSubscription { id: u64 },
+
+    #[error("Insufficient credits: {required} required, {available} available")]
+    InsufficientCredits { required: Uint128, available: Uint128 },
+
+    #[error("Insufficient bounty pool: {required} required, {available} available")]
+    InsufficientBountyPool { required: Uint128, available: Uint128 },
+
+    #[error("Fee denom is already set to {denom}")]
+    FeeDenomAlreadySet { denom: String },
+
+    #[error("No fee denom is configured")]
+    FeesDisabled {},
+
+    #[error("Only {expected} is accepted")]
+    WrongDenom { expected: String },
+
+    #[error("Rate limit exceeded for {scope}")]
+    RateLimited { scope: String },
+
+    #[error("Too many requests in flight for {scope}, at most {max} allowed")]
+    TooManyInFlight { scope: String, max: u64 },
+
+    #[error("Rate limits need a non-zero packet count and window")]
+    InvalidRateLimit {},
+
+    #[error("Sending is paused (channel: {channel}, kind: {kind})")]
+    Paused { channel: String, kind: String },
+
+    #[error("Invalid price: {raw}")]
+    InvalidPrice { raw: String },
+
+    #[error("No price for {base}/{quote}")]
+    NoPrice { base: String, quote: String },
+
+    #[error("Only {fresh} fresh sources for {base}/{quote}, {required} required")]
+    InsufficientSources { base: String, quote: String, fresh: u32, required: u32 },
+
+    #[error("Aggregating the sources of {base}/{quote} overflowed")]
+    FeedOverflow { base: String, quote: String },
+
+    #[error("Price of {denom} was published at {publish_time}, more than {max_age_seconds}s ago")]
+    StalePrice { denom: String, publish_time: u64, max_age_seconds: u64 },
+
+    #[error("No quote denom is configured")]
+    QuoteDenomNotSet {},
+
+    #[error("No chain is known as {alias}")]
+    NoSuchChainAlias { alias: String },
+
+    #[error("No portfolio named {name}")]
+    NoSuchPortfolio { name: String },
+
+    #[error("Invalid portfolio: {reason}")]
+    InvalidPortfolio { reason: String },
+
+    #[error("Invalid snapshot window: {reason}")]
+    InvalidSnapshotWindow { reason: String },
+
+    #[error("No watch with id {id}")]
+    NoSuchWatch { id: u64 },
+
+    #[error("No contract watch with id {id}")]
+    NoSuchContractWatch { id: u64 },
+
+    #[error("No condition with id {id}")]
+    NoSuchCondition { id: u64 },
+
+    #[error("Condition {id} is no longer pending")]
+    ConditionNotPending { id: u64 },
+
+    #[error("Invalid condition: {reason}")]
+    InvalidCondition { reason: String },
+
+    #[error("Too many pending conditions, at most {max} are allowed per owner")]
+    TooManyConditions { max: u64 },
+
+    #[error("Cannot answer query {path}: {reason}")]
+    UnsupportedQuery { path: String, reason: String },
+
+    #[error("Invalid smart query: {reason}")]
+    InvalidSmartQuery { reason: String },
+
+    #[error("No wasm query result for sequence {sequence}")]
+    NoSuchWasmResult { sequence: u64 },
+
+    #[error("Invalid store name {store}")]
+    InvalidStoreName { store: String },
+
+    #[error("No proven result for sequence {sequence}")]
+    NoSuchProvenResult { sequence: u64 },
+
+    #[error("Proof of sequence {sequence} was already checked")]
+    ProofAlreadyChecked { sequence: u64 },
+
+    #[error("No app hash known for {channel} at height {height}")]
+    NoAppHash { channel: String, height: u64 },
+
+    #[error("Invalid query height {height}")]
+    InvalidHeight { height: u64 },
+
+    #[error("No reconciliation of {voucher_denom} on {channel}")]
+    NoSuchReconciliation { channel: String, voucher_denom: String },
+
+    #[error("No proposal {proposal_id} known on {chain}")]
+    NoSuchProposal { chain: String, proposal_id: u64 },
+
+    #[error("No account {address} known on {chain}")]
+    NoSuchRemoteAccount { chain: String, address: String },
+
+    #[error("No fee allowance from {granter} to {grantee} known on {chain}")]
+    NoSuchFeeAllowance { chain: String, granter: String, grantee: String },
+
+    #[error("Validator {operator_address} is not watched on {chain}")]
+    NoSuchValidatorWatch { chain: String, operator_address: String },
+
+    #[error("Invalid batch: {reason}")]
+    InvalidBatch { reason: String },
+
+    #[error("No batch result for sequence {sequence}")]
+    NoSuchBatchResult { sequence: u64 },
+
+    #[error("No feed for {base}/{quote}")]
+    NoSuchFeed { base: String, quote: String },
+
+    #[error("Invalid feed: {reason}")]
+    InvalidFeed { reason: String },
+
+    #[error("No quarantined price for sequence {sequence}")]
+    NoSuchQuarantinedPrice { sequence: u64 },
+
+    #[error("Invalid denom {denom}: {reason}")]
+    InvalidDenom { denom: String, reason: String },
+
+    #[error("Invalid pool id: {pool_id}")]
+    InvalidPoolId { pool_id: u64 },
+
+    #[error("TWAP base and quote asset are both {denom}")]
+    IdenticalTwapAssets { denom: String },
 }
 
 impl From<FromUtf8Error> for ContractError {
@@ -0,0 +1,140 @@
+use cosmwasm_std::{Decimal256, Deps, DepsMut, MessageInfo, Order, Response, StdResult, Storage, Timestamp};
+
+use crate::contract::ensure_admin;
+use crate::denom_trace::denom_trace;
+use crate::error::ContractError;
+use crate::msg::{AggregatedPriceResponse, SourcePrice};
+use crate::price::price_source;
+use crate::state::{Aggregation, FEEDS, PRICES, PriceFeed};
+use crate::validation::validate_twap_query;
+
+/// Defines or replaces the feed of `feed.base` in `feed.quote`. Admin only.
+pub fn set_feed(deps: DepsMut, info: MessageInfo, feed: PriceFeed) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+
+    if feed.sources.is_empty() || feed.min_sources == 0 || feed.min_sources as usize > feed.sources.len() {
+        return Err(ContractError::InvalidFeed { reason: "min_sources must be between 1 and the number of sources".to_string() });
+    }
+    for source in &feed.sources {
+        validate_twap_query(source.pool_id, &source.base_asset, &source.quote_asset)?;
+        if source.weight.is_zero() {
+            return Err(ContractError::InvalidFeed { reason: "source weights must be positive".to_string() });
+        }
+        if !same_asset(deps.storage, &source.channel, &source.base_asset, &feed.base)?
+            || !same_asset(deps.storage, &source.channel, &source.quote_asset, &feed.quote)?
+        {
+            return Err(ContractError::InvalidFeed {
+                reason: format!(
+                    "source {} quotes {}/{} instead of {}/{}",
+                    price_source(&source.channel, source.pool_id),
+                    source.base_asset,
+                    source.quote_asset,
+                    feed.base,
+                    feed.quote,
+                ),
+            });
+        }
+    }
+
+    FEEDS.save(deps.storage, (&feed.base, &feed.quote), &feed)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_feed")
+        .add_attribute("base", feed.base)
+        .add_attribute("quote", feed.quote))
+}
+
+pub fn remove_feed(deps: DepsMut, info: MessageInfo, base: String, quote: String) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+
+    FEEDS.remove(deps.storage, (&base, &quote));
+
+    Ok(Response::new()
+        .add_attribute("method", "remove_feed")
+        .add_attribute("base", base)
+        .add_attribute("quote", quote))
+}
+
+/// Whether `asset`, as known on the chain behind `channel`, is the feed asset
+/// `denom`: either the same denom or an `ibc/` denom resolved to it.
+fn same_asset(storage: &dyn Storage, channel: &str, asset: &str, denom: &str) -> StdResult<bool> {
+    if asset == denom {
+        return Ok(true);
+    }
+    Ok(denom_trace(storage, channel, asset)?.is_some_and(|trace| trace.base_denom == denom))
+}
+
+/// None when the prices are too large to be averaged.
+fn median(mut prices: Vec<Decimal256>) -> Option<Decimal256> {
+    prices.sort();
+    let middle = prices.len() / 2;
+    if prices.len().is_multiple_of(2) {
+        prices[middle - 1].checked_add(prices[middle]).ok()?.checked_div(Decimal256::percent(200)).ok()
+    } else {
+        Some(prices[middle])
+    }
+}
+
+/// None when the weighted sum of the prices overflows.
+fn weighted_mean(sources: &[SourcePrice]) -> Option<Decimal256> {
+    let mut total_weight = Decimal256::zero();
+    let mut weighted = Decimal256::zero();
+    for source in sources {
+        total_weight = total_weight.checked_add(source.weight).ok()?;
+        weighted = weighted.checked_add(source.price.checked_mul(source.weight).ok()?).ok()?;
+    }
+    weighted.checked_div(total_weight).ok()
+}
+
+/// Combines the fresh sources of the feed, failing if fewer than
+/// `min_sources` of them were updated within `max_staleness_seconds`.
+pub fn aggregate(storage: &dyn Storage, feed: &PriceFeed, now: Timestamp) -> Result<AggregatedPriceResponse, ContractError> {
+    let mut fresh = vec![];
+    for source in &feed.sources {
+        let id = price_source(&source.channel, source.pool_id);
+        let Some(record) = PRICES.may_load(storage, (&source.base_asset, &source.quote_asset, &id))? else {
+            continue;
+        };
+        if record.publish_time.plus_seconds(feed.max_staleness_seconds) < now {
+            continue;
+        }
+        fresh.push(SourcePrice {
+            source: id,
+            price: record.price,
+            weight: source.weight,
+            publish_time: record.publish_time,
+        });
+    }
+
+    if fresh.len() < feed.min_sources as usize {
+        return Err(ContractError::InsufficientSources {
+            base: feed.base.clone(),
+            quote: feed.quote.clone(),
+            fresh: fresh.len() as u32,
+            required: feed.min_sources,
+        });
+    }
+
+    let price = match feed.aggregation {
+        Aggregation::Median => median(fresh.iter().map(|source| source.price).collect()),
+        Aggregation::WeightedMean => weighted_mean(&fresh),
+    }
+    .ok_or_else(|| ContractError::FeedOverflow { base: feed.base.clone(), quote: feed.quote.clone() })?;
+    let oldest_publish_time = fresh.iter().map(|source| source.publish_time).min().expect("at least one fresh source");
+
+    Ok(AggregatedPriceResponse { price, sources: fresh, oldest_publish_time })
+}
+
+pub fn query_aggregated_price(deps: Deps, now: Timestamp, base: String, quote: String) -> Result<AggregatedPriceResponse, ContractError> {
+    let feed = FEEDS
+        .may_load(deps.storage, (&base, &quote))?
+        .ok_or(ContractError::NoSuchFeed { base, quote })?;
+    aggregate(deps.storage, &feed, now)
+}
+
+pub fn query_feeds(deps: Deps) -> StdResult<Vec<PriceFeed>> {
+    FEEDS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, feed)| feed))
+        .collect()
+}
@@ -0,0 +1,154 @@
+use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::AbciQueryRequest;
+use cosmwasm_std::{Addr, BankMsg, coins, Deps, DepsMut, MessageInfo, Order, Response, StdResult, Storage, Uint128};
+
+use crate::contract::ensure_admin;
+use crate::error::ContractError;
+use crate::msg::{FeeRate, FeeScheduleResponse, QueryKind};
+use crate::state::{CONFIG, CREDITS, FEE_SCHEDULE, FEES_COLLECTED, PendingRequest};
+
+/// Sum of the fees of every request in a packet.
+pub fn packet_fee(storage: &dyn Storage, requests: &[AbciQueryRequest]) -> StdResult<Uint128> {
+    requests.iter().try_fold(Uint128::zero(), |total, req| {
+        let fee = FEE_SCHEDULE
+            .may_load(storage, QueryKind::from_path(&req.path).as_str())?
+            .unwrap_or_default();
+        Ok(total + fee)
+    })
+}
+
+/// Takes `amount` from the credits of `payer`, failing without any write if
+/// they cannot cover it.
+pub fn debit_credits(storage: &mut dyn Storage, payer: &Addr, amount: Uint128) -> Result<(), ContractError> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    let available = CREDITS.may_load(storage, payer)?.unwrap_or_default();
+    if available < amount {
+        return Err(ContractError::InsufficientCredits { required: amount, available });
+    }
+    CREDITS.save(storage, payer, &(available - amount))?;
+    Ok(())
+}
+
+/// Settles the fee of a finished request: refunded to the payer when it failed
+/// and refunds are enabled, otherwise added to the collected fees. A request
+/// whose packet was acknowledged counts as served even when the host rejected
+/// some of its queries, so their fees are kept.
+pub fn settle(storage: &mut dyn Storage, pending: &PendingRequest, failed: bool) -> StdResult<bool> {
+    if pending.fee.is_zero() {
+        return Ok(false);
+    }
+    let config = CONFIG.load(storage)?;
+    if failed && config.refund_on_failure {
+        CREDITS.update(storage, &pending.waiters[0], |credits| -> StdResult<_> {
+            Ok(credits.unwrap_or_default() + pending.fee)
+        })?;
+        return Ok(true);
+    }
+    let collected = FEES_COLLECTED.may_load(storage)?.unwrap_or_default();
+    FEES_COLLECTED.save(storage, &(collected + pending.fee))?;
+    Ok(false)
+}
+
+pub fn deposit(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let denom = config.fee_denom.ok_or(ContractError::FeesDisabled {})?;
+
+    let amount = match info.funds.as_slice() {
+        [] => return Err(ContractError::NoFunds {}),
+        [coin] if coin.denom == denom => coin.amount,
+        _ => return Err(ContractError::WrongDenom { expected: denom }),
+    };
+    if amount.is_zero() {
+        return Err(ContractError::NoFunds {});
+    }
+
+    let credits = CREDITS.update(deps.storage, &info.sender, |credits| -> StdResult<_> {
+        Ok(credits.unwrap_or_default() + amount)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("method", "deposit")
+        .add_attribute("sender", info.sender)
+        .add_attribute("amount", amount)
+        .add_attribute("credits", credits))
+}
+
+pub fn withdraw(deps: DepsMut, info: MessageInfo, amount: Uint128) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let denom = config.fee_denom.ok_or(ContractError::FeesDisabled {})?;
+    if amount.is_zero() {
+        return Err(ContractError::NoFunds {});
+    }
+
+    debit_credits(deps.storage, &info.sender, amount)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "withdraw")
+        .add_attribute("sender", &info.sender)
+        .add_attribute("amount", amount)
+        .add_message(BankMsg::Send { to_address: info.sender.into_string(), amount: coins(amount.u128(), denom) }))
+}
+
+pub fn set_fee(deps: DepsMut, info: MessageInfo, kind: QueryKind, amount: Uint128) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+    // without a fee denom there are no credits to pay a fee from
+    if !amount.is_zero() && CONFIG.load(deps.storage)?.fee_denom.is_none() {
+        return Err(ContractError::FeesDisabled {});
+    }
+
+    if amount.is_zero() {
+        FEE_SCHEDULE.remove(deps.storage, kind.as_str());
+    } else {
+        FEE_SCHEDULE.save(deps.storage, kind.as_str(), &amount)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "set_fee")
+        .add_attribute("kind", kind.as_str())
+        .add_attribute("amount", amount))
+}
+
+/// Sends the fees of completed requests to `to`, or the admin. Admin only.
+pub fn collect_fees(deps: DepsMut, info: MessageInfo, to: Option<String>) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+    let config = CONFIG.load(deps.storage)?;
+    let denom = config.fee_denom.ok_or(ContractError::FeesDisabled {})?;
+    let to = match to {
+        Some(to) => deps.api.addr_validate(&to)?,
+        None => config.admin,
+    };
+
+    let collected = FEES_COLLECTED.may_load(deps.storage)?.unwrap_or_default();
+    if collected.is_zero() {
+        return Err(ContractError::NoFunds {});
+    }
+    FEES_COLLECTED.save(deps.storage, &Uint128::zero())?;
+
+    Ok(Response::new()
+        .add_attribute("method", "collect_fees")
+        .add_attribute("amount", collected)
+        .add_message(BankMsg::Send { to_address: to.into_string(), amount: coins(collected.u128(), denom) }))
+}
+
+pub fn query_credits(deps: Deps, address: String) -> StdResult<Uint128> {
+    let address = deps.api.addr_validate(&address)?;
+    Ok(CREDITS.may_load(deps.storage, &address)?.unwrap_or_default())
+}
+
+pub fn query_fee_schedule(deps: Deps) -> StdResult<FeeScheduleResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let fees = FEE_SCHEDULE
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (kind, amount) = item?;
+            Ok(FeeRate { kind, amount })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(FeeScheduleResponse {
+        denom: config.fee_denom,
+        refund_on_failure: config.refund_on_failure,
+        collected: FEES_COLLECTED.may_load(deps.storage)?.unwrap_or_default(),
+        fees,
+    })
+}
@@ -0,0 +1,182 @@
+use cosmos_sdk_proto::cosmos::gov::v1::{Proposal, ProposalStatus as ProtoProposalStatus, QueryProposalResponse, QueryProposalsResponse, TallyResult};
+use cosmwasm_std::{Deps, DepsMut, Env, Event, MessageInfo, Order, Response, StdResult, Storage, Uint128};
+use cw_storage_plus::Bound;
+use prost::Message;
+
+use crate::contract::{block_time, build_query_request, PacketOutcome, prepare_icq_packet};
+use crate::error::ContractError;
+use crate::msg::{GOV_PROPOSAL_QUERY_PATH, QuerySpec};
+use crate::portfolio::resolve_chain;
+use crate::state::{PROPOSALS, ProposalRecord, ProposalStatus, ProposalTally};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Proposals asked for by a `GovProposals` query, the host answering the
+/// newest first.
+pub const PROPOSALS_PAGE_LIMIT: u64 = 20;
+
+pub fn proposal_status_code(status: ProposalStatus) -> i32 {
+    let status = match status {
+        ProposalStatus::Unspecified => ProtoProposalStatus::Unspecified,
+        ProposalStatus::DepositPeriod => ProtoProposalStatus::DepositPeriod,
+        ProposalStatus::VotingPeriod => ProtoProposalStatus::VotingPeriod,
+        ProposalStatus::Passed => ProtoProposalStatus::Passed,
+        ProposalStatus::Rejected => ProtoProposalStatus::Rejected,
+        ProposalStatus::Failed => ProtoProposalStatus::Failed,
+    };
+    status as i32
+}
+
+fn proposal_status(code: i32) -> ProposalStatus {
+    match ProtoProposalStatus::try_from(code) {
+        Ok(ProtoProposalStatus::DepositPeriod) => ProposalStatus::DepositPeriod,
+        Ok(ProtoProposalStatus::VotingPeriod) => ProposalStatus::VotingPeriod,
+        Ok(ProtoProposalStatus::Passed) => ProposalStatus::Passed,
+        Ok(ProtoProposalStatus::Rejected) => ProposalStatus::Rejected,
+        Ok(ProtoProposalStatus::Failed) => ProposalStatus::Failed,
+        _ => ProposalStatus::Unspecified,
+    }
+}
+
+fn status_name(status: ProposalStatus) -> &'static str {
+    match status {
+        ProposalStatus::Unspecified => "unspecified",
+        ProposalStatus::DepositPeriod => "deposit_period",
+        ProposalStatus::VotingPeriod => "voting_period",
+        ProposalStatus::Passed => "passed",
+        ProposalStatus::Rejected => "rejected",
+        ProposalStatus::Failed => "failed",
+    }
+}
+
+fn tally(result: Option<TallyResult>) -> StdResult<ProposalTally> {
+    let Some(result) = result else {
+        return Ok(ProposalTally::default());
+    };
+    let count = |count: &str| if count.is_empty() { Ok(Uint128::zero()) } else { count.parse::<Uint128>() };
+    Ok(ProposalTally {
+        yes: count(&result.yes_count)?,
+        abstain: count(&result.abstain_count)?,
+        no: count(&result.no_count)?,
+        no_with_veto: count(&result.no_with_veto_count)?,
+    })
+}
+
+fn send_gov_query(mut deps: DepsMut, env: Env, info: MessageInfo, channel: String, spec: QuerySpec, method: &str) -> Result<Response, ContractError> {
+    let req = build_query_request(deps.as_ref(), &env, &channel, &spec)?;
+    let res = Response::new()
+        .add_attribute("method", method)
+        .add_attribute("channel", &channel);
+
+    match prepare_icq_packet(deps.branch(), &env, &info.sender, &channel, vec![req.clone()])? {
+        PacketOutcome::Cached(cached) => {
+            let events = record_proposals(deps.storage, &env, &channel, &req.path, &cached.value, 0)?;
+            Ok(res.add_attribute("cache_hit", "true").add_events(events))
+        }
+        PacketOutcome::Coalesced { request_id } => Ok(res
+            .add_attribute("coalesced", "true")
+            .add_attribute("request_id", request_id.to_string())),
+        PacketOutcome::Sent { request_id, msg } => Ok(res
+            .add_attribute("request_id", request_id.to_string())
+            .add_message(msg)),
+    }
+}
+
+pub fn send_query_proposal(deps: DepsMut, env: Env, info: MessageInfo, channel: String, proposal_id: u64) -> Result<Response, ContractError> {
+    send_gov_query(deps, env, info, channel, QuerySpec::GovProposal { proposal_id }, "send_query_proposal")
+}
+
+pub fn send_query_proposals(deps: DepsMut, env: Env, info: MessageInfo, channel: String, status: Option<ProposalStatus>) -> Result<Response, ContractError> {
+    send_gov_query(deps, env, info, channel, QuerySpec::GovProposals { status }, "send_query_proposals")
+}
+
+/// Stores the proposals answered to a `Proposal` or `Proposals` query and
+/// returns an event for each that entered its voting period or passed since
+/// it was last seen. A proposal seen for the first time only raises
+/// `proposal_voting_started`, so that listing past proposals stays quiet.
+pub fn record_proposals(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel: &str,
+    path: &str,
+    value: &[u8],
+    height: u64,
+) -> Result<Vec<Event>, ContractError> {
+    let proposals: Vec<Proposal> = if path == GOV_PROPOSAL_QUERY_PATH {
+        QueryProposalResponse::decode(value)?.proposal.into_iter().collect()
+    } else {
+        QueryProposalsResponse::decode(value)?.proposals
+    };
+    // every proposal is parsed before any is stored, so that an answer failing
+    // on one does not leave the others stored without their events
+    let records = proposals
+        .into_iter()
+        .map(|proposal| {
+            let record = ProposalRecord {
+                title: proposal.title,
+                status: proposal_status(proposal.status),
+                voting_end_time: proposal.voting_end_time.as_ref().and_then(block_time),
+                tally: tally(proposal.final_tally_result)?,
+                height,
+                updated_at: env.block.time,
+            };
+            Ok((proposal.id, record))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut events = vec![];
+    for (id, record) in records {
+        events.extend(record_proposal(storage, channel, id, record)?);
+    }
+    Ok(events)
+}
+
+fn record_proposal(storage: &mut dyn Storage, channel: &str, id: u64, record: ProposalRecord) -> Result<Option<Event>, ContractError> {
+    let old = PROPOSALS.may_load(storage, (channel, id))?;
+    PROPOSALS.save(storage, (channel, id), &record)?;
+
+    let old_status = old.map(|old| old.status);
+    let kind = match record.status {
+        ProposalStatus::VotingPeriod if old_status != Some(ProposalStatus::VotingPeriod) => "proposal_voting_started",
+        ProposalStatus::Passed if old_status.is_some_and(|status| status != ProposalStatus::Passed) => "proposal_passed",
+        _ => return Ok(None),
+    };
+    let mut event = Event::new(kind)
+        .add_attribute("channel", channel)
+        .add_attribute("proposal_id", id.to_string())
+        .add_attribute("title", record.title)
+        .add_attribute("status", status_name(record.status));
+    if let Some(old_status) = old_status {
+        event = event.add_attribute("previous_status", status_name(old_status));
+    }
+    if let Some(voting_end_time) = record.voting_end_time {
+        event = event.add_attribute("voting_end_time", voting_end_time.to_string());
+    }
+    Ok(Some(event))
+}
+
+pub fn query_proposal(deps: Deps, chain: String, proposal_id: u64) -> Result<ProposalRecord, ContractError> {
+    let channel = resolve_chain(deps.storage, &chain)?;
+    PROPOSALS
+        .may_load(deps.storage, (&channel, proposal_id))?
+        .ok_or(ContractError::NoSuchProposal { chain, proposal_id })
+}
+
+pub fn query_proposals(
+    deps: Deps,
+    chain: String,
+    status: Option<ProposalStatus>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Vec<(u64, ProposalRecord)>, ContractError> {
+    let channel = resolve_chain(deps.storage, &chain)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let proposals = PROPOSALS
+        .prefix(&channel)
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .filter(|item| item.as_ref().map_or(true, |(_, record)| status.is_none_or(|status| record.status == status)))
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+    Ok(proposals)
+}
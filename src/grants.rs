@@ -0,0 +1,350 @@
+use cosmos_sdk_proto::Any;
+use cosmos_sdk_proto::cosmos::auth::v1beta1::{BaseAccount, ModuleAccount, QueryAccountRequest, QueryAccountResponse};
+use cosmos_sdk_proto::cosmos::authz::v1beta1::{
+    GenericAuthorization, QueryGranterGrantsRequest, QueryGranterGrantsResponse, QueryGrantsRequest, QueryGrantsResponse,
+};
+use cosmos_sdk_proto::cosmos::feegrant::v1beta1::{AllowedMsgAllowance, BasicAllowance, PeriodicAllowance, QueryAllowanceResponse};
+use cosmos_sdk_proto::cosmos::vesting::v1beta1::DelayedVestingAccount;
+use cosmos_sdk_proto::Timestamp as ProtoTimestamp;
+use cosmwasm_std::{Coin, Deps, DepsMut, Env, Event, MessageInfo, Order, Response, StdResult, Storage, Timestamp, Uint128};
+use prost::Message;
+
+use crate::contract::{block_time, build_query_request, PacketOutcome, prepare_icq_packet};
+use crate::error::ContractError;
+use crate::msg::{
+    AUTH_ACCOUNT_QUERY_PATH, AUTHZ_GRANTER_GRANTS_QUERY_PATH, AUTHZ_GRANTS_QUERY_PATH, ExpiringGrant, FEE_ALLOWANCE_QUERY_PATH, GrantKind, QuerySpec,
+};
+use crate::portfolio::resolve_chain;
+use crate::state::{AUTHZ_GRANTS, AuthzGrant, CONFIG, FEE_ALLOWANCES, FeeAllowance, REMOTE_ACCOUNTS, RemoteAccount};
+
+const BASE_ACCOUNT_TYPE: &str = "/cosmos.auth.v1beta1.BaseAccount";
+const VESTING_ACCOUNT_PREFIX: &str = "/cosmos.vesting.v1beta1.";
+const GENERIC_AUTHORIZATION_TYPE: &str = "/cosmos.authz.v1beta1.GenericAuthorization";
+const BASIC_ALLOWANCE_TYPE: &str = "/cosmos.feegrant.v1beta1.BasicAllowance";
+const PERIODIC_ALLOWANCE_TYPE: &str = "/cosmos.feegrant.v1beta1.PeriodicAllowance";
+const ALLOWED_MSG_ALLOWANCE_TYPE: &str = "/cosmos.feegrant.v1beta1.AllowedMsgAllowance";
+
+/// Grants asked for by an `AuthzGranterGrants` query. When the granter has
+/// more, the grantees left out cannot be told from revoked ones.
+pub const GRANTER_GRANTS_PAGE_LIMIT: u64 = 100;
+
+fn send_grant_query(mut deps: DepsMut, env: Env, info: MessageInfo, channel: String, spec: QuerySpec, method: &str) -> Result<Response, ContractError> {
+    let req = build_query_request(deps.as_ref(), &env, &channel, &spec)?;
+    let res = Response::new()
+        .add_attribute("method", method)
+        .add_attribute("channel", &channel);
+
+    match prepare_icq_packet(deps.branch(), &env, &info.sender, &channel, vec![req.clone()])? {
+        PacketOutcome::Cached(cached) => {
+            let events = record_grant_response(deps.storage, &env, &channel, &req.path, &req.data, &cached.value, 0)?;
+            Ok(res.add_attribute("cache_hit", "true").add_events(events))
+        }
+        PacketOutcome::Coalesced { request_id } => Ok(res
+            .add_attribute("coalesced", "true")
+            .add_attribute("request_id", request_id.to_string())),
+        PacketOutcome::Sent { request_id, msg } => Ok(res
+            .add_attribute("request_id", request_id.to_string())
+            .add_message(msg)),
+    }
+}
+
+pub fn send_query_account(deps: DepsMut, env: Env, info: MessageInfo, channel: String, address: String) -> Result<Response, ContractError> {
+    send_grant_query(deps, env, info, channel, QuerySpec::Account { address }, "send_query_account")
+}
+
+pub fn send_query_grants(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel: String,
+    granter: String,
+    grantee: Option<String>,
+) -> Result<Response, ContractError> {
+    let spec = match grantee {
+        Some(grantee) => QuerySpec::AuthzGrants { granter, grantee },
+        None => QuerySpec::AuthzGranterGrants { granter },
+    };
+    send_grant_query(deps, env, info, channel, spec, "send_query_grants")
+}
+
+pub fn send_query_fee_allowance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel: String,
+    granter: String,
+    grantee: String,
+) -> Result<Response, ContractError> {
+    send_grant_query(deps, env, info, channel, QuerySpec::FeeAllowance { granter, grantee }, "send_query_fee_allowance")
+}
+
+/// Stores the answer to an account, authz or feegrant query. Returns a
+/// `grant_expiring` event for each grant that came within the warning window
+/// of its expiration since it was last seen.
+pub fn record_grant_response(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel: &str,
+    path: &str,
+    request: &[u8],
+    response: &[u8],
+    height: u64,
+) -> Result<Vec<Event>, ContractError> {
+    let warning_seconds = CONFIG.load(storage)?.grant_expiry_warning_seconds;
+    let expiring = |expiration: Option<Timestamp>| expiration.is_some_and(|expiration| expiration <= env.block.time.plus_seconds(warning_seconds));
+
+    match path {
+        AUTH_ACCOUNT_QUERY_PATH => {
+            let request = QueryAccountRequest::decode(request)?;
+            if let Some(account) = QueryAccountResponse::decode(response)?.account {
+                record_account(storage, env, channel, &request.address, &account, height)?;
+            }
+            Ok(vec![])
+        }
+        AUTHZ_GRANTS_QUERY_PATH => {
+            let request = QueryGrantsRequest::decode(request)?;
+            let grants = QueryGrantsResponse::decode(response)?
+                .grants
+                .into_iter()
+                .map(|grant| authz_grant(env, grant.authorization, grant.expiration, &expiring))
+                .collect::<Result<_, _>>()?;
+            save_authz_grants(storage, channel, &request.granter, &request.grantee, grants)
+        }
+        AUTHZ_GRANTER_GRANTS_QUERY_PATH => {
+            let request = QueryGranterGrantsRequest::decode(request)?;
+            let response = QueryGranterGrantsResponse::decode(response)?;
+            let complete = response.pagination.as_ref().is_none_or(|page| page.next_key.is_empty());
+            let mut by_grantee: Vec<(String, Vec<AuthzGrant>)> = vec![];
+            for grant in response.grants {
+                let authz_grant = authz_grant(env, grant.authorization, grant.expiration, &expiring)?;
+                match by_grantee.iter_mut().find(|(grantee, _)| *grantee == grant.grantee) {
+                    Some((_, grants)) => grants.push(authz_grant),
+                    None => by_grantee.push((grant.grantee, vec![authz_grant])),
+                }
+            }
+
+            // grants no longer answered were revoked or have expired, which
+            // only holds when the answer is not cut to a page
+            if complete {
+                let known: Vec<String> = AUTHZ_GRANTS
+                    .prefix((channel, &request.granter))
+                    .keys(storage, None, None, Order::Ascending)
+                    .collect::<StdResult<_>>()?;
+                for grantee in known {
+                    if !by_grantee.iter().any(|(answered, _)| *answered == grantee) {
+                        AUTHZ_GRANTS.remove(storage, (channel, &request.granter, &grantee));
+                    }
+                }
+            }
+            let mut events = vec![];
+            for (grantee, grants) in by_grantee {
+                events.extend(save_authz_grants(storage, channel, &request.granter, &grantee, grants)?);
+            }
+            Ok(events)
+        }
+        FEE_ALLOWANCE_QUERY_PATH => {
+            let Some(grant) = QueryAllowanceResponse::decode(response)?.allowance else {
+                return Ok(vec![]);
+            };
+            let mut allowance = FeeAllowance {
+                allowance: String::new(),
+                spend_limit: vec![],
+                expiration: None,
+                allowed_messages: vec![],
+                expiring: false,
+                updated_at: env.block.time,
+            };
+            if let Some(any) = &grant.allowance {
+                allowance.allowance = any.type_url.clone();
+                decode_allowance(any, &mut allowance)?;
+            }
+            allowance.expiring = expiring(allowance.expiration);
+
+            let key = (channel, grant.granter.as_str(), grant.grantee.as_str());
+            let old = FEE_ALLOWANCES.may_load(storage, key)?;
+            FEE_ALLOWANCES.save(storage, key, &allowance)?;
+            let warned = old.is_some_and(|old| old.expiring && old.expiration == allowance.expiration);
+            if !allowance.expiring || warned {
+                return Ok(vec![]);
+            }
+            Ok(vec![grant_expiring(channel, GrantKind::FeeAllowance, &grant.granter, &grant.grantee, &allowance.allowance, allowance.expiration)])
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+/// Most account types embed a base account as their first field, vesting
+/// accounts one level deeper.
+fn record_account(storage: &mut dyn Storage, env: &Env, channel: &str, address: &str, account: &Any, height: u64) -> Result<(), ContractError> {
+    let base = if account.type_url == BASE_ACCOUNT_TYPE {
+        Some(BaseAccount::decode(account.value.as_slice())?)
+    } else if account.type_url.starts_with(VESTING_ACCOUNT_PREFIX) {
+        DelayedVestingAccount::decode(account.value.as_slice())?
+            .base_vesting_account
+            .and_then(|vesting| vesting.base_account)
+    } else {
+        ModuleAccount::decode(account.value.as_slice())?.base_account
+    };
+    let base = base.unwrap_or_default();
+
+    REMOTE_ACCOUNTS.save(storage, (channel, address), &RemoteAccount {
+        account_type: account.type_url.clone(),
+        account_number: base.account_number,
+        sequence: base.sequence,
+        height,
+        updated_at: env.block.time,
+    })?;
+    Ok(())
+}
+
+fn authz_grant(
+    env: &Env,
+    authorization: Option<Any>,
+    expiration: Option<ProtoTimestamp>,
+    expiring: &dyn Fn(Option<Timestamp>) -> bool,
+) -> Result<AuthzGrant, ContractError> {
+    let authorization = authorization.unwrap_or_default();
+    let msg_type_url = if authorization.type_url == GENERIC_AUTHORIZATION_TYPE {
+        Some(GenericAuthorization::decode(authorization.value.as_slice())?.msg)
+    } else {
+        None
+    };
+    let expiration = expiration.as_ref().and_then(block_time);
+    Ok(AuthzGrant {
+        authorization: authorization.type_url,
+        msg_type_url,
+        expiration,
+        expiring: expiring(expiration),
+        updated_at: env.block.time,
+    })
+}
+
+fn save_authz_grants(storage: &mut dyn Storage, channel: &str, granter: &str, grantee: &str, grants: Vec<AuthzGrant>) -> Result<Vec<Event>, ContractError> {
+    let old = AUTHZ_GRANTS.may_load(storage, (channel, granter, grantee))?.unwrap_or_default();
+    if grants.is_empty() {
+        AUTHZ_GRANTS.remove(storage, (channel, granter, grantee));
+    } else {
+        AUTHZ_GRANTS.save(storage, (channel, granter, grantee), &grants)?;
+    }
+
+    let mut events = vec![];
+    for grant in grants.iter().filter(|grant| grant.expiring) {
+        let warned = old.iter().any(|old| {
+            old.expiring
+                && old.authorization == grant.authorization
+                && old.msg_type_url == grant.msg_type_url
+                && old.expiration == grant.expiration
+        });
+        if !warned {
+            let type_url = grant.msg_type_url.as_ref().unwrap_or(&grant.authorization);
+            events.push(grant_expiring(channel, GrantKind::Authz, granter, grantee, type_url, grant.expiration));
+        }
+    }
+    Ok(events)
+}
+
+fn decode_allowance(any: &Any, allowance: &mut FeeAllowance) -> Result<(), ContractError> {
+    let basic = match any.type_url.as_str() {
+        BASIC_ALLOWANCE_TYPE => Some(BasicAllowance::decode(any.value.as_slice())?),
+        PERIODIC_ALLOWANCE_TYPE => PeriodicAllowance::decode(any.value.as_slice())?.basic,
+        ALLOWED_MSG_ALLOWANCE_TYPE => {
+            let allowed = AllowedMsgAllowance::decode(any.value.as_slice())?;
+            allowance.allowed_messages = allowed.allowed_messages;
+            if let Some(inner) = &allowed.allowance {
+                decode_allowance(inner, allowance)?;
+            }
+            None
+        }
+        _ => None,
+    };
+    if let Some(basic) = basic {
+        allowance.spend_limit = basic
+            .spend_limit
+            .into_iter()
+            .map(|coin| Ok(Coin::new(coin.amount.parse::<Uint128>()?, coin.denom)))
+            .collect::<StdResult<_>>()?;
+        allowance.expiration = basic.expiration.as_ref().and_then(block_time);
+    }
+    Ok(())
+}
+
+fn grant_expiring(channel: &str, kind: GrantKind, granter: &str, grantee: &str, type_url: &str, expiration: Option<Timestamp>) -> Event {
+    let kind = match kind {
+        GrantKind::Authz => "authz",
+        GrantKind::FeeAllowance => "fee_allowance",
+    };
+    Event::new("grant_expiring")
+        .add_attribute("channel", channel)
+        .add_attribute("kind", kind)
+        .add_attribute("granter", granter)
+        .add_attribute("grantee", grantee)
+        .add_attribute("type_url", type_url)
+        .add_attribute("expiration", expiration.unwrap_or_default().to_string())
+}
+
+pub fn query_remote_account(deps: Deps, chain: String, address: String) -> Result<RemoteAccount, ContractError> {
+    let channel = resolve_chain(deps.storage, &chain)?;
+    REMOTE_ACCOUNTS
+        .may_load(deps.storage, (&channel, &address))?
+        .ok_or(ContractError::NoSuchRemoteAccount { chain, address })
+}
+
+pub fn query_authz_grants(deps: Deps, chain: String, granter: String, grantee: Option<String>) -> Result<Vec<(String, Vec<AuthzGrant>)>, ContractError> {
+    let channel = resolve_chain(deps.storage, &chain)?;
+    if let Some(grantee) = grantee {
+        let grants = AUTHZ_GRANTS.may_load(deps.storage, (&channel, &granter, &grantee))?;
+        return Ok(grants.map(|grants| (grantee, grants)).into_iter().collect());
+    }
+    let grants = AUTHZ_GRANTS
+        .prefix((&channel, &granter))
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    Ok(grants)
+}
+
+pub fn query_fee_allowance(deps: Deps, chain: String, granter: String, grantee: String) -> Result<FeeAllowance, ContractError> {
+    let channel = resolve_chain(deps.storage, &chain)?;
+    FEE_ALLOWANCES
+        .may_load(deps.storage, (&channel, &granter, &grantee))?
+        .ok_or(ContractError::NoSuchFeeAllowance { chain, granter, grantee })
+}
+
+pub fn query_expiring_grants(deps: Deps, env: Env, chain: String, within_seconds: Option<u64>) -> Result<Vec<ExpiringGrant>, ContractError> {
+    let channel = resolve_chain(deps.storage, &chain)?;
+    let within_seconds = match within_seconds {
+        Some(within_seconds) => within_seconds,
+        None => CONFIG.load(deps.storage)?.grant_expiry_warning_seconds,
+    };
+    let deadline = env.block.time.plus_seconds(within_seconds);
+
+    let mut expiring = vec![];
+    for item in AUTHZ_GRANTS.sub_prefix(&channel).range(deps.storage, None, None, Order::Ascending) {
+        let ((granter, grantee), grants) = item?;
+        for grant in grants {
+            if let Some(expiration) = grant.expiration.filter(|expiration| *expiration <= deadline) {
+                expiring.push(ExpiringGrant {
+                    kind: GrantKind::Authz,
+                    granter: granter.clone(),
+                    grantee: grantee.clone(),
+                    type_url: grant.authorization,
+                    msg_type_url: grant.msg_type_url,
+                    expiration,
+                });
+            }
+        }
+    }
+    for item in FEE_ALLOWANCES.sub_prefix(&channel).range(deps.storage, None, None, Order::Ascending) {
+        let ((granter, grantee), allowance) = item?;
+        if let Some(expiration) = allowance.expiration.filter(|expiration| *expiration <= deadline) {
+            expiring.push(ExpiringGrant {
+                kind: GrantKind::FeeAllowance,
+                granter,
+                grantee,
+                type_url: allowance.allowance,
+                msg_type_url: None,
+                expiration,
+            });
+        }
+    }
+    Ok(expiring)
+}
@@ -0,0 +1,78 @@
+use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::AbciQueryResponse;
+use cosmwasm_std::{Binary, Deps, DepsMut, Empty, Env, from_json, MessageInfo, Order, Response, StdResult, to_json_binary};
+use prost::Message;
+
+use crate::ack::Ack;
+use crate::contract::ensure_admin;
+use crate::error::ContractError;
+use crate::msg::{CosmosQuery, CosmosResponse, CosmosResponsePacket, InterchainQueryPacketData};
+use crate::state::HOST_QUERY_PATHS;
+
+/// Answers every request of an incoming ICQ packet against this chain and
+/// returns the success ack. Fails without answering anything if the packet
+/// is malformed or any request is not allowed, like the async-icq host module.
+pub fn answer_queries(deps: Deps, env: &Env, channel: &str, data: &Binary) -> Result<Binary, ContractError> {
+    let packet_data: InterchainQueryPacketData = from_json(data)?;
+    let query = CosmosQuery::decode(packet_data.data.as_slice())?;
+
+    for req in &query.requests {
+        if !HOST_QUERY_PATHS.has(deps.storage, &req.path) {
+            return Err(ContractError::QueryPathNotAllowed { channel: channel.to_string(), path: req.path.clone() });
+        }
+        if req.height != 0 || req.prove {
+            return Err(ContractError::UnsupportedQuery {
+                path: req.path.clone(),
+                reason: "only the latest height without proof can be queried".to_string(),
+            });
+        }
+    }
+
+    let responses = query
+        .requests
+        .into_iter()
+        .map(|req| {
+            let value = deps.querier.query_grpc(req.path, Binary::from(req.data))?;
+            Ok(AbciQueryResponse {
+                value: value.to_vec(),
+                height: env.block.height as i64,
+                ..Default::default()
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    let packet = CosmosResponsePacket { data: CosmosResponse { responses }.encode_to_vec().into() };
+    Ok(to_json_binary(&Ack::Result(to_json_binary(&packet)?))?)
+}
+
+pub fn allow_host_query_paths(deps: DepsMut, info: MessageInfo, paths: Vec<String>) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+
+    for path in &paths {
+        if !path.starts_with('/') {
+            return Err(ContractError::InvalidQueryPath { path: path.clone() });
+        }
+        HOST_QUERY_PATHS.save(deps.storage, path, &Empty {})?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "allow_host_query_paths")
+        .add_attribute("paths", paths.join(",")))
+}
+
+pub fn disallow_host_query_paths(deps: DepsMut, info: MessageInfo, paths: Vec<String>) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+
+    for path in &paths {
+        HOST_QUERY_PATHS.remove(deps.storage, path);
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "disallow_host_query_paths")
+        .add_attribute("paths", paths.join(",")))
+}
+
+pub fn query_host_query_paths(deps: Deps) -> StdResult<Vec<String>> {
+    HOST_QUERY_PATHS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect()
+}
@@ -1,13 +1,31 @@
-use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::AbciQueryResponse;
-use cosmwasm_std::{Binary, DepsMut, Env, from_json, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcOrder, IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse};
+use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::{AbciQueryRequest, AbciQueryResponse};
+use cosmos_sdk_proto::cosmos::bank::v1beta1::{QueryBalanceRequest, QueryBalanceResponse, QueryDenomMetadataResponse, QuerySupplyOfRequest};
+use cosmos_sdk_proto::cosmos::slashing::v1beta1::QuerySigningInfoResponse;
+use cosmos_sdk_proto::cosmos::staking::v1beta1::QueryValidatorResponse;
+use cosmwasm_std::{Binary, DepsMut, Env, from_json, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcOrder, IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, Storage, Uint128};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use prost::Message;
 
 use crate::{ContractError, error::Never};
-use crate::ack::{Ack, make_ack_success};
-use crate::msg::{ArithmeticTwapToNowResponse, CosmosResponse, CosmosResponsePacket, InterchainQueryPacketAck};
-use crate::state::{CHANNEL_INFO, ChannelInfo, ICQ_ERRORS, ICQ_PRICE_RESPONSES, LAST_SEQUENCE_ACKNOWLEDGMENT};
+use crate::ack::{Ack, make_ack_fail};
+use crate::batch::record_response_heights;
+use crate::conditions::evaluate_conditions;
+use crate::contract_watch::{evaluate_code_info, evaluate_contract_info};
+use crate::denom_trace::{record_denom_hash, record_denom_trace};
+use crate::gov::record_proposals;
+use crate::grants::record_grant_response;
+use crate::validator_watch::{evaluate_signing_info, evaluate_validator};
+use crate::host::answer_queries;
+use crate::portfolio::{record_balance, record_denom_metadata};
+use crate::price::record_twap;
+use crate::proof::record_proven;
+use crate::reconcile::record_supply;
+use crate::requests::complete_request;
+use crate::wasm::record_wasm_result;
+use crate::watch::evaluate_watches;
+use crate::msg::{ArithmeticTwapToNowRequest, ArithmeticTwapToNowResponse, AUTH_ACCOUNT_QUERY_PATH, AUTHZ_GRANTER_GRANTS_QUERY_PATH, AUTHZ_GRANTS_QUERY_PATH, BALANCE_QUERY_PATH, CosmosQuery, CosmosResponse, CosmosResponsePacket, DENOM_HASH_QUERY_PATH, DENOM_METADATA_QUERY_PATH, DENOM_TRACE_QUERY_PATH, FEE_ALLOWANCE_QUERY_PATH, GOV_PROPOSAL_QUERY_PATH, GOV_PROPOSALS_QUERY_PATH, InterchainQueryPacketAck, InterchainQueryPacketData, ProtoCoin, QueryCodeInfoResponse, SLASHING_SIGNING_INFO_QUERY_PATH, STAKING_VALIDATOR_QUERY_PATH, SUPPLY_OF_QUERY_PATH, TWAP_QUERY_PATH, WASM_CODE_INFO_QUERY_PATH, WASM_CONTRACT_INFO_QUERY_PATH, WASM_RAW_QUERY_PATH, WASM_SMART_QUERY_PATH};
+use crate::state::{CHANNEL_INFO, ChannelInfo, ICQ_ERRORS, ICQ_PRICE_RESPONSES, ICQ_RAW_RESPONSES, ICQ_RESPONSES, LAST_SEQUENCE_ACKNOWLEDGMENT, LAST_SEQUENCE_RECEIVE, RESULT_CACHE, WasmQueryData};
 
 pub const IBC_VERSION: &str = "icq-1";
 
@@ -59,48 +77,67 @@ pub fn ibc_channel_close(
         .add_attribute("channel", channel))
 }
 
+/// Answers the queries of the counterparty against this chain. Anything that
+/// cannot be answered is reported in an error ack rather than failing the
+/// transaction, so the packet is not left unacknowledged.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn ibc_packet_receive(
-    _deps: DepsMut,
-    _env: Env,
-    _msg: IbcPacketReceiveMsg,
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
 ) -> Result<IbcReceiveResponse, Never> {
-    Ok(IbcReceiveResponse::new(make_ack_success()).add_attribute("method", "ibc_packet_receive"))
+    let packet = msg.packet;
+    // a storage error here would only fail the bookkeeping, not the answer
+    let _ = LAST_SEQUENCE_RECEIVE.save(deps.storage, &packet.sequence);
+
+    let res = match answer_queries(deps.as_ref(), &env, &packet.dest.channel_id, &packet.data) {
+        Ok(ack) => IbcReceiveResponse::new(ack).add_attribute("success", "true"),
+        Err(err) => IbcReceiveResponse::new(make_ack_fail(err.to_string()))
+            .add_attribute("success", "false")
+            .add_attribute("error", err.to_string()),
+    };
+    Ok(res
+        .add_attribute("method", "ibc_packet_receive")
+        .add_attribute("sequence", packet.sequence.to_string()))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn ibc_packet_ack(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     msg: IbcPacketAckMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
     LAST_SEQUENCE_ACKNOWLEDGMENT.save(deps.storage, &msg.original_packet.sequence)?;
 
     let icq_msg: Ack = from_json(&msg.acknowledgement.data)?;
     match icq_msg {
-        Ack::Result(_) => on_packet_success(deps, msg.acknowledgement.data, msg.original_packet),
+        Ack::Result(_) => on_packet_success(deps, env, msg.acknowledgement.data, msg.original_packet),
         Ack::Error(error) => {
             ICQ_ERRORS.save(deps.storage, msg.original_packet.sequence, &error)?;
+            let events = complete_request(deps.storage, &msg.original_packet, None, "error", env.block.time)?;
             Ok(IbcBasicResponse::new()
                    // .set_ack(make_ack_fail(error.to_string()))
                    .add_attribute("method", "ibc_packet_ack")
                    .add_attribute("error", error.to_string())
-                   .add_attribute("sequence", msg.original_packet.sequence.to_string()))
+                   .add_attribute("sequence", msg.original_packet.sequence.to_string())
+                   .add_events(events))
         }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn ibc_packet_timeout(
-    _deps: DepsMut,
-    _env: Env,
-    _msg: IbcPacketTimeoutMsg,
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketTimeoutMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
-    // As with ack above, nothing to do here. If we cared about
-    // keeping track of state between the two chains then we'd want to
-    // respond to this likely as it means that the packet in question
-    // isn't going anywhere.
-    Ok(IbcBasicResponse::new().add_attribute("method", "ibc_packet_timeout"))
+    // The packet isn't going anywhere, release the request so the next
+    // identical query sends a fresh packet instead of joining this one.
+    let events = complete_request(deps.storage, &msg.packet, None, "timeout", env.block.time)?;
+    Ok(IbcBasicResponse::new()
+        .add_attribute("method", "ibc_packet_timeout")
+        .add_attribute("sequence", msg.packet.sequence.to_string())
+        .add_events(events))
 }
 
 pub fn validate_order_and_version(
@@ -141,25 +178,182 @@ pub fn validate_order_and_version(
     Ok(())
 }
 
-// update the balance stored on this (channel, denom) index
-fn on_packet_success(deps: DepsMut, result: Binary, packet: IbcPacket) -> Result<IbcBasicResponse, ContractError> {
+// store every response of the packet according to the path it answers
+fn on_packet_success(mut deps: DepsMut, env: Env, result: Binary, packet: IbcPacket) -> Result<IbcBasicResponse, ContractError> {
     let ack_data: InterchainQueryPacketAck = from_json(&result)?;
 
     let cosmos_response: CosmosResponsePacket = from_json(&ack_data.result)?;
 
     let query_responses: CosmosResponse = CosmosResponse::decode(cosmos_response.data.as_slice())?;
 
-    if let Some(first_response) = get_first_response_safe(&query_responses) {
-        let price_response: ArithmeticTwapToNowResponse = ArithmeticTwapToNowResponse::decode(first_response.value.as_slice())?;
-        ICQ_PRICE_RESPONSES.save(deps.storage, packet.sequence, &price_response.arithmetic_twap)?;
+    // the original packet tells us which path each response belongs to
+    let packet_data: InterchainQueryPacketData = from_json(&packet.data)?;
+    let query: CosmosQuery = CosmosQuery::decode(packet_data.data.as_slice())?;
+
+    let channel = &packet.src.channel_id;
+    let mut res = IbcBasicResponse::new();
+    let mut prices_updated = false;
+    let mut unrecorded = vec![];
+    for (request, response) in query.requests.iter().zip(query_responses.responses.iter()) {
+        if response.code != 0 {
+            ICQ_ERRORS.save(deps.storage, packet.sequence, &response.log)?;
+            continue;
+        }
+        // a response that cannot be recorded must not fail the ack, which
+        // would leave the packet to be relayed forever
+        match record_response(deps.branch(), &env, channel, packet.sequence, request, response) {
+            Ok(recorded) => {
+                prices_updated |= !request.prove && request.height == 0 && request.path == TWAP_QUERY_PATH;
+                res = res.add_events(recorded.events).add_submessages(recorded.messages);
+            }
+            Err(err) => {
+                ICQ_ERRORS.save(deps.storage, packet.sequence, &err.to_string())?;
+                res = res.add_attribute("error", err.to_string());
+                unrecorded.push(request);
+            }
+        }
     }
 
-    Ok(IbcBasicResponse::new()
+    if prices_updated {
+        let (events, msgs) = evaluate_conditions(deps.storage, &env)?;
+        res = res.add_events(events).add_submessages(msgs);
+    }
+
+    // before completing the request, which forgets whether it was a batch
+    if let Some(event) = record_response_heights(deps.storage, &env, &packet, &query.requests, &query_responses.responses)? {
+        res = res.add_event(event);
+    }
+
+    let events = complete_request(deps.storage, &packet, Some(&query_responses), "success", env.block.time)?;
+    // so that asking again queries the host instead of failing on the cache
+    for request in unrecorded {
+        RESULT_CACHE.remove(deps.storage, (channel, &request.path, &request.data));
+    }
+
+    Ok(res
         .add_attribute("method", "ibc_packet_ack")
         .add_attribute("sequence", packet.sequence.to_string())
+        .add_events(events)
     )
 }
 
-fn get_first_response_safe(cosmos_response: &CosmosResponse) -> Option<&AbciQueryResponse> {
-    cosmos_response.responses.first()
-}
\ No newline at end of file
+/// Records the response to one request of a packet, returning the events and
+/// messages it leads to.
+fn record_response(
+    mut deps: DepsMut,
+    env: &Env,
+    channel: &str,
+    sequence: u64,
+    request: &AbciQueryRequest,
+    response: &AbciQueryResponse,
+) -> Result<IbcBasicResponse, ContractError> {
+    let mut res = IbcBasicResponse::new();
+    if request.prove {
+        let event = record_proven(deps.branch(), env, channel, sequence, request, response)?;
+        return Ok(res.add_event(event));
+    }
+    if request.height != 0 {
+        record_pinned(deps.storage, env, channel, sequence, request, response)?;
+        return Ok(res);
+    }
+    match request.path.as_str() {
+        BALANCE_QUERY_PATH => {
+            let balance_response = QueryBalanceResponse::decode(response.value.as_slice())?;
+            let balance_request = QueryBalanceRequest::decode(request.data.as_slice())?;
+            if let Some(coin) = balance_response.balance {
+                let coin = ProtoCoin { denom: coin.denom, amount: coin.amount };
+                ICQ_RESPONSES.save(deps.storage, sequence, &coin)?;
+
+                if let Ok(amount) = coin.amount.parse::<Uint128>() {
+                    let (events, callbacks) = evaluate_watches(deps.storage, channel, &balance_request.address, &balance_request.denom, amount)?;
+                    res = res.add_events(events).add_submessages(callbacks);
+                }
+                record_balance(deps.storage, channel, &balance_request, coin, response.height.max(0) as u64, env.block.time)?;
+            }
+        }
+        DENOM_TRACE_QUERY_PATH => record_denom_trace(deps.storage, channel, &request.data, &response.value)?,
+        DENOM_HASH_QUERY_PATH => record_denom_hash(deps.storage, channel, &request.data, &response.value)?,
+        AUTH_ACCOUNT_QUERY_PATH | AUTHZ_GRANTS_QUERY_PATH | AUTHZ_GRANTER_GRANTS_QUERY_PATH | FEE_ALLOWANCE_QUERY_PATH => {
+            let events = record_grant_response(deps.storage, env, channel, &request.path, &request.data, &response.value, response.height.max(0) as u64)?;
+            res = res.add_events(events);
+        }
+        STAKING_VALIDATOR_QUERY_PATH => {
+            if let Some(validator) = QueryValidatorResponse::decode(response.value.as_slice())?.validator {
+                let (events, msgs) = evaluate_validator(deps.branch(), env, channel, &validator)?;
+                res = res.add_events(events).add_submessages(msgs);
+            }
+        }
+        SLASHING_SIGNING_INFO_QUERY_PATH => {
+            if let Some(info) = QuerySigningInfoResponse::decode(response.value.as_slice())?.val_signing_info {
+                let events = evaluate_signing_info(deps.storage, env, channel, &info)?;
+                res = res.add_events(events);
+            }
+        }
+        GOV_PROPOSAL_QUERY_PATH | GOV_PROPOSALS_QUERY_PATH => {
+            let events = record_proposals(deps.storage, env, channel, &request.path, &response.value, response.height.max(0) as u64)?;
+            res = res.add_events(events);
+        }
+        DENOM_METADATA_QUERY_PATH => {
+            let metadata_response = QueryDenomMetadataResponse::decode(response.value.as_slice())?;
+            if let Some(metadata) = metadata_response.metadata {
+                record_denom_metadata(deps.storage, channel, &metadata)?;
+            }
+        }
+        SUPPLY_OF_QUERY_PATH => {
+            let supply_request = QuerySupplyOfRequest::decode(request.data.as_slice())?;
+            let event = record_supply(deps.branch(), env, channel, &supply_request.denom, &response.value, response.height.max(0) as u64)?;
+            res = res.add_events(event);
+        }
+        WASM_SMART_QUERY_PATH | WASM_RAW_QUERY_PATH | WASM_CONTRACT_INFO_QUERY_PATH => {
+            let result = record_wasm_result(deps.storage, env, channel, sequence, request, response)?;
+            if let WasmQueryData::ContractInfo(info) = &result.data {
+                let (events, msgs) = evaluate_contract_info(deps.branch(), env, channel, &result.contract, info)?;
+                res = res.add_events(events).add_submessages(msgs);
+            }
+        }
+        WASM_CODE_INFO_QUERY_PATH => {
+            let code_info = QueryCodeInfoResponse::decode(response.value.as_slice())?;
+            let (events, callbacks) = evaluate_code_info(deps.storage, env, channel, &code_info)?;
+            res = res.add_events(events).add_submessages(callbacks);
+        }
+        TWAP_QUERY_PATH => {
+            let price_response: ArithmeticTwapToNowResponse = ArithmeticTwapToNowResponse::decode(response.value.as_slice())?;
+            ICQ_PRICE_RESPONSES.save(deps.storage, sequence, &price_response.arithmetic_twap)?;
+
+            let twap_request = ArithmeticTwapToNowRequest::decode(request.data.as_slice())?;
+            let event = record_twap(deps.storage, env, channel, &twap_request, &price_response.arithmetic_twap, sequence)?;
+            res = res.add_event(event);
+        }
+        _ => ICQ_RAW_RESPONSES.save(deps.storage, sequence, &Binary::from(response.value.clone()))?,
+    }
+    Ok(res)
+}
+
+/// Records the response to a request pinned to a past height under its
+/// sequence only, as the prices, balances and watches kept of the host follow
+/// its latest state and must not move back to an older one.
+fn record_pinned(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel: &str,
+    sequence: u64,
+    request: &AbciQueryRequest,
+    response: &AbciQueryResponse,
+) -> Result<(), ContractError> {
+    match request.path.as_str() {
+        BALANCE_QUERY_PATH => {
+            if let Some(coin) = QueryBalanceResponse::decode(response.value.as_slice())?.balance {
+                ICQ_RESPONSES.save(storage, sequence, &ProtoCoin { denom: coin.denom, amount: coin.amount })?;
+            }
+        }
+        TWAP_QUERY_PATH => {
+            let price_response = ArithmeticTwapToNowResponse::decode(response.value.as_slice())?;
+            ICQ_PRICE_RESPONSES.save(storage, sequence, &price_response.arithmetic_twap)?;
+        }
+        WASM_SMART_QUERY_PATH | WASM_RAW_QUERY_PATH | WASM_CONTRACT_INFO_QUERY_PATH => {
+            record_wasm_result(storage, env, channel, sequence, request, response)?;
+        }
+        _ => ICQ_RAW_RESPONSES.save(storage, sequence, &Binary::from(response.value.clone()))?,
+    }
+    Ok(())
+}
@@ -0,0 +1,366 @@
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+pub const IAVL_PROOF_TYPE: &str = "ics23:iavl";
+pub const SIMPLE_PROOF_TYPE: &str = "ics23:simple";
+
+const HASH_OP_NO_HASH: i32 = 0;
+const HASH_OP_SHA256: i32 = 1;
+
+const LENGTH_OP_NO_PREFIX: i32 = 0;
+const LENGTH_OP_VAR_PROTO: i32 = 1;
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CommitmentProof {
+    #[prost(oneof = "commitment_proof::Proof", tags = "1, 2, 3, 4")]
+    pub proof: Option<commitment_proof::Proof>,
+}
+
+pub mod commitment_proof {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Proof {
+        #[prost(message, tag = "1")]
+        Exist(super::ExistenceProof),
+        #[prost(message, tag = "2")]
+        Nonexist(super::NonExistenceProof),
+        /// the remaining proof kinds are only decoded to be rejected
+        #[prost(bytes, tag = "3")]
+        Batch(Vec<u8>),
+        #[prost(bytes, tag = "4")]
+        Compressed(Vec<u8>),
+    }
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExistenceProof {
+    #[prost(bytes = "vec", tag = "1")]
+    pub key: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub value: Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    pub leaf: Option<LeafOp>,
+    #[prost(message, repeated, tag = "4")]
+    pub path: Vec<InnerOp>,
+}
+
+/// Proves a key is absent by proving the keys right before and after it,
+/// either being left out at the edges of the tree.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NonExistenceProof {
+    #[prost(bytes = "vec", tag = "1")]
+    pub key: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub left: Option<ExistenceProof>,
+    #[prost(message, optional, tag = "3")]
+    pub right: Option<ExistenceProof>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LeafOp {
+    #[prost(int32, tag = "1")]
+    pub hash: i32,
+    #[prost(int32, tag = "2")]
+    pub prehash_key: i32,
+    #[prost(int32, tag = "3")]
+    pub prehash_value: i32,
+    #[prost(int32, tag = "4")]
+    pub length: i32,
+    #[prost(bytes = "vec", tag = "5")]
+    pub prefix: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InnerOp {
+    #[prost(int32, tag = "1")]
+    pub hash: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub prefix: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub suffix: Vec<u8>,
+}
+
+/// The parts of an ICS-23 `ProofSpec` that constrain existence proofs.
+struct ProofSpec {
+    leaf: LeafOp,
+    child_size: usize,
+    /// number of children of an inner node
+    children: usize,
+    min_prefix_length: usize,
+    max_prefix_length: usize,
+    /// whether the ops must also follow the IAVL node encoding
+    iavl: bool,
+}
+
+fn leaf_spec() -> LeafOp {
+    LeafOp {
+        hash: HASH_OP_SHA256,
+        prehash_key: HASH_OP_NO_HASH,
+        prehash_value: HASH_OP_SHA256,
+        length: LENGTH_OP_VAR_PROTO,
+        prefix: vec![0],
+    }
+}
+
+fn iavl_spec() -> ProofSpec {
+    ProofSpec { leaf: leaf_spec(), child_size: 33, children: 2, min_prefix_length: 4, max_prefix_length: 12, iavl: true }
+}
+
+fn tendermint_spec() -> ProofSpec {
+    ProofSpec { leaf: leaf_spec(), child_size: 32, children: 2, min_prefix_length: 1, max_prefix_length: 1, iavl: false }
+}
+
+fn hash(op: i32, data: &[u8]) -> Result<Vec<u8>, String> {
+    match op {
+        HASH_OP_NO_HASH => Ok(data.to_vec()),
+        HASH_OP_SHA256 => Ok(Sha256::digest(data).to_vec()),
+        _ => Err(format!("unsupported hash op {op}")),
+    }
+}
+
+fn length_prefixed(op: i32, data: Vec<u8>) -> Result<Vec<u8>, String> {
+    match op {
+        LENGTH_OP_NO_PREFIX => Ok(data),
+        LENGTH_OP_VAR_PROTO => {
+            let mut prefixed = Vec::with_capacity(data.len() + 10);
+            prost::encoding::encode_varint(data.len() as u64, &mut prefixed);
+            prefixed.extend(data);
+            Ok(prefixed)
+        }
+        _ => Err(format!("unsupported length op {op}")),
+    }
+}
+
+/// Checks that the prefix of an op at `layer` of the tree, the leaf being at
+/// layer 0, is an IAVL node header: the zigzag varint height, size and
+/// version of the node, followed for an inner node by the length of the left
+/// child hash, and the left child itself when the proven node is on the right.
+fn check_iavl_prefix(prefix: &[u8], hash: i32, layer: usize) -> Result<(), String> {
+    let mut rest = prefix;
+    let mut values = [0i64; 3];
+    for value in &mut values {
+        let varint = prost::encoding::decode_varint(&mut rest).map_err(|err| format!("invalid IAVL node header: {err}"))?;
+        *value = ((varint >> 1) as i64) ^ -((varint & 1) as i64);
+        if *value < 0 {
+            return Err(format!("negative value {value} in IAVL node header"));
+        }
+    }
+    if values[0] < layer as i64 {
+        return Err(format!("IAVL node of height {} at layer {layer}", values[0]));
+    }
+    if layer == 0 {
+        if !rest.is_empty() {
+            return Err(format!("IAVL leaf header followed by {} bytes", rest.len()));
+        }
+    } else {
+        if rest.len() != 1 && rest.len() != 34 {
+            return Err(format!("IAVL inner node header followed by {} bytes", rest.len()));
+        }
+        if hash != HASH_OP_SHA256 {
+            return Err(format!("unsupported IAVL inner hash op {hash}"));
+        }
+    }
+    Ok(())
+}
+
+fn check_spec(proof: &ExistenceProof, spec: &ProofSpec) -> Result<(), String> {
+    let leaf = proof.leaf.as_ref().ok_or("existence proof without leaf")?;
+    if spec.iavl {
+        check_iavl_prefix(&leaf.prefix, leaf.hash, 0)?;
+    }
+    if leaf.hash != spec.leaf.hash
+        || leaf.prehash_key != spec.leaf.prehash_key
+        || leaf.prehash_value != spec.leaf.prehash_value
+        || leaf.length != spec.leaf.length
+    {
+        return Err("leaf ops do not match the proof spec".to_string());
+    }
+    if !leaf.prefix.starts_with(&spec.leaf.prefix) {
+        return Err("leaf prefix does not match the proof spec".to_string());
+    }
+
+    let max_prefix_length = (spec.children - 1) * spec.child_size + spec.max_prefix_length;
+    for (index, inner) in proof.path.iter().enumerate() {
+        if inner.hash != HASH_OP_SHA256 {
+            return Err(format!("unsupported inner hash op {}", inner.hash));
+        }
+        if spec.iavl {
+            check_iavl_prefix(&inner.prefix, inner.hash, index + 1)?;
+        }
+        // an inner node must never be mistaken for a leaf
+        if inner.prefix.starts_with(&spec.leaf.prefix) {
+            return Err("inner node has a leaf prefix".to_string());
+        }
+        if inner.prefix.len() < spec.min_prefix_length || inner.prefix.len() > max_prefix_length {
+            return Err(format!("inner prefix of {} bytes is out of spec", inner.prefix.len()));
+        }
+        if inner.suffix.len() % spec.child_size != 0 {
+            return Err(format!("inner suffix of {} bytes is out of spec", inner.suffix.len()));
+        }
+    }
+    Ok(())
+}
+
+/// Root committed to by `proof`.
+fn calculate_root(proof: &ExistenceProof) -> Result<Vec<u8>, String> {
+    let leaf = proof.leaf.as_ref().ok_or("existence proof without leaf")?;
+    if proof.key.is_empty() || proof.value.is_empty() {
+        return Err("existence proof of an empty key or value".to_string());
+    }
+
+    let mut preimage = leaf.prefix.clone();
+    preimage.extend(length_prefixed(leaf.length, hash(leaf.prehash_key, &proof.key)?)?);
+    preimage.extend(length_prefixed(leaf.length, hash(leaf.prehash_value, &proof.value)?)?);
+    let mut node = hash(leaf.hash, &preimage)?;
+
+    for inner in &proof.path {
+        let mut preimage = inner.prefix.clone();
+        preimage.extend(&node);
+        preimage.extend(&inner.suffix);
+        node = hash(inner.hash, &preimage)?;
+    }
+    Ok(node)
+}
+
+/// Whether `inner` is laid out as the child at `branch` of its parent, i.e.
+/// with `branch` siblings before it and the others after it.
+fn has_padding(inner: &InnerOp, spec: &ProofSpec, branch: usize) -> bool {
+    let min_prefix_length = branch * spec.child_size + spec.min_prefix_length;
+    let max_prefix_length = branch * spec.child_size + spec.max_prefix_length;
+    let suffix_length = (spec.children - 1 - branch) * spec.child_size;
+    (min_prefix_length..=max_prefix_length).contains(&inner.prefix.len()) && inner.suffix.len() == suffix_length
+}
+
+fn is_left_most(path: &[InnerOp], spec: &ProofSpec) -> bool {
+    path.iter().all(|inner| has_padding(inner, spec, 0))
+}
+
+fn is_right_most(path: &[InnerOp], spec: &ProofSpec) -> bool {
+    path.iter().all(|inner| has_padding(inner, spec, spec.children - 1))
+}
+
+fn branch_of(inner: &InnerOp, spec: &ProofSpec) -> Option<usize> {
+    (0..spec.children).find(|branch| has_padding(inner, spec, *branch))
+}
+
+/// Whether the leaves proven by `left` and `right`, both ordered from the
+/// leaf up, are next to each other: below the node where the paths part,
+/// the left one only takes right turns and the right one only left turns.
+fn is_left_neighbor(mut left: &[InnerOp], mut right: &[InnerOp], spec: &ProofSpec) -> bool {
+    loop {
+        let (Some((top_left, rest_left)), Some((top_right, rest_right))) = (left.split_last(), right.split_last()) else {
+            return false;
+        };
+        left = rest_left;
+        right = rest_right;
+        if top_left.prefix == top_right.prefix && top_left.suffix == top_right.suffix {
+            continue;
+        }
+        let (Some(left_branch), Some(right_branch)) = (branch_of(top_left, spec), branch_of(top_right, spec)) else {
+            return false;
+        };
+        return right_branch == left_branch + 1 && is_right_most(left, spec) && is_left_most(right, spec);
+    }
+}
+
+fn decode_proof(data: &[u8]) -> Result<commitment_proof::Proof, String> {
+    let proof = CommitmentProof::decode(data).map_err(|err| err.to_string())?;
+    match proof.proof {
+        Some(commitment_proof::Proof::Batch(_) | commitment_proof::Proof::Compressed(_)) => {
+            Err("only existence and non-existence proofs are supported".to_string())
+        }
+        Some(proof) => Ok(proof),
+        None => Err("empty commitment proof".to_string()),
+    }
+}
+
+/// Checks that `data`, an encoded `CommitmentProof`, proves `key` holds
+/// `value` under the spec, and returns the root it proves it against.
+fn verify_existence(data: &[u8], spec: &ProofSpec, key: &[u8], value: &[u8]) -> Result<Vec<u8>, String> {
+    let commitment_proof::Proof::Exist(exist) = decode_proof(data)? else {
+        return Err("expected an existence proof".to_string());
+    };
+    if exist.key != key {
+        return Err("proof is for another key".to_string());
+    }
+    if exist.value != value {
+        return Err("proof is for another value".to_string());
+    }
+    check_spec(&exist, spec)?;
+    calculate_root(&exist)
+}
+
+/// Checks that `data`, an encoded `CommitmentProof`, proves `key` is absent
+/// under the spec, by proving the keys next to it on either side, and
+/// returns the root it proves it against.
+fn verify_non_existence(data: &[u8], spec: &ProofSpec, key: &[u8]) -> Result<Vec<u8>, String> {
+    let commitment_proof::Proof::Nonexist(nonexist) = decode_proof(data)? else {
+        return Err("expected a non-existence proof".to_string());
+    };
+    if nonexist.key != key {
+        return Err("proof is for another key".to_string());
+    }
+
+    let mut roots = vec![];
+    for neighbor in [&nonexist.left, &nonexist.right].into_iter().flatten() {
+        check_spec(neighbor, spec)?;
+        roots.push(calculate_root(neighbor)?);
+    }
+    let root = match roots.as_slice() {
+        [] => return Err("non-existence proof without neighbors".to_string()),
+        [root] => root.clone(),
+        [left, right] if left == right => left.clone(),
+        _ => return Err("neighbors are proven against different roots".to_string()),
+    };
+
+    if nonexist.left.as_ref().is_some_and(|left| left.key.as_slice() >= key) {
+        return Err("left neighbor is not before the key".to_string());
+    }
+    if nonexist.right.as_ref().is_some_and(|right| right.key.as_slice() <= key) {
+        return Err("right neighbor is not after the key".to_string());
+    }
+    let adjacent = match (&nonexist.left, &nonexist.right) {
+        (None, Some(right)) => is_left_most(&right.path, spec),
+        (Some(left), None) => is_right_most(&left.path, spec),
+        (Some(left), Some(right)) => is_left_neighbor(&left.path, &right.path, spec),
+        (None, None) => false,
+    };
+    if !adjacent {
+        return Err("neighbors are not adjacent".to_string());
+    }
+    Ok(root)
+}
+
+/// A proof op as returned in the `proof_ops` of an ABCI query response.
+pub struct ProofOpRef<'a> {
+    pub r#type: &'a str,
+    pub key: &'a [u8],
+    pub data: &'a [u8],
+}
+
+/// Verifies the proof ops of a `prove: true` query of a Cosmos SDK store: an
+/// IAVL proof that `key` holds `value` in `store`, or that it is absent when
+/// `value` is empty, followed by a Tendermint simple merkle proof that the
+/// store root is committed to by `app_hash`. Batch and compressed proofs are
+/// not supported.
+pub fn verify_store_proof(ops: &[ProofOpRef], store: &str, key: &[u8], value: &[u8], app_hash: &[u8]) -> Result<(), String> {
+    let [iavl, simple] = ops else {
+        return Err(format!("expected 2 proof ops, got {}", ops.len()));
+    };
+    if iavl.r#type != IAVL_PROOF_TYPE || simple.r#type != SIMPLE_PROOF_TYPE {
+        return Err(format!("unexpected proof op types {} and {}", iavl.r#type, simple.r#type));
+    }
+    if iavl.key != key || simple.key != store.as_bytes() {
+        return Err("proof ops are for another key or store".to_string());
+    }
+
+    let store_root = if value.is_empty() {
+        verify_non_existence(iavl.data, &iavl_spec(), key)?
+    } else {
+        verify_existence(iavl.data, &iavl_spec(), key, value)?
+    };
+    let root = verify_existence(simple.data, &tendermint_spec(), store.as_bytes(), &store_root)?;
+    if root != app_hash {
+        return Err("proof does not match the app hash".to_string());
+    }
+    Ok(())
+}
@@ -1,8 +1,30 @@
 pub mod ack;
+pub mod batch;
+pub mod conditions;
 pub mod contract;
+pub mod contract_watch;
+pub mod denom_trace;
 mod error;
+pub mod feeds;
+pub mod fees;
+pub mod gov;
+pub mod grants;
+pub mod host;
 pub mod ibc;
+pub mod ics23;
 pub mod msg;
+pub mod pause;
+pub mod portfolio;
+pub mod price;
+pub mod proof;
+pub mod rate_limit;
+pub mod reconcile;
+pub mod requests;
+pub mod schedule;
 pub mod state;
+pub mod validation;
+pub mod validator_watch;
+pub mod wasm;
+pub mod watch;
 
 pub use crate::error::ContractError;
@@ -1,16 +1,239 @@
 use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::{AbciQueryRequest, AbciQueryResponse};
-use cosmwasm_schema::{cw_serde};
+use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_schema::schemars::JsonSchema;
 use cosmwasm_schema::serde::{Deserialize, Serialize};
-use cosmwasm_std::{Binary};
+use cosmwasm_std::{Binary, Coin, CosmosMsg, Decimal256, Int256, Timestamp as BlockTimestamp, Uint128};
+
+use crate::state::{AuthzGrant, BalanceSample, BatchResult, Comparison, Condition, Config, ContractWatch, DenomTrace, FeeAllowance, Holding, PendingRequest, Portfolio, PriceBounds, PriceFeed, PriceRecord, ProposalRecord, ProposalStatus, ProvenResult, QuarantinedPrice, Reconciliation, RateLimit, RemoteAccount, Subscription, ValidatorWatch, WasmQueryResult, Watch, WatchCallback, WatchDirection};
+
+pub const BALANCE_QUERY_PATH: &str = "/cosmos.bank.v1beta1.Query/Balance";
+
+pub const DENOM_METADATA_QUERY_PATH: &str = "/cosmos.bank.v1beta1.Query/DenomMetadata";
+
+pub const SUPPLY_OF_QUERY_PATH: &str = "/cosmos.bank.v1beta1.Query/SupplyOf";
+
+pub const WASM_SMART_QUERY_PATH: &str = "/cosmwasm.wasm.v1.Query/SmartContractState";
+
+pub const WASM_RAW_QUERY_PATH: &str = "/cosmwasm.wasm.v1.Query/RawContractState";
+
+pub const WASM_CONTRACT_INFO_QUERY_PATH: &str = "/cosmwasm.wasm.v1.Query/ContractInfo";
+
+pub const DENOM_TRACE_QUERY_PATH: &str = "/ibc.applications.transfer.v1.Query/DenomTrace";
+
+pub const DENOM_HASH_QUERY_PATH: &str = "/ibc.applications.transfer.v1.Query/DenomHash";
+
+pub const AUTH_ACCOUNT_QUERY_PATH: &str = "/cosmos.auth.v1beta1.Query/Account";
+
+pub const AUTHZ_GRANTS_QUERY_PATH: &str = "/cosmos.authz.v1beta1.Query/Grants";
+
+pub const AUTHZ_GRANTER_GRANTS_QUERY_PATH: &str = "/cosmos.authz.v1beta1.Query/GranterGrants";
+
+pub const FEE_ALLOWANCE_QUERY_PATH: &str = "/cosmos.feegrant.v1beta1.Query/Allowance";
+
+pub const STAKING_VALIDATOR_QUERY_PATH: &str = "/cosmos.staking.v1beta1.Query/Validator";
+
+pub const SLASHING_SIGNING_INFO_QUERY_PATH: &str = "/cosmos.slashing.v1beta1.Query/SigningInfo";
+
+pub const GOV_PROPOSAL_QUERY_PATH: &str = "/cosmos.gov.v1.Query/Proposal";
+
+pub const GOV_PROPOSALS_QUERY_PATH: &str = "/cosmos.gov.v1.Query/Proposals";
+
+/// only served by hosts running wasmd 0.53 or later
+pub const WASM_CODE_INFO_QUERY_PATH: &str = "/cosmwasm.wasm.v1.Query/CodeInfo";
+
+pub const TWAP_QUERY_PATH: &str = "/osmosis.twap.v1beta1.Query/ArithmeticTwapToNow";
+
+/// TWAP queries average over this many seconds up to the time they are built
+pub const TWAP_WINDOW_SECONDS: u64 = 4 * 3600;
 
 #[cw_serde]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    /// defaults to the instantiating address
+    pub admin: Option<String>,
+    /// defaults to 0, which disables result caching
+    pub cache_ttl_seconds: Option<u64>,
+    /// defaults to 10
+    pub poke_batch_size: Option<u32>,
+    pub poke_bounty: Option<Coin>,
+    pub fee_denom: Option<String>,
+    /// defaults to false. Refunds the fee of a packet that got an error ack or
+    /// timed out, not of queries the host rejected in a successful ack
+    pub refund_on_failure: Option<bool>,
+}
 
 #[cw_serde]
 pub enum ExecuteMsg {
     SendQueryBalance(QueryBalanceMsg),
     SendQueryTwap(QueryTwapMsg),
+    SendQueryRaw(QueryRawMsg),
+    /// Queries a raw store key with `prove: true`, the result is kept with its
+    /// proof until it can be verified against an app hash.
+    SendQueryProven(QueryProvenMsg),
+    /// Smart queries a CosmWasm contract on the host, the JSON it answers
+    /// with is kept as is.
+    SendQueryWasmSmart(QueryWasmSmartMsg),
+    SendQueryWasmRaw(QueryWasmRawMsg),
+    SendQueryContractInfo(QueryContractInfoMsg),
+    /// Queries the trace of an `ibc/` denom of the chain behind `channel`.
+    ResolveDenom { channel: String, denom: String },
+    /// Queries the `ibc/` denom of a full trace on the chain behind `channel`.
+    ResolveDenomHash { channel: String, trace: String },
+    /// Queries a governance proposal of the chain behind `channel`.
+    SendQueryProposal { channel: String, proposal_id: u64 },
+    /// Queries the latest governance proposals of the chain behind `channel`,
+    /// only those in `status` if set.
+    SendQueryProposals {
+        channel: String,
+        status: Option<ProposalStatus>,
+    },
+    /// Queries the account number and sequence of a remote account.
+    SendQueryAccount { channel: String, address: String },
+    /// Queries the authz grants `granter` gave `grantee`, or gave anyone when
+    /// `grantee` is unset.
+    SendQueryGrants {
+        channel: String,
+        granter: String,
+        grantee: Option<String>,
+    },
+    SendQueryFeeAllowance {
+        channel: String,
+        granter: String,
+        grantee: String,
+    },
+    /// Compares the remote supply of the voucher of a local denom with the
+    /// local transfer escrow backing it. Admin only.
+    ReconcileEscrow(ReconcileEscrowMsg),
+    /// Sends the queries in one packet and flags the result if the host
+    /// answered them at different heights.
+    SendQueryBatch(QueryBatchMsg),
+    /// Records the app hash committing to the state at `height` on the chain
+    /// behind `channel`. Admin only.
+    SubmitAppHash {
+        channel: String,
+        height: u64,
+        app_hash: Binary,
+    },
+    /// Verifies the proof of a result against the submitted app hash of its
+    /// height, or the one returned by the light client.
+    VerifyProof { sequence: u64 },
+    /// Permits the given ABCI query paths on `channel`. Admin only.
+    AllowQueryPaths {
+        channel: String,
+        paths: Vec<String>,
+    },
+    /// Removes the given ABCI query paths from the allowlist of `channel`. Admin only.
+    DisallowQueryPaths {
+        channel: String,
+        paths: Vec<String>,
+    },
+    /// Answers incoming queries of the given paths against this chain. Admin only.
+    AllowHostQueryPaths { paths: Vec<String> },
+    DisallowHostQueryPaths { paths: Vec<String> },
+    /// Updates the given configuration fields. Admin only.
+    UpdateConfig(UpdateConfigMsg),
+    /// Sets the fee charged per request of `kind`, zero makes it free. Needs a
+    /// fee denom to be set. Admin only.
+    SetFee {
+        kind: QueryKind,
+        amount: Uint128,
+    },
+    /// Adds the attached fee denom funds to the sender's credits.
+    Deposit {},
+    /// Returns unused credits to the sender.
+    Withdraw { amount: Uint128 },
+    /// Sends the fees of completed requests to `to`, defaulting to the admin. Admin only.
+    CollectFees { to: Option<String> },
+    /// Sets the limit every sender is held to, None removes it. Admin only.
+    SetSenderRateLimit { limit: Option<RateLimit> },
+    /// Sets the limit shared by all senders on `channel`, None removes it. Admin only.
+    SetChannelRateLimit {
+        channel: String,
+        limit: Option<RateLimit>,
+    },
+    /// Rejects new sends in the scope. Admin or guardian only.
+    Pause(PauseScope),
+    /// Lifts the pause of exactly this scope. Admin or guardian only.
+    Unpause(PauseScope),
+    /// Accepts a quarantined price as the current price of its source. Admin only.
+    ReleaseQuarantinedPrice { sequence: u64 },
+    /// Defines or replaces the feed of `base` in `quote`. Admin only.
+    SetFeed(PriceFeed),
+    RemoveFeed { base: String, quote: String },
+    /// Names the chain behind `channel`, None removes the alias. Admin only.
+    SetChainAlias { alias: String, channel: Option<String> },
+    /// Creates a portfolio owned by the sender, or replaces one they own.
+    SetPortfolio { name: String, holdings: Vec<Holding> },
+    /// Owner only.
+    RemovePortfolio { name: String },
+    /// Queries every balance of the portfolio and the metadata of its denoms,
+    /// sending one packet per chain. Owner only, the owner pays the fees.
+    RefreshPortfolio { name: String },
+    /// Alerts when a remote balance crosses a threshold. Admin only.
+    AddWatch(AddWatchMsg),
+    /// Admin only.
+    RemoveWatch { id: u64 },
+    /// Periodically queries the info of a remote contract and of its code,
+    /// alerting when any of it changes. Admin only, the admin pays the fees.
+    WatchContract(WatchContractMsg),
+    /// Admin only.
+    UnwatchContract { id: u64 },
+    /// Periodically queries the staking info and signing info of a remote
+    /// validator, alerting when it gets jailed or tombstoned or its commission
+    /// changes. Admin only, the admin pays the fees.
+    WatchValidator(WatchValidatorMsg),
+    /// Admin only.
+    UnwatchValidator {
+        chain: String,
+        operator_address: String,
+    },
+    /// Executes a message once the price of a pair crosses a threshold. Admins
+    /// may register any message, other senders only a bank send of the funds
+    /// attached to this call, which are held until the message runs.
+    AddCondition(AddConditionMsg),
+    /// Refunds the escrow of a pending condition. Owner or admin only.
+    CancelCondition { id: u64 },
+    /// Sends `query` over `channel` every `interval_seconds`, starting with the next poke.
+    Subscribe {
+        channel: String,
+        query: QuerySpec,
+        interval_seconds: u64,
+    },
+    /// Cancels a subscription. Owner or admin only.
+    Unsubscribe { id: u64 },
+    /// Sends the queries of due subscriptions. Anyone may call this, earning
+    /// the poke bounty when a subscription of the admin is among them.
+    Poke {},
+    /// Adds the attached funds to the pool poke bounties are paid from. Admin only.
+    FundBountyPool {},
+    /// Takes funds back out of the bounty pool. Admin only.
+    WithdrawBountyPool { amount: Coin },
+    /// Sets (or clears, when `prefix` is None) the bech32 prefix that addresses
+    /// sent over `channel` must use. Admin only.
+    SetChannelPrefix {
+        channel: String,
+        prefix: Option<String>,
+    },
+}
+
+/// Fields left as None keep their current value
+#[cw_serde]
+#[derive(Default)]
+pub struct UpdateConfigMsg {
+    pub admin: Option<String>,
+    pub cache_ttl_seconds: Option<u64>,
+    pub poke_batch_size: Option<u32>,
+    /// a zero amount removes the bounty
+    pub poke_bounty: Option<Coin>,
+    /// can only be set while unset, credits being held in it
+    pub fee_denom: Option<String>,
+    pub refund_on_failure: Option<bool>,
+    pub guardian: Option<String>,
+    pub price_bounds: Option<PriceBounds>,
+    pub intermediate_asset: Option<String>,
+    pub light_client: Option<String>,
+    pub quote_denom: Option<String>,
+    pub max_price_age_seconds: Option<u64>,
+    pub grant_expiry_warning_seconds: Option<u64>,
 }
 
 #[cw_serde]
@@ -18,6 +241,48 @@ pub struct QueryBalanceMsg {
     pub channel: String,
     pub address: String,
     pub denom: String,
+    /// host height to query at, defaults to the latest; an answer at a pinned
+    /// height is kept under its sequence without updating any tracked state
+    pub height: Option<u64>,
+}
+
+/// Watches the balance of `denom` held by `address` on `chain`, a chain alias
+#[cw_serde]
+pub struct AddWatchMsg {
+    pub chain: String,
+    pub address: String,
+    pub denom: String,
+    pub threshold: Uint128,
+    pub direction: WatchDirection,
+    /// defaults to 0, which fires again as soon as the balance crosses back
+    pub hysteresis: Option<Uint128>,
+    pub callback: Option<WatchCallback>,
+}
+
+/// Watches `contract` on `chain`, a chain alias
+#[cw_serde]
+pub struct WatchContractMsg {
+    pub chain: String,
+    pub contract: String,
+    pub interval_seconds: u64,
+    pub callback: Option<WatchCallback>,
+}
+
+/// Watches the validator `operator_address` on `chain`, a chain alias
+#[cw_serde]
+pub struct WatchValidatorMsg {
+    pub chain: String,
+    pub operator_address: String,
+    pub interval_seconds: u64,
+}
+
+#[cw_serde]
+pub struct AddConditionMsg {
+    pub base: String,
+    pub quote: String,
+    pub comparison: Comparison,
+    pub threshold: Decimal256,
+    pub msg: CosmosMsg,
 }
 
 #[cw_serde]
@@ -26,15 +291,593 @@ pub struct QueryTwapMsg {
     pub pool_id: u64,
     pub base_asset: String,
     pub quote_asset: String,
+    /// host height to query at, defaults to the latest; an answer at a pinned
+    /// height is kept under its sequence without updating any tracked state
+    pub height: Option<u64>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[cw_serde]
+#[derive(QueryResponses)]
 pub enum QueryMsg {
+    #[returns(Vec<(u64, ProtoCoin)>)]
     AllBalances {},
+    #[returns(Vec<(u64, String)>)]
     AllPriceFeeds {},
+    #[returns(Vec<(u64, String)>)]
     AllErrors {},
+    #[returns(u64)]
     LastSequence {},
+    #[returns(Config)]
+    Config {},
+    #[returns(Option<String>)]
+    ChannelPrefix { channel: String },
+    /// ABCI query paths the channel may send, ascending
+    #[returns(Vec<String>)]
+    AllowedQueryPaths { channel: String },
+    /// ABCI query paths answered for counterparties, ascending
+    #[returns(Vec<String>)]
+    HostQueryPaths {},
+    #[returns(Vec<(u64, Binary)>)]
+    AllRawResponses {},
+    /// Requests sent and still waiting for their ack or timeout
+    #[returns(Vec<(u64, PendingRequest)>)]
+    PendingRequests {},
+    #[returns(Subscription)]
+    Subscription { id: u64 },
+    #[returns(Vec<(u64, Subscription)>)]
+    Subscriptions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Funds left for poke bounties
+    #[returns(Vec<Coin>)]
+    BountyPool {},
+    /// Prepaid credits of an address
+    #[returns(Uint128)]
+    Credits { address: String },
+    #[returns(FeeScheduleResponse)]
+    FeeSchedule {},
+    /// Packets a sender may still send at the current height
+    #[returns(QuotaResponse)]
+    SenderQuota { sender: String },
+    #[returns(QuotaResponse)]
+    ChannelQuota { channel: String },
+    /// Scopes currently paused
+    #[returns(Vec<PauseScope>)]
+    Paused {},
+    /// Current accepted price of every source quoting the pair
+    #[returns(Vec<(String, PriceRecord)>)]
+    Prices { base: String, quote: String },
+    #[returns(Vec<(u64, QuarantinedPrice)>)]
+    QuarantinedPrices {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Price of `base` in `quote`, derived from stored pairs if no pool quotes it
+    #[returns(DerivedPriceResponse)]
+    DerivedPrice { base: String, quote: String },
+    /// Median or weighted mean of the fresh sources of a feed
+    #[returns(AggregatedPriceResponse)]
+    AggregatedPrice { base: String, quote: String },
+    #[returns(Vec<PriceFeed>)]
+    Feeds {},
+    /// Price of `denom` in the configured quote denom, failing if it is missing
+    /// or stale. Meant for other contracts.
+    #[returns(PriceResponse)]
+    GetPrice { denom: String },
+    /// (alias, channel) pairs, ascending by alias
+    #[returns(Vec<(String, String)>)]
+    ChainAliases {},
+    #[returns(Portfolio)]
+    Portfolio { name: String },
+    /// Latest balances of a portfolio valued in the configured quote denom
+    #[returns(PortfolioValueResponse)]
+    PortfolioValue { name: String },
+    /// Change of a remote balance between two points of its history, `chain`
+    /// being a chain alias
+    #[returns(BalanceDeltaResponse)]
+    BalanceDelta {
+        chain: String,
+        address: String,
+        denom: String,
+        from: SnapshotPoint,
+        to: SnapshotPoint,
+    },
+    #[returns(ContractWatch)]
+    ContractWatch { id: u64 },
+    #[returns(Vec<(u64, ContractWatch)>)]
+    ContractWatches {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(ValidatorWatch)]
+    ValidatorWatch {
+        chain: String,
+        operator_address: String,
+    },
+    #[returns(Vec<(String, ValidatorWatch)>)]
+    ValidatorWatches {
+        chain: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(Watch)]
+    Watch { id: u64 },
+    #[returns(Vec<(u64, Watch)>)]
+    Watches {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Host height of each response in the ack of a packet
+    #[returns(Vec<u64>)]
+    ResponseHeights { sequence: u64 },
+    #[returns(WasmQueryResult)]
+    WasmResult { sequence: u64 },
+    #[returns(Vec<(u64, WasmQueryResult)>)]
+    WasmResults {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Trace of an `ibc/` denom of the chain behind `channel`, if resolved
+    #[returns(Option<DenomTrace>)]
+    DenomTrace { channel: String, denom: String },
+    #[returns(Vec<(String, DenomTrace)>)]
+    DenomTraces { channel: String },
+    /// Last known state of a governance proposal, `chain` being a chain alias
+    #[returns(ProposalRecord)]
+    Proposal { chain: String, proposal_id: u64 },
+    #[returns(Vec<(u64, ProposalRecord)>)]
+    Proposals {
+        chain: String,
+        status: Option<ProposalStatus>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(RemoteAccount)]
+    RemoteAccount { chain: String, address: String },
+    /// Known authz grants of `granter` by grantee, only those to `grantee` if set
+    #[returns(Vec<(String, Vec<AuthzGrant>)>)]
+    AuthzGrants {
+        chain: String,
+        granter: String,
+        grantee: Option<String>,
+    },
+    #[returns(FeeAllowance)]
+    FeeAllowance {
+        chain: String,
+        granter: String,
+        grantee: String,
+    },
+    /// Known grants and allowances expiring within `within_seconds`, which
+    /// defaults to the configured warning window
+    #[returns(Vec<ExpiringGrant>)]
+    ExpiringGrants {
+        chain: String,
+        within_seconds: Option<u64>,
+    },
+    #[returns(Reconciliation)]
+    Reconciliation { channel: String, voucher_denom: String },
+    #[returns(Vec<((String, String), Reconciliation)>)]
+    Reconciliations {},
+    #[returns(BatchResult)]
+    BatchResult { sequence: u64 },
+    #[returns(ProvenResult)]
+    ProvenResult { sequence: u64 },
+    #[returns(Vec<(u64, ProvenResult)>)]
+    ProvenResults {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(Option<Binary>)]
+    AppHash { channel: String, height: u64 },
+    #[returns(Condition)]
+    Condition { id: u64 },
+    #[returns(Vec<(u64, Condition)>)]
+    Conditions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct PortfolioValueResponse {
+    pub quote_denom: String,
+    /// sum of the values of every chain
+    pub total: Decimal256,
+    pub chains: Vec<ChainValue>,
+    /// holdings left out of the total, with the reason
+    pub missing: Vec<MissingValue>,
+}
+
+#[cw_serde]
+pub struct ChainValue {
+    pub chain: String,
+    pub value: Decimal256,
+    pub holdings: Vec<HoldingValue>,
+}
+
+#[cw_serde]
+pub struct HoldingValue {
+    pub address: String,
+    pub denom: String,
+    /// balance in the base unit of `denom`
+    pub amount: Uint128,
+    /// exponent of the display unit `amount` is converted to before pricing
+    pub exponent: u32,
+    pub price: Decimal256,
+    pub value: Decimal256,
+    pub updated_at: BlockTimestamp,
+    /// origin of `denom` when it is a resolved `ibc/` denom
+    pub trace: Option<DenomTrace>,
+}
+
+#[cw_serde]
+pub struct MissingValue {
+    pub chain: String,
+    pub address: String,
+    pub denom: String,
+    pub trace: Option<DenomTrace>,
+    pub reason: String,
+}
+
+/// Bound of a balance history window, both ends being inclusive
+#[cw_serde]
+pub enum SnapshotPoint {
+    Time(BlockTimestamp),
+    /// host height, samples whose height is unknown are left out
+    Height(u64),
+}
+
+#[cw_serde]
+pub struct BalanceDeltaResponse {
+    /// first and last samples within the window
+    pub first_seen: Option<BalanceSample>,
+    pub last_seen: Option<BalanceSample>,
+    /// `last_seen` minus `first_seen`, zero without samples
+    pub delta: Int256,
+    /// samples within the window, counted up to 100
+    pub samples: u32,
+}
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum GrantKind {
+    Authz,
+    FeeAllowance,
+}
+
+#[cw_serde]
+pub struct ExpiringGrant {
+    pub kind: GrantKind,
+    pub granter: String,
+    pub grantee: String,
+    /// type URL of the authorization or allowance
+    pub type_url: String,
+    pub msg_type_url: Option<String>,
+    pub expiration: BlockTimestamp,
+}
+
+#[cw_serde]
+pub struct PriceResponse {
+    pub price: Decimal256,
+    /// publish time of the oldest price `price` is derived from
+    pub publish_time: BlockTimestamp,
+    /// number of pool prices `price` is derived from
+    pub source_count: u32,
+}
+
+#[cw_serde]
+pub struct AggregatedPriceResponse {
+    pub price: Decimal256,
+    /// the fresh sources the price was computed from
+    pub sources: Vec<SourcePrice>,
+    pub oldest_publish_time: BlockTimestamp,
+}
+
+#[cw_serde]
+pub struct SourcePrice {
+    pub source: String,
+    pub price: Decimal256,
+    pub weight: Decimal256,
+    pub publish_time: BlockTimestamp,
+}
+
+#[cw_serde]
+pub struct DerivedPriceResponse {
+    pub price: Decimal256,
+    /// the stored prices multiplied to get `price`, in order
+    pub components: Vec<PriceComponent>,
+    /// publish time of the oldest component
+    pub oldest_publish_time: BlockTimestamp,
+}
+
+#[cw_serde]
+pub struct PriceComponent {
+    pub base: String,
+    pub quote: String,
+    pub source: String,
+    /// price of `base` in `quote`, already inverted if `inverted` is set
+    pub price: Decimal256,
+    pub publish_time: BlockTimestamp,
+    /// number of pool prices behind the component, more than one for a feed
+    pub source_count: u32,
+    /// the source quotes `quote` in `base`
+    pub inverted: bool,
+}
+
+/// `None` limits mean no limit is configured
+#[cw_serde]
+pub struct QuotaResponse {
+    pub packets_remaining: Option<u64>,
+    pub in_flight: u64,
+    pub max_in_flight: Option<u64>,
+}
+
+#[cw_serde]
+pub struct FeeScheduleResponse {
+    pub denom: Option<String>,
+    pub refund_on_failure: bool,
+    /// fees of completed requests awaiting collection
+    pub collected: Uint128,
+    pub fees: Vec<FeeRate>,
+}
+
+#[cw_serde]
+pub struct FeeRate {
+    pub kind: String,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub enum SudoMsg {
+    /// Same as `ExecuteMsg::Poke` without a bounty, for chains whose cron module calls sudo
+    Poke {},
+    /// Called every block by the clock module of juno-style chains
+    ClockEndBlock {},
+}
+
+/// The kinds of query fees are charged for and sends are paused by, every
+/// path without a dedicated decoder counting as `Raw`.
+#[cw_serde]
+#[derive(Copy)]
+pub enum QueryKind {
+    Balance,
+    Twap,
+    DenomMetadata,
+    Supply,
+    WasmSmart,
+    WasmRaw,
+    WasmContractInfo,
+    WasmCodeInfo,
+    /// both directions of the ibc transfer denom trace lookup
+    DenomTrace,
+    Account,
+    /// grants by granter and grantee, and by granter alone
+    AuthzGrants,
+    FeeAllowance,
+    Validator,
+    SigningInfo,
+    /// a single proposal or a page of them
+    GovProposals,
+    /// raw store keys queried with a proof
+    Proven,
+    Raw,
+}
+
+impl QueryKind {
+    pub fn from_path(path: &str) -> Self {
+        match path {
+            BALANCE_QUERY_PATH => QueryKind::Balance,
+            TWAP_QUERY_PATH => QueryKind::Twap,
+            DENOM_METADATA_QUERY_PATH => QueryKind::DenomMetadata,
+            SUPPLY_OF_QUERY_PATH => QueryKind::Supply,
+            WASM_SMART_QUERY_PATH => QueryKind::WasmSmart,
+            WASM_RAW_QUERY_PATH => QueryKind::WasmRaw,
+            WASM_CONTRACT_INFO_QUERY_PATH => QueryKind::WasmContractInfo,
+            WASM_CODE_INFO_QUERY_PATH => QueryKind::WasmCodeInfo,
+            DENOM_TRACE_QUERY_PATH | DENOM_HASH_QUERY_PATH => QueryKind::DenomTrace,
+            AUTH_ACCOUNT_QUERY_PATH => QueryKind::Account,
+            AUTHZ_GRANTS_QUERY_PATH | AUTHZ_GRANTER_GRANTS_QUERY_PATH => QueryKind::AuthzGrants,
+            FEE_ALLOWANCE_QUERY_PATH => QueryKind::FeeAllowance,
+            STAKING_VALIDATOR_QUERY_PATH => QueryKind::Validator,
+            SLASHING_SIGNING_INFO_QUERY_PATH => QueryKind::SigningInfo,
+            GOV_PROPOSAL_QUERY_PATH | GOV_PROPOSALS_QUERY_PATH => QueryKind::GovProposals,
+            _ if path.starts_with("/store/") => QueryKind::Proven,
+            _ => QueryKind::Raw,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueryKind::Balance => "balance",
+            QueryKind::Twap => "twap",
+            QueryKind::DenomMetadata => "denom_metadata",
+            QueryKind::Supply => "supply",
+            QueryKind::WasmSmart => "wasm_smart",
+            QueryKind::WasmRaw => "wasm_raw",
+            QueryKind::WasmContractInfo => "wasm_contract_info",
+            QueryKind::WasmCodeInfo => "wasm_code_info",
+            QueryKind::DenomTrace => "denom_trace",
+            QueryKind::Account => "account",
+            QueryKind::AuthzGrants => "authz_grants",
+            QueryKind::FeeAllowance => "fee_allowance",
+            QueryKind::Validator => "validator",
+            QueryKind::SigningInfo => "signing_info",
+            QueryKind::GovProposals => "gov_proposals",
+            QueryKind::Proven => "proven",
+            QueryKind::Raw => "raw",
+        }
+    }
+}
+
+/// Leaving `channel` or `kind` unset pauses every channel or kind, so the
+/// empty scope pauses all sends.
+#[cw_serde]
+pub struct PauseScope {
+    pub channel: Option<String>,
+    pub kind: Option<QueryKind>,
+}
+
+/// A query independent of the channel it is sent over, as stored by
+/// subscriptions and built by `build_query_request`.
+#[cw_serde]
+pub enum QuerySpec {
+    Balance {
+        address: String,
+        denom: String,
+    },
+    Twap {
+        pool_id: u64,
+        base_asset: String,
+        quote_asset: String,
+    },
+    DenomMetadata {
+        denom: String,
+    },
+    SupplyOf {
+        denom: String,
+    },
+    /// smart query of a remote CosmWasm contract, `msg` being its JSON
+    WasmSmart {
+        contract: String,
+        msg: Binary,
+    },
+    WasmRaw {
+        contract: String,
+        key: Binary,
+    },
+    WasmContractInfo {
+        contract: String,
+    },
+    WasmCodeInfo {
+        code_id: u64,
+    },
+    /// path and base denom behind an `ibc/` denom of the host
+    DenomTrace {
+        denom: String,
+    },
+    /// `ibc/` denom of a full trace like `transfer/channel-0/uatom`
+    DenomHash {
+        trace: String,
+    },
+    Account {
+        address: String,
+    },
+    AuthzGrants {
+        granter: String,
+        grantee: String,
+    },
+    AuthzGranterGrants {
+        granter: String,
+    },
+    FeeAllowance {
+        granter: String,
+        grantee: String,
+    },
+    StakingValidator {
+        operator_address: String,
+    },
+    SigningInfo {
+        consensus_address: String,
+    },
+    GovProposal {
+        proposal_id: u64,
+    },
+    /// newest proposals first, only those in `status` if set
+    GovProposals {
+        status: Option<ProposalStatus>,
+    },
+    /// raw key of a store, queried with `prove: true`
+    Proven {
+        store: String,
+        key: Binary,
+    },
+    /// any allowed path but the TWAP one, whose window is set by `Twap`
+    Raw {
+        path: String,
+        data: Binary,
+    },
+}
+
+/// The value of `key` in the KV store `store`, e.g. "bank"
+#[cw_serde]
+pub struct QueryProvenMsg {
+    pub channel: String,
+    pub store: String,
+    pub key: Binary,
+    /// host height to query at, defaults to the latest
+    pub height: Option<u64>,
+}
+
+/// `msg` is the JSON query message, passed to the contract untouched
+#[cw_serde]
+pub struct QueryWasmSmartMsg {
+    pub channel: String,
+    pub contract: String,
+    pub msg: Binary,
+    /// host height to query at, defaults to the latest; an answer at a pinned
+    /// height is kept under its sequence without updating any tracked state
+    pub height: Option<u64>,
+}
+
+#[cw_serde]
+pub struct QueryWasmRawMsg {
+    pub channel: String,
+    pub contract: String,
+    pub key: Binary,
+    /// host height to query at, defaults to the latest; an answer at a pinned
+    /// height is kept under its sequence without updating any tracked state
+    pub height: Option<u64>,
+}
+
+#[cw_serde]
+pub struct QueryContractInfoMsg {
+    pub channel: String,
+    pub contract: String,
+    /// host height to query at, defaults to the latest; an answer at a pinned
+    /// height is kept under its sequence without updating any tracked state
+    pub height: Option<u64>,
+}
+
+/// `voucher_denom` is the `ibc/` denom of `denom` on the chain behind
+/// `channel`, sent there through `transfer_channel`
+#[cw_serde]
+pub struct ReconcileEscrowMsg {
+    pub channel: String,
+    pub transfer_channel: String,
+    pub denom: String,
+    pub voucher_denom: String,
+}
+
+/// Queries sent in one packet, whose responses must all come from the same
+/// host height
+#[cw_serde]
+pub struct QueryBatchMsg {
+    pub channel: String,
+    pub queries: Vec<QuerySpec>,
+    /// host height to query at, defaults to the latest; an answer at a pinned
+    /// height is kept under its sequence without updating any tracked state
+    pub height: Option<u64>,
+}
+
+/// Query a light client contract has to answer to be used as `light_client`
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum LightClientQueryMsg {
+    /// App hash committing to the state at `height` of the chain behind `channel`
+    #[returns(Option<Binary>)]
+    AppHash { channel: String, height: u64 },
+}
+
+/// An arbitrary ABCI query, `data` being the proto encoded request
+#[cw_serde]
+pub struct QueryRawMsg {
+    pub channel: String,
+    pub path: String,
+    pub data: Binary,
+    /// host height to query at, defaults to the latest; an answer at a pinned
+    /// height is kept under its sequence without updating any tracked state
+    pub height: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -108,4 +951,57 @@ pub struct Timestamp {
 pub struct ArithmeticTwapToNowResponse {
     #[prost(string, tag = "1")]
     pub arithmetic_twap: String,
-}
\ No newline at end of file
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryCodeInfoRequest {
+    #[prost(uint64, tag = "1")]
+    pub code_id: u64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryCodeInfoResponse {
+    #[prost(uint64, tag = "1")]
+    pub code_id: u64,
+
+    #[prost(string, tag = "2")]
+    pub creator: String,
+
+    /// sha256 of the wasm byte code
+    #[prost(bytes = "vec", tag = "3")]
+    pub checksum: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryDenomTraceRequest {
+    /// hex hash of the denom, without the `ibc/` prefix
+    #[prost(string, tag = "1")]
+    pub hash: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryDenomTraceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub denom_trace: Option<ProtoDenomTrace>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProtoDenomTrace {
+    #[prost(string, tag = "1")]
+    pub path: String,
+
+    #[prost(string, tag = "2")]
+    pub base_denom: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryDenomHashRequest {
+    #[prost(string, tag = "1")]
+    pub trace: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryDenomHashResponse {
+    #[prost(string, tag = "1")]
+    pub hash: String,
+}
@@ -0,0 +1,74 @@
+use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::AbciQueryRequest;
+use cosmwasm_std::{Deps, DepsMut, MessageInfo, Order, Response, StdResult, Storage};
+
+use crate::error::ContractError;
+use crate::msg::{PauseScope, QueryKind};
+use crate::state::{CONFIG, PAUSED};
+
+/// Stands in for "every channel" or "every kind" in the keys of `PAUSED`.
+const ANY: &str = "*";
+
+fn scope_key(scope: &PauseScope) -> (&str, &str) {
+    (
+        scope.channel.as_deref().unwrap_or(ANY),
+        scope.kind.map(|kind| kind.as_str()).unwrap_or(ANY),
+    )
+}
+
+/// Fails if sending any of `requests` over `channel` is paused globally, for
+/// the channel, for the query kind, or for the kind on this channel.
+pub fn ensure_not_paused(storage: &dyn Storage, channel: &str, requests: &[AbciQueryRequest]) -> Result<(), ContractError> {
+    for req in requests {
+        let kind = QueryKind::from_path(&req.path).as_str();
+        for key in [(ANY, ANY), (channel, ANY), (ANY, kind), (channel, kind)] {
+            if PAUSED.has(storage, key) {
+                return Err(ContractError::Paused { channel: key.0.to_string(), kind: key.1.to_string() });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn ensure_admin_or_guardian(deps: Deps, info: &MessageInfo) -> Result<(), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin && Some(&info.sender) != config.guardian.as_ref() {
+        return Err(ContractError::Unauthorized);
+    }
+    Ok(())
+}
+
+/// Rejects new sends in `scope`. Acks and timeouts of packets already in
+/// flight are still processed. Admin or guardian only.
+pub fn pause(deps: DepsMut, info: MessageInfo, scope: PauseScope) -> Result<Response, ContractError> {
+    ensure_admin_or_guardian(deps.as_ref(), &info)?;
+
+    let (channel, kind) = scope_key(&scope);
+    PAUSED.save(deps.storage, (channel, kind), &scope)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "pause")
+        .add_attribute("channel", channel)
+        .add_attribute("kind", kind)
+        .add_attribute("sender", info.sender))
+}
+
+/// Lifts a pause previously set for exactly `scope`. Admin or guardian only.
+pub fn unpause(deps: DepsMut, info: MessageInfo, scope: PauseScope) -> Result<Response, ContractError> {
+    ensure_admin_or_guardian(deps.as_ref(), &info)?;
+
+    let (channel, kind) = scope_key(&scope);
+    PAUSED.remove(deps.storage, (channel, kind));
+
+    Ok(Response::new()
+        .add_attribute("method", "unpause")
+        .add_attribute("channel", channel)
+        .add_attribute("kind", kind)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn query_paused(deps: Deps) -> StdResult<Vec<PauseScope>> {
+    PAUSED
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, scope)| scope))
+        .collect()
+}
@@ -0,0 +1,339 @@
+use std::collections::BTreeMap;
+
+use cosmos_sdk_proto::cosmos::bank::v1beta1::{Metadata, QueryBalanceRequest};
+use cosmwasm_std::{Decimal256, Deps, DepsMut, Empty, Env, Int256, MessageInfo, Order, Response, StdResult, Storage, Timestamp, Uint128, Uint256};
+use cw_storage_plus::Bound;
+
+use crate::contract::{build_query_request, ensure_admin, PacketOutcome, prepare_icq_packet};
+use crate::denom_trace::denom_trace;
+use crate::error::ContractError;
+use crate::msg::{BalanceDeltaResponse, ChainValue, HoldingValue, MissingValue, PortfolioValueResponse, ProtoCoin, QuerySpec, SnapshotPoint};
+use crate::price::query_price;
+use crate::state::{BALANCE_HISTORY, BALANCE_HISTORY_BY_HEIGHT, BALANCE_HISTORY_LENGTHS, BALANCES, BalanceKey, BalanceRecord, BalanceSample, CHAIN_ALIASES, CHANNEL_INFO, CONFIG, DENOM_EXPONENTS, Holding, Portfolio, PORTFOLIOS};
+
+/// Samples kept in the history of a balance, the oldest being pruned first.
+pub const MAX_BALANCE_SAMPLES: u32 = 1000;
+
+/// Samples counted by a `BalanceDelta` query, bounding its gas.
+pub const MAX_COUNTED_SAMPLES: usize = 100;
+
+/// Names the chain behind `channel`, None removes the alias. Admin only.
+pub fn set_chain_alias(deps: DepsMut, info: MessageInfo, alias: String, channel: Option<String>) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+
+    match &channel {
+        Some(channel) => {
+            if !CHANNEL_INFO.has(deps.storage, channel) {
+                return Err(ContractError::NoSuchChannel { id: channel.clone() });
+            }
+            CHAIN_ALIASES.save(deps.storage, &alias, channel)?;
+        }
+        None => CHAIN_ALIASES.remove(deps.storage, &alias),
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "set_chain_alias")
+        .add_attribute("alias", alias)
+        .add_attribute("channel", channel.unwrap_or_default()))
+}
+
+pub(crate) fn resolve_chain(storage: &dyn Storage, alias: &str) -> Result<String, ContractError> {
+    CHAIN_ALIASES
+        .may_load(storage, alias)?
+        .ok_or(ContractError::NoSuchChainAlias { alias: alias.to_string() })
+}
+
+fn load_owned(deps: Deps, info: &MessageInfo, name: &str) -> Result<Portfolio, ContractError> {
+    let portfolio = PORTFOLIOS
+        .may_load(deps.storage, name)?
+        .ok_or(ContractError::NoSuchPortfolio { name: name.to_string() })?;
+    if portfolio.owner != info.sender {
+        return Err(ContractError::Unauthorized);
+    }
+    Ok(portfolio)
+}
+
+/// Creates a portfolio owned by the sender, or replaces one they own.
+pub fn set_portfolio(deps: DepsMut, env: Env, info: MessageInfo, name: String, holdings: Vec<Holding>) -> Result<Response, ContractError> {
+    if PORTFOLIOS.has(deps.storage, &name) {
+        load_owned(deps.as_ref(), &info, &name)?;
+    }
+    if holdings.is_empty() {
+        return Err(ContractError::InvalidPortfolio { reason: "no holdings".to_string() });
+    }
+    // reject holdings that could never be refreshed
+    for holding in &holdings {
+        let channel = resolve_chain(deps.storage, &holding.chain)?;
+        if holding.denoms.is_empty() {
+            return Err(ContractError::InvalidPortfolio { reason: format!("no denoms held by {}", holding.address) });
+        }
+        for denom in &holding.denoms {
+            let spec = QuerySpec::Balance { address: holding.address.clone(), denom: denom.clone() };
+            build_query_request(deps.as_ref(), &env, &channel, &spec)?;
+        }
+    }
+
+    PORTFOLIOS.save(deps.storage, &name, &Portfolio { owner: info.sender.clone(), holdings })?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_portfolio")
+        .add_attribute("name", name)
+        .add_attribute("owner", info.sender))
+}
+
+pub fn remove_portfolio(deps: DepsMut, info: MessageInfo, name: String) -> Result<Response, ContractError> {
+    load_owned(deps.as_ref(), &info, &name)?;
+    PORTFOLIOS.remove(deps.storage, &name);
+
+    Ok(Response::new()
+        .add_attribute("method", "remove_portfolio")
+        .add_attribute("name", name))
+}
+
+/// Sends one packet per chain querying every balance held there, the
+/// metadata of each denom and the trace of `ibc/` denoms not resolved yet.
+/// Owner only, the owner pays the fees.
+pub fn refresh_portfolio(mut deps: DepsMut, env: Env, info: MessageInfo, name: String) -> Result<Response, ContractError> {
+    let portfolio = load_owned(deps.as_ref(), &info, &name)?;
+
+    let mut specs: BTreeMap<String, Vec<QuerySpec>> = BTreeMap::new();
+    for holding in &portfolio.holdings {
+        let channel = resolve_chain(deps.storage, &holding.chain)?;
+        let channel_specs = specs.entry(channel.clone()).or_default();
+        for denom in &holding.denoms {
+            // ahead of the balance, so the balance is recorded with its trace
+            let trace = QuerySpec::DenomTrace { denom: denom.clone() };
+            if denom.starts_with("ibc/") && denom_trace(deps.storage, &channel, denom)?.is_none() && !channel_specs.contains(&trace) {
+                channel_specs.push(trace);
+            }
+            channel_specs.push(QuerySpec::Balance { address: holding.address.clone(), denom: denom.clone() });
+            let metadata = QuerySpec::DenomMetadata { denom: denom.clone() };
+            if !channel_specs.contains(&metadata) {
+                channel_specs.push(metadata);
+            }
+        }
+    }
+
+    let mut res = Response::new()
+        .add_attribute("method", "refresh_portfolio")
+        .add_attribute("name", name);
+    for (channel, channel_specs) in specs {
+        let requests = channel_specs
+            .iter()
+            .map(|spec| build_query_request(deps.as_ref(), &env, &channel, spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        // a packet always carries a balance and a metadata request, so it is
+        // never answered from the cache or coalesced
+        if let PacketOutcome::Sent { request_id, msg } = prepare_icq_packet(deps.branch(), &env, &info.sender, &channel, requests)? {
+            res = res
+                .add_attribute(format!("request_id_{channel}"), request_id.to_string())
+                .add_message(msg);
+        }
+    }
+
+    Ok(res)
+}
+
+/// Stores the balance returned for `request` as the latest of its holding,
+/// and appends it to the history of the holding unless its amount is invalid.
+pub fn record_balance(
+    storage: &mut dyn Storage,
+    channel: &str,
+    request: &QueryBalanceRequest,
+    coin: ProtoCoin,
+    height: u64,
+    time: Timestamp,
+) -> StdResult<()> {
+    let key = (channel, request.address.as_str(), request.denom.as_str());
+    if let Ok(amount) = coin.amount.parse::<Uint128>() {
+        append_sample(storage, key, BalanceSample { amount, height, time })?;
+    }
+    let trace = denom_trace(storage, channel, &request.denom)?;
+    BALANCES.save(storage, key, &BalanceRecord { coin, time, trace })
+}
+
+/// Appends `sample` to the history of a balance, replacing one of the same
+/// block, and prunes the oldest samples beyond `MAX_BALANCE_SAMPLES`.
+fn append_sample(storage: &mut dyn Storage, key: BalanceKey, sample: BalanceSample) -> StdResult<()> {
+    let nanos = sample.time.nanos();
+    let mut length = BALANCE_HISTORY_LENGTHS.may_load(storage, key)?.unwrap_or_default();
+    match BALANCE_HISTORY.may_load(storage, (key, nanos))? {
+        Some(old) => remove_sample(storage, key, nanos, &old),
+        None => length += 1,
+    }
+    BALANCE_HISTORY.save(storage, (key, nanos), &sample)?;
+    if sample.height != 0 {
+        BALANCE_HISTORY_BY_HEIGHT.save(storage, (key, sample.height, nanos), &Empty {})?;
+    }
+
+    while length > MAX_BALANCE_SAMPLES {
+        let Some((nanos, oldest)) = BALANCE_HISTORY.prefix(key).range(storage, None, None, Order::Ascending).next().transpose()? else {
+            break;
+        };
+        remove_sample(storage, key, nanos, &oldest);
+        length -= 1;
+    }
+    BALANCE_HISTORY_LENGTHS.save(storage, key, &length)
+}
+
+fn remove_sample(storage: &mut dyn Storage, key: BalanceKey, nanos: u64, sample: &BalanceSample) {
+    BALANCE_HISTORY.remove(storage, (key, nanos));
+    BALANCE_HISTORY_BY_HEIGHT.remove(storage, (key, sample.height, nanos));
+}
+
+/// Stores the exponent of the display unit of the denom described by
+/// `metadata`. Metadata without a matching display unit is ignored.
+pub fn record_denom_metadata(storage: &mut dyn Storage, channel: &str, metadata: &Metadata) -> StdResult<()> {
+    let display = metadata.denom_units.iter().find(|unit| unit.denom == metadata.display);
+    if let Some(unit) = display {
+        DENOM_EXPONENTS.save(storage, (channel, &metadata.base), &unit.exponent)?;
+    }
+    Ok(())
+}
+
+/// Value of one denom of a holding, or why it cannot be valued.
+fn holding_value(deps: Deps, now: Timestamp, channel: &str, address: &str, denom: &str) -> Result<HoldingValue, String> {
+    let balance = BALANCES
+        .may_load(deps.storage, (channel, address, denom))
+        .map_err(|err| err.to_string())?
+        .ok_or("no balance received yet")?;
+    let amount: Uint128 = balance.coin.amount.parse().map_err(|_| format!("invalid amount {}", balance.coin.amount))?;
+    let exponent = DENOM_EXPONENTS
+        .may_load(deps.storage, (channel, denom))
+        .map_err(|err| err.to_string())?
+        .ok_or("no denom metadata received yet")?;
+    // a voucher is priced as the asset it represents on its origin chain
+    let trace = denom_trace(deps.storage, channel, denom).map_err(|err| err.to_string())?;
+    let priced_denom = match &trace {
+        Some(trace) => trace.base_denom.clone(),
+        None if denom.starts_with("ibc/") => return Err("denom trace not resolved yet".to_string()),
+        None => denom.to_string(),
+    };
+    let price = query_price(deps, now, priced_denom).map_err(|err| err.to_string())?.price;
+
+    let value = Decimal256::from_atomics(Uint256::from(amount), exponent)
+        .ok()
+        .and_then(|whole| whole.checked_mul(price).ok())
+        .ok_or(format!("cannot value {amount} with exponent {exponent}"))?;
+
+    Ok(HoldingValue {
+        address: address.to_string(),
+        denom: denom.to_string(),
+        amount,
+        exponent,
+        price,
+        value,
+        updated_at: balance.time,
+        trace,
+    })
+}
+
+/// Values the latest balances of the portfolio in the configured quote denom.
+/// Balances are converted to display units with the exponent of the denom's
+/// metadata before being multiplied by its price, that of an IBC voucher
+/// being the price of the base denom of its trace. Holdings that cannot be
+/// valued are listed in `missing` and left out of the total.
+pub fn query_portfolio_value(deps: Deps, now: Timestamp, name: String) -> Result<PortfolioValueResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let quote_denom = config.quote_denom.ok_or(ContractError::QuoteDenomNotSet {})?;
+    let portfolio = PORTFOLIOS
+        .may_load(deps.storage, &name)?
+        .ok_or(ContractError::NoSuchPortfolio { name })?;
+
+    let mut chains: Vec<ChainValue> = vec![];
+    let mut missing = vec![];
+    for holding in portfolio.holdings {
+        let channel = resolve_chain(deps.storage, &holding.chain)?;
+        let position = match chains.iter().position(|chain| chain.chain == holding.chain) {
+            Some(position) => position,
+            None => {
+                chains.push(ChainValue { chain: holding.chain.clone(), value: Decimal256::zero(), holdings: vec![] });
+                chains.len() - 1
+            }
+        };
+        for denom in holding.denoms {
+            match holding_value(deps, now, &channel, &holding.address, &denom) {
+                Ok(value) => {
+                    let chain = &mut chains[position];
+                    chain.value += value.value;
+                    chain.holdings.push(value);
+                }
+                Err(reason) => missing.push(MissingValue {
+                    chain: holding.chain.clone(),
+                    address: holding.address.clone(),
+                    trace: denom_trace(deps.storage, &channel, &denom)?,
+                    denom,
+                    reason,
+                }),
+            }
+        }
+    }
+
+    let total = chains.iter().map(|chain| chain.value).sum();
+    Ok(PortfolioValueResponse { quote_denom, total, chains, missing })
+}
+
+pub fn query_chain_aliases(deps: Deps) -> StdResult<Vec<(String, String)>> {
+    CHAIN_ALIASES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect()
+}
+
+pub fn query_portfolio(deps: Deps, name: String) -> Result<Portfolio, ContractError> {
+    PORTFOLIOS
+        .may_load(deps.storage, &name)?
+        .ok_or(ContractError::NoSuchPortfolio { name })
+}
+
+/// Change of a balance between the first and the last sample of its history
+/// within `from` and `to`.
+pub fn query_balance_delta(
+    deps: Deps,
+    chain: String,
+    address: String,
+    denom: String,
+    from: SnapshotPoint,
+    to: SnapshotPoint,
+) -> Result<BalanceDeltaResponse, ContractError> {
+    let channel = resolve_chain(deps.storage, &chain)?;
+    let key = (channel.as_str(), address.as_str(), denom.as_str());
+
+    // samples are keyed by time, those of a known height being indexed by it
+    let (times, heights) = match (from, to) {
+        (SnapshotPoint::Time(from), SnapshotPoint::Time(to)) => (Some((from.nanos(), to.nanos())), None),
+        (SnapshotPoint::Height(from), SnapshotPoint::Height(to)) => (None, Some((from, to))),
+        _ => {
+            return Err(ContractError::InvalidSnapshotWindow {
+                reason: "both ends must be times or both heights".to_string(),
+            })
+        }
+    };
+    // time keys of the samples within the window, in `order`
+    let window = |order: Order| -> Box<dyn Iterator<Item = StdResult<u64>> + '_> {
+        match (times, heights) {
+            (Some((from, to)), _) => {
+                Box::new(BALANCE_HISTORY.prefix(key).keys(deps.storage, Some(Bound::inclusive(from)), Some(Bound::inclusive(to)), order))
+            }
+            (_, Some((from, to))) => Box::new(
+                BALANCE_HISTORY_BY_HEIGHT
+                    .sub_prefix(key)
+                    .keys(deps.storage, Some(Bound::inclusive((from, 0))), Some(Bound::inclusive((to, u64::MAX))), order)
+                    .map(|item| item.map(|(_, nanos)| nanos)),
+            ),
+            _ => Box::new(std::iter::empty()),
+        }
+    };
+
+    let sample = |nanos: Option<StdResult<u64>>| -> StdResult<Option<BalanceSample>> {
+        nanos.transpose()?.map(|nanos| BALANCE_HISTORY.load(deps.storage, (key, nanos))).transpose()
+    };
+    let first_seen = sample(window(Order::Ascending).next())?;
+    let last_seen = sample(window(Order::Descending).next())?;
+    let samples = window(Order::Ascending).take(MAX_COUNTED_SAMPLES).count() as u32;
+
+    let delta = match (&first_seen, &last_seen) {
+        (Some(first), Some(last)) => Int256::from(last.amount.u128()) - Int256::from(first.amount.u128()),
+        _ => Int256::zero(),
+    };
+    Ok(BalanceDeltaResponse { first_seen, last_seen, delta, samples })
+}
@@ -0,0 +1,275 @@
+use std::str::FromStr;
+
+use cosmwasm_std::{Decimal256, Deps, DepsMut, Env, Event, MessageInfo, Order, Response, StdResult, Storage, Timestamp, Uint256};
+use cw_storage_plus::Bound;
+
+use crate::contract::ensure_admin;
+use crate::error::ContractError;
+use crate::msg::{ArithmeticTwapToNowRequest, DerivedPriceResponse, PriceComponent, PriceResponse, TWAP_WINDOW_SECONDS};
+use crate::feeds::aggregate;
+use crate::state::{CONFIG, FEEDS, PRICES, PriceBounds, PriceRecord, QUARANTINED_PRICES, QuarantinedPrice};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Osmosis encodes `sdk.Dec` as its 18 decimal integer representation on the
+/// wire, while JSON gateways return the dotted form. Both are accepted.
+pub fn parse_twap(raw: &str) -> Result<Decimal256, ContractError> {
+    let invalid = || ContractError::InvalidPrice { raw: raw.to_string() };
+    if raw.contains('.') {
+        Decimal256::from_str(raw).map_err(|_| invalid())
+    } else {
+        let atomics = Uint256::from_str(raw).map_err(|_| invalid())?;
+        Decimal256::from_atomics(atomics, 18).map_err(|_| invalid())
+    }
+}
+
+/// Source name of components taken from an aggregated feed.
+const FEED_SOURCE: &str = "feed";
+
+/// Identifies where a price came from, several sources can quote the same pair.
+pub fn price_source(channel: &str, pool_id: u64) -> String {
+    format!("{channel}/{pool_id}")
+}
+
+/// Returns why `price` must not become the current price of its source, if
+/// anything.
+fn check_bounds(
+    bounds: &PriceBounds,
+    price: Decimal256,
+    last: Option<&PriceRecord>,
+    observed_until: Timestamp,
+    now: Timestamp,
+) -> Option<String> {
+    if price.is_zero() {
+        return Some("zero price".to_string());
+    }
+    if let Some(max_age) = bounds.max_age_seconds {
+        let age = now.seconds().saturating_sub(observed_until.seconds());
+        if age > max_age {
+            return Some(format!("age {age}s exceeds {max_age}s"));
+        }
+    }
+    if let (Some(max_deviation), Some(last)) = (bounds.max_deviation, last) {
+        let difference = if price > last.price { price - last.price } else { last.price - price };
+        // a jump too large for a decimal is past any deviation bound
+        let Ok(deviation) = difference.checked_div(last.price) else {
+            return Some(format!("deviation from {} overflows", last.price));
+        };
+        if deviation > max_deviation {
+            return Some(format!("deviation {deviation} from {} exceeds {max_deviation}", last.price));
+        }
+    }
+    None
+}
+
+/// Parses a TWAP returned for `request` and either stores it as the current
+/// price of its source or quarantines it with the reason it was rejected.
+pub fn record_twap(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel: &str,
+    request: &ArithmeticTwapToNowRequest,
+    raw: &str,
+    sequence: u64,
+) -> Result<Event, ContractError> {
+    let config = CONFIG.load(storage)?;
+    let source = price_source(channel, request.pool_id);
+    let key = (request.base_asset.as_str(), request.quote_asset.as_str(), source.as_str());
+    let last = PRICES.may_load(storage, key)?;
+
+    // the TWAP window ends where the request was built
+    let observed_until = request
+        .start_time
+        .as_ref()
+        .map(|start| Timestamp::from_seconds(start.seconds as u64 + TWAP_WINDOW_SECONDS))
+        .unwrap_or(env.block.time);
+
+    let event = Event::new("price_update")
+        .add_attribute("base", &request.base_asset)
+        .add_attribute("quote", &request.quote_asset)
+        .add_attribute("source", &source)
+        .add_attribute("sequence", sequence.to_string())
+        .add_attribute("raw", raw);
+
+    let (price, reason) = match parse_twap(raw) {
+        Ok(price) => match check_bounds(&config.price_bounds, price, last.as_ref(), observed_until, env.block.time) {
+            None => {
+                PRICES.save(storage, key, &PriceRecord { price, publish_time: env.block.time, sequence })?;
+                return Ok(event.add_attribute("status", "accepted").add_attribute("price", price.to_string()));
+            }
+            Some(reason) => (Some(price), reason),
+        },
+        Err(err) => (None, err.to_string()),
+    };
+
+    QUARANTINED_PRICES.save(storage, sequence, &QuarantinedPrice {
+        base: request.base_asset.clone(),
+        quote: request.quote_asset.clone(),
+        source,
+        raw: raw.to_string(),
+        price,
+        reason: reason.clone(),
+        time: env.block.time,
+    })?;
+    Ok(event.add_attribute("status", "quarantined").add_attribute("reason", reason))
+}
+
+/// Makes a quarantined price the current price of its source, e.g. after a
+/// genuine market move larger than the deviation bound. Admin only.
+pub fn release_quarantined_price(deps: DepsMut, env: Env, info: MessageInfo, sequence: u64) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+
+    let quarantined = QUARANTINED_PRICES
+        .may_load(deps.storage, sequence)?
+        .ok_or(ContractError::NoSuchQuarantinedPrice { sequence })?;
+    let price = quarantined
+        .price
+        .filter(|price| !price.is_zero())
+        .ok_or(ContractError::InvalidPrice { raw: quarantined.raw.clone() })?;
+
+    QUARANTINED_PRICES.remove(deps.storage, sequence);
+    PRICES.save(
+        deps.storage,
+        (&quarantined.base, &quarantined.quote, &quarantined.source),
+        &PriceRecord { price, publish_time: env.block.time, sequence },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "release_quarantined_price")
+        .add_attribute("sequence", sequence.to_string())
+        .add_attribute("price", price.to_string()))
+}
+
+/// Freshest accepted price of `base` in `quote` across all sources.
+fn freshest_source(storage: &dyn Storage, base: &str, quote: &str) -> StdResult<Option<PriceComponent>> {
+    let mut freshest: Option<PriceComponent> = None;
+    for item in PRICES.prefix((base, quote)).range(storage, None, None, Order::Ascending) {
+        let (source, record) = item?;
+        if freshest.as_ref().is_none_or(|current| record.publish_time > current.publish_time) {
+            freshest = Some(PriceComponent {
+                base: base.to_string(),
+                quote: quote.to_string(),
+                source,
+                price: record.price,
+                publish_time: record.publish_time,
+                source_count: 1,
+                inverted: false,
+            });
+        }
+    }
+    Ok(freshest)
+}
+
+/// Aggregate of the feed defined for the pair, or the freshest source quoting
+/// it when there is none.
+fn direct_price(storage: &dyn Storage, base: &str, quote: &str, now: Timestamp) -> Result<Option<PriceComponent>, ContractError> {
+    match FEEDS.may_load(storage, (base, quote))? {
+        Some(feed) => {
+            let aggregated = aggregate(storage, &feed, now)?;
+            Ok(Some(PriceComponent {
+                base: base.to_string(),
+                quote: quote.to_string(),
+                source: FEED_SOURCE.to_string(),
+                price: aggregated.price,
+                publish_time: aggregated.oldest_publish_time,
+                source_count: aggregated.sources.len() as u32,
+                inverted: false,
+            }))
+        }
+        None => Ok(freshest_source(storage, base, quote)?),
+    }
+}
+
+/// Price of `base` in `quote` from a stored pair, inverting the opposite pair
+/// when only that one is quoted.
+pub fn pair_price(storage: &dyn Storage, base: &str, quote: &str, now: Timestamp) -> Result<Option<PriceComponent>, ContractError> {
+    if let Some(direct) = direct_price(storage, base, quote, now)? {
+        return Ok(Some(direct));
+    }
+    match direct_price(storage, quote, base, now)? {
+        Some(mut opposite) => {
+            opposite.price = Decimal256::one()
+                .checked_div(opposite.price)
+                .map_err(|_| ContractError::InvalidPrice { raw: opposite.price.to_string() })?;
+            opposite.inverted = true;
+            Ok(Some(opposite))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Price of `base` in `quote` from a stored pair, its inverse, or by
+/// triangulating through the configured intermediate asset.
+pub fn derived_price(storage: &dyn Storage, base: &str, quote: &str, now: Timestamp) -> Result<DerivedPriceResponse, ContractError> {
+    if base == quote {
+        return Err(ContractError::IdenticalTwapAssets { denom: base.to_string() });
+    }
+
+    let components = match pair_price(storage, base, quote, now)? {
+        Some(component) => vec![component],
+        None => {
+            let config = CONFIG.load(storage)?;
+            let intermediate = config
+                .intermediate_asset
+                .filter(|intermediate| intermediate != base && intermediate != quote)
+                .ok_or(ContractError::NoPrice { base: base.to_string(), quote: quote.to_string() })?;
+            match (pair_price(storage, base, &intermediate, now)?, pair_price(storage, &intermediate, quote, now)?) {
+                (Some(first), Some(second)) => vec![first, second],
+                _ => return Err(ContractError::NoPrice { base: base.to_string(), quote: quote.to_string() }),
+            }
+        }
+    };
+
+    let price = components
+        .iter()
+        .try_fold(Decimal256::one(), |price, component| price.checked_mul(component.price))
+        .map_err(|_| ContractError::InvalidPrice { raw: format!("{base}/{quote}") })?;
+    let oldest_publish_time = components
+        .iter()
+        .map(|component| component.publish_time)
+        .min()
+        .expect("at least one component");
+
+    Ok(DerivedPriceResponse { price, components, oldest_publish_time })
+}
+
+/// Price of `denom` in the configured quote denom, for consumer contracts that
+/// only need a number they can trust to be recent.
+pub fn query_price(deps: Deps, now: Timestamp, denom: String) -> Result<PriceResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let quote = config.quote_denom.ok_or(ContractError::QuoteDenomNotSet {})?;
+    if denom == quote {
+        return Ok(PriceResponse { price: Decimal256::one(), publish_time: now, source_count: 0 });
+    }
+
+    let derived = derived_price(deps.storage, &denom, &quote, now)?;
+    if derived.oldest_publish_time.plus_seconds(config.max_price_age_seconds) < now {
+        return Err(ContractError::StalePrice {
+            denom,
+            publish_time: derived.oldest_publish_time.seconds(),
+            max_age_seconds: config.max_price_age_seconds,
+        });
+    }
+
+    Ok(PriceResponse {
+        price: derived.price,
+        publish_time: derived.oldest_publish_time,
+        source_count: derived.components.iter().map(|component| component.source_count).sum(),
+    })
+}
+
+pub fn query_prices(deps: Deps, base: String, quote: String) -> StdResult<Vec<(String, PriceRecord)>> {
+    PRICES
+        .prefix((&base, &quote))
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect()
+}
+
+pub fn query_quarantined_prices(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<(u64, QuarantinedPrice)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    QUARANTINED_PRICES
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
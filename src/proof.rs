@@ -0,0 +1,152 @@
+use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::{AbciQueryRequest, AbciQueryResponse};
+use cosmwasm_std::{Binary, Deps, DepsMut, Env, Event, MessageInfo, Order, Response, StdResult};
+use cw_storage_plus::Bound;
+
+use crate::contract::ensure_admin;
+use crate::error::ContractError;
+use crate::ics23::{ProofOpRef, verify_store_proof};
+use crate::msg::LightClientQueryMsg;
+use crate::state::{APP_HASHES, CONFIG, PROVEN_RESULTS, ProofStatus, ProvenResult, StoredProofOp};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// ABCI path of the raw KV store of a module, the request data being the key.
+pub fn proven_query_path(store: &str) -> String {
+    format!("/store/{store}/key")
+}
+
+fn store_from_path(path: &str) -> Option<&str> {
+    path.strip_prefix("/store/")?.strip_suffix("/key")
+}
+
+/// App hash submitted by the admin for `height`, or else the one the light
+/// client returns. A light client that cannot answer counts as not knowing it.
+fn trusted_app_hash(deps: Deps, channel: &str, height: u64) -> StdResult<Option<Binary>> {
+    if let Some(app_hash) = APP_HASHES.may_load(deps.storage, (channel, height))? {
+        return Ok(Some(app_hash));
+    }
+    let config = CONFIG.load(deps.storage)?;
+    let Some(light_client) = config.light_client else {
+        return Ok(None);
+    };
+    let msg = LightClientQueryMsg::AppHash { channel: channel.to_string(), height };
+    Ok(deps.querier.query_wasm_smart(light_client, &msg).ok().flatten())
+}
+
+fn check_proof(result: &ProvenResult, app_hash: &[u8]) -> ProofStatus {
+    let ops: Vec<ProofOpRef> = result
+        .proof_ops
+        .iter()
+        .map(|op| ProofOpRef { r#type: &op.r#type, key: op.key.as_slice(), data: op.data.as_slice() })
+        .collect();
+    match verify_store_proof(&ops, &result.store, &result.key, &result.value, app_hash) {
+        Ok(()) => ProofStatus::Verified,
+        Err(reason) => ProofStatus::Rejected { reason },
+    }
+}
+
+fn status_event(sequence: u64, result: &ProvenResult) -> Event {
+    let event = Event::new("proof_result")
+        .add_attribute("sequence", sequence.to_string())
+        .add_attribute("channel", &result.channel)
+        .add_attribute("store", &result.store)
+        .add_attribute("height", result.height.to_string());
+    match &result.status {
+        ProofStatus::Unverified => event.add_attribute("status", "unverified"),
+        ProofStatus::Verified => event.add_attribute("status", "verified"),
+        ProofStatus::Rejected { reason } => event.add_attribute("status", "rejected").add_attribute("reason", reason),
+    }
+}
+
+/// Stores the result of a proven query with its proof ops, verifying it right
+/// away when the app hash of its height is already known.
+pub fn record_proven(
+    deps: DepsMut,
+    env: &Env,
+    channel: &str,
+    sequence: u64,
+    request: &AbciQueryRequest,
+    response: &AbciQueryResponse,
+) -> Result<Event, ContractError> {
+    let store = store_from_path(&request.path).ok_or(ContractError::InvalidQueryPath { path: request.path.clone() })?;
+    let proof_ops = response
+        .proof_ops
+        .as_ref()
+        .map(|proof_ops| {
+            proof_ops
+                .ops
+                .iter()
+                .map(|op| StoredProofOp { r#type: op.r#type.clone(), key: op.key.clone().into(), data: op.data.clone().into() })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut result = ProvenResult {
+        channel: channel.to_string(),
+        store: store.to_string(),
+        key: request.data.clone().into(),
+        value: response.value.clone().into(),
+        height: response.height.max(0) as u64,
+        proof_ops,
+        status: ProofStatus::Unverified,
+        time: env.block.time,
+    };
+    if result.height == 0 {
+        result.status = ProofStatus::Rejected { reason: "response without height".to_string() };
+    } else if let Some(app_hash) = trusted_app_hash(deps.as_ref(), channel, result.height)? {
+        result.status = check_proof(&result, &app_hash);
+    }
+
+    PROVEN_RESULTS.save(deps.storage, sequence, &result)?;
+    Ok(status_event(sequence, &result))
+}
+
+/// Records the app hash committing to the state at `height`, i.e. the one in
+/// the header of `height + 1`. Admin only.
+pub fn submit_app_hash(deps: DepsMut, info: MessageInfo, channel: String, height: u64, app_hash: Binary) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+
+    APP_HASHES.save(deps.storage, (&channel, height), &app_hash)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "submit_app_hash")
+        .add_attribute("channel", channel)
+        .add_attribute("height", height.to_string())
+        .add_attribute("app_hash", app_hash.to_string()))
+}
+
+/// Verifies a result that arrived before the app hash of its height was known.
+pub fn verify_proof(deps: DepsMut, sequence: u64) -> Result<Response, ContractError> {
+    let mut result = PROVEN_RESULTS
+        .may_load(deps.storage, sequence)?
+        .ok_or(ContractError::NoSuchProvenResult { sequence })?;
+    if result.status != ProofStatus::Unverified {
+        return Err(ContractError::ProofAlreadyChecked { sequence });
+    }
+    let app_hash = trusted_app_hash(deps.as_ref(), &result.channel, result.height)?.ok_or(ContractError::NoAppHash {
+        channel: result.channel.clone(),
+        height: result.height,
+    })?;
+
+    result.status = check_proof(&result, &app_hash);
+    PROVEN_RESULTS.save(deps.storage, sequence, &result)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "verify_proof")
+        .add_event(status_event(sequence, &result)))
+}
+
+pub fn query_proven_result(deps: Deps, sequence: u64) -> Result<ProvenResult, ContractError> {
+    PROVEN_RESULTS
+        .may_load(deps.storage, sequence)?
+        .ok_or(ContractError::NoSuchProvenResult { sequence })
+}
+
+pub fn query_proven_results(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<(u64, ProvenResult)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    PROVEN_RESULTS
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
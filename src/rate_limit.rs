@@ -0,0 +1,173 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage};
+
+use crate::contract::ensure_admin;
+use crate::error::ContractError;
+use crate::msg::QuotaResponse;
+use crate::state::{Bucket, CHANNEL_BUCKETS, CHANNEL_IN_FLIGHT, CHANNEL_RATE_LIMITS, CONFIG, RateLimit, SENDER_BUCKETS, SENDER_IN_FLIGHT};
+
+/// Buckets hold `packets_per_window * window_blocks` units and gain
+/// `packets_per_window` units per block, while a packet costs `window_blocks`
+/// units. This keeps the refill exact without fractional tokens.
+fn refill(bucket: Option<Bucket>, limit: &RateLimit, height: u64) -> Bucket {
+    let max_units = limit.packets_per_window as u128 * limit.window_blocks as u128;
+    match bucket {
+        Some(bucket) => {
+            let elapsed = height.saturating_sub(bucket.height) as u128;
+            Bucket {
+                units: (bucket.units + elapsed * limit.packets_per_window as u128).min(max_units),
+                height,
+            }
+        }
+        None => Bucket { units: max_units, height },
+    }
+}
+
+fn ensure_limit(
+    bucket: &Bucket,
+    in_flight: u64,
+    limit: &RateLimit,
+    scope: String,
+) -> Result<(), ContractError> {
+    if bucket.units < limit.window_blocks as u128 {
+        return Err(ContractError::RateLimited { scope });
+    }
+    if in_flight >= limit.max_in_flight {
+        return Err(ContractError::TooManyInFlight { scope, max: limit.max_in_flight });
+    }
+    Ok(())
+}
+
+/// Buckets refilled to the current height, to be consumed once the packet is
+/// certain to be sent.
+pub struct RateLimitCheck {
+    sender: Option<Bucket>,
+    channel: Option<Bucket>,
+}
+
+/// Fails if either the sender or the channel is out of packets or has too many
+/// requests in flight. Does not write anything.
+pub fn check_rate_limits(storage: &dyn Storage, sender: &Addr, channel: &str, height: u64) -> Result<RateLimitCheck, ContractError> {
+    let config = CONFIG.load(storage)?;
+
+    let sender_bucket = match &config.sender_rate_limit {
+        Some(limit) => {
+            let bucket = refill(SENDER_BUCKETS.may_load(storage, sender)?, limit, height);
+            let in_flight = SENDER_IN_FLIGHT.may_load(storage, sender)?.unwrap_or_default();
+            ensure_limit(&bucket, in_flight, limit, format!("sender {sender}"))?;
+            Some(bucket)
+        }
+        None => None,
+    };
+
+    let channel_bucket = match CHANNEL_RATE_LIMITS.may_load(storage, channel)? {
+        Some(limit) => {
+            let bucket = refill(CHANNEL_BUCKETS.may_load(storage, channel)?, &limit, height);
+            let in_flight = CHANNEL_IN_FLIGHT.may_load(storage, channel)?.unwrap_or_default();
+            ensure_limit(&bucket, in_flight, &limit, format!("channel {channel}"))?;
+            Some(bucket)
+        }
+        None => None,
+    };
+
+    Ok(RateLimitCheck { sender: sender_bucket, channel: channel_bucket })
+}
+
+/// Takes one packet from the checked buckets and counts the request as in flight.
+pub fn consume_rate_limits(storage: &mut dyn Storage, sender: &Addr, channel: &str, check: RateLimitCheck) -> StdResult<()> {
+    let config = CONFIG.load(storage)?;
+    if let (Some(mut bucket), Some(limit)) = (check.sender, config.sender_rate_limit) {
+        bucket.units -= limit.window_blocks as u128;
+        SENDER_BUCKETS.save(storage, sender, &bucket)?;
+    }
+    if let (Some(mut bucket), Some(limit)) = (check.channel, CHANNEL_RATE_LIMITS.may_load(storage, channel)?) {
+        bucket.units -= limit.window_blocks as u128;
+        CHANNEL_BUCKETS.save(storage, channel, &bucket)?;
+    }
+
+    SENDER_IN_FLIGHT.update(storage, sender, |count| -> StdResult<_> { Ok(count.unwrap_or_default() + 1) })?;
+    CHANNEL_IN_FLIGHT.update(storage, channel, |count| -> StdResult<_> { Ok(count.unwrap_or_default() + 1) })?;
+    Ok(())
+}
+
+/// Called once the request of `sender` on `channel` is acknowledged or timed out.
+pub fn release_in_flight(storage: &mut dyn Storage, sender: &Addr, channel: &str) -> StdResult<()> {
+    SENDER_IN_FLIGHT.update(storage, sender, |count| -> StdResult<_> { Ok(count.unwrap_or_default().saturating_sub(1)) })?;
+    CHANNEL_IN_FLIGHT.update(storage, channel, |count| -> StdResult<_> { Ok(count.unwrap_or_default().saturating_sub(1)) })?;
+    Ok(())
+}
+
+fn validate_limit(limit: &Option<RateLimit>) -> Result<(), ContractError> {
+    if let Some(limit) = limit {
+        if limit.packets_per_window == 0 || limit.window_blocks == 0 {
+            return Err(ContractError::InvalidRateLimit {});
+        }
+    }
+    Ok(())
+}
+
+/// Sets the limit every sender is held to, None removes it. Admin only.
+pub fn set_sender_rate_limit(deps: DepsMut, info: MessageInfo, limit: Option<RateLimit>) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+    validate_limit(&limit)?;
+
+    CONFIG.update(deps.storage, |mut config| -> StdResult<_> {
+        config.sender_rate_limit = limit;
+        Ok(config)
+    })?;
+
+    Ok(Response::new().add_attribute("method", "set_sender_rate_limit"))
+}
+
+/// Sets the limit shared by all senders on `channel`, None removes it. Admin only.
+pub fn set_channel_rate_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel: String,
+    limit: Option<RateLimit>,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+    validate_limit(&limit)?;
+
+    match limit {
+        Some(limit) => CHANNEL_RATE_LIMITS.save(deps.storage, &channel, &limit)?,
+        None => CHANNEL_RATE_LIMITS.remove(deps.storage, &channel),
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "set_channel_rate_limit")
+        .add_attribute("channel", channel))
+}
+
+fn quota(bucket: Option<Bucket>, limit: Option<RateLimit>, in_flight: u64, height: u64) -> QuotaResponse {
+    match limit {
+        Some(limit) => {
+            let bucket = refill(bucket, &limit, height);
+            QuotaResponse {
+                packets_remaining: Some((bucket.units / limit.window_blocks as u128) as u64),
+                in_flight,
+                max_in_flight: Some(limit.max_in_flight),
+            }
+        }
+        None => QuotaResponse { packets_remaining: None, in_flight, max_in_flight: None },
+    }
+}
+
+pub fn query_sender_quota(deps: Deps, env: Env, sender: String) -> StdResult<QuotaResponse> {
+    let sender = deps.api.addr_validate(&sender)?;
+    let config = CONFIG.load(deps.storage)?;
+    Ok(quota(
+        SENDER_BUCKETS.may_load(deps.storage, &sender)?,
+        config.sender_rate_limit,
+        SENDER_IN_FLIGHT.may_load(deps.storage, &sender)?.unwrap_or_default(),
+        env.block.height,
+    ))
+}
+
+pub fn query_channel_quota(deps: Deps, env: Env, channel: String) -> StdResult<QuotaResponse> {
+    Ok(quota(
+        CHANNEL_BUCKETS.may_load(deps.storage, &channel)?,
+        CHANNEL_RATE_LIMITS.may_load(deps.storage, &channel)?,
+        CHANNEL_IN_FLIGHT.may_load(deps.storage, &channel)?.unwrap_or_default(),
+        env.block.height,
+    ))
+}
@@ -0,0 +1,115 @@
+use cosmos_sdk_proto::cosmos::bank::v1beta1::QuerySupplyOfResponse;
+use cosmwasm_std::{Addr, Api, CanonicalAddr, Deps, DepsMut, Env, Event, MessageInfo, Order, Response, StdResult, Uint128};
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+use crate::contract::{build_query_request, ensure_admin, PacketOutcome, prepare_icq_packet};
+use crate::error::ContractError;
+use crate::msg::{QuerySpec, ReconcileEscrowMsg};
+use crate::state::{ESCROW_TARGETS, EscrowTarget, Reconciliation, RECONCILIATIONS};
+use crate::validation::validate_denom;
+
+const TRANSFER_PORT: &str = "transfer";
+const ICS20_VERSION: &str = "ics20-1";
+
+/// Escrow account of an ICS-20 channel, derived the way ibc-go does.
+pub fn escrow_address(api: &dyn Api, port: &str, channel: &str) -> StdResult<Addr> {
+    let mut preimage = ICS20_VERSION.as_bytes().to_vec();
+    preimage.push(0);
+    preimage.extend(format!("{port}/{channel}").as_bytes());
+    let hash = Sha256::digest(&preimage);
+    api.addr_humanize(&CanonicalAddr::from(&hash[..20]))
+}
+
+pub fn reconcile_escrow(mut deps: DepsMut, env: Env, info: MessageInfo, msg: ReconcileEscrowMsg) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+    validate_denom(&msg.denom)?;
+
+    let spec = QuerySpec::SupplyOf { denom: msg.voucher_denom.clone() };
+    let req = build_query_request(deps.as_ref(), &env, &msg.channel, &spec)?;
+    let escrow = escrow_address(deps.api, TRANSFER_PORT, &msg.transfer_channel)?;
+    ESCROW_TARGETS.save(deps.storage, (&msg.channel, &msg.voucher_denom), &EscrowTarget {
+        transfer_channel: msg.transfer_channel,
+        denom: msg.denom,
+        escrow: escrow.clone(),
+    })?;
+
+    let res = Response::new()
+        .add_attribute("method", "reconcile_escrow")
+        .add_attribute("channel", &msg.channel)
+        .add_attribute("voucher_denom", &msg.voucher_denom)
+        .add_attribute("escrow", escrow);
+
+    match prepare_icq_packet(deps.branch(), &env, &info.sender, &msg.channel, vec![req])? {
+        PacketOutcome::Cached(cached) => {
+            let events = record_supply(deps, &env, &msg.channel, &msg.voucher_denom, &cached.value, 0)?;
+            Ok(res.add_attribute("cache_hit", "true").add_events(events))
+        }
+        PacketOutcome::Coalesced { request_id } => Ok(res
+            .add_attribute("coalesced", "true")
+            .add_attribute("request_id", request_id.to_string())),
+        PacketOutcome::Sent { request_id, msg } => Ok(res
+            .add_attribute("request_id", request_id.to_string())
+            .add_message(msg)),
+    }
+}
+
+/// Compares the remote supply of `voucher_denom` with the local escrow it is
+/// registered against, reading the escrow balance now. Returns an
+/// `escrow_discrepancy` event when they differ. Supplies of vouchers without a
+/// registered escrow are ignored.
+pub fn record_supply(
+    deps: DepsMut,
+    env: &Env,
+    channel: &str,
+    voucher_denom: &str,
+    value: &[u8],
+    height: u64,
+) -> Result<Option<Event>, ContractError> {
+    let Some(target) = ESCROW_TARGETS.may_load(deps.storage, (channel, voucher_denom))? else {
+        return Ok(None);
+    };
+    let supply = QuerySupplyOfResponse::decode(value)?;
+    let remote_supply = match supply.amount {
+        Some(coin) => coin.amount.parse::<Uint128>()?,
+        None => Uint128::zero(),
+    };
+    let escrow_amount = deps.querier.query_balance(&target.escrow, &target.denom)?.amount;
+
+    let reconciliation = Reconciliation {
+        transfer_channel: target.transfer_channel,
+        denom: target.denom,
+        escrow: target.escrow,
+        escrow_amount,
+        remote_supply,
+        remote_height: height,
+        matched: escrow_amount == remote_supply,
+        time: env.block.time,
+    };
+    RECONCILIATIONS.save(deps.storage, (channel, voucher_denom), &reconciliation)?;
+
+    if reconciliation.matched {
+        return Ok(None);
+    }
+    Ok(Some(
+        Event::new("escrow_discrepancy")
+            .add_attribute("channel", channel)
+            .add_attribute("voucher_denom", voucher_denom)
+            .add_attribute("transfer_channel", reconciliation.transfer_channel)
+            .add_attribute("denom", reconciliation.denom)
+            .add_attribute("escrow_amount", escrow_amount)
+            .add_attribute("remote_supply", remote_supply),
+    ))
+}
+
+pub fn query_reconciliation(deps: Deps, channel: String, voucher_denom: String) -> Result<Reconciliation, ContractError> {
+    RECONCILIATIONS
+        .may_load(deps.storage, (&channel, &voucher_denom))?
+        .ok_or(ContractError::NoSuchReconciliation { channel, voucher_denom })
+}
+
+pub fn query_reconciliations(deps: Deps) -> StdResult<Vec<((String, String), Reconciliation)>> {
+    RECONCILIATIONS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect()
+}
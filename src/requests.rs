@@ -0,0 +1,145 @@
+use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::AbciQueryRequest;
+use cosmwasm_std::{Addr, Binary, Event, from_json, IbcPacket, StdError, StdResult, Storage, Timestamp, Uint128};
+use prost::Message;
+
+use crate::fees::settle;
+use crate::rate_limit::release_in_flight;
+use crate::msg::{CosmosQuery, CosmosResponse, InterchainQueryPacketData};
+use crate::state::{CachedResult, IN_FLIGHT, NEXT_REQUEST_ID, PENDING_REQUESTS, PendingRequest, RESULT_CACHE};
+
+/// The memo of every packet carries the id of the request it belongs to, so
+/// acks and timeouts can be matched without predicting IBC sequences.
+const REQUEST_MEMO_PREFIX: &str = "icq-sender request ";
+
+pub fn request_memo(id: u64) -> String {
+    format!("{REQUEST_MEMO_PREFIX}{id}")
+}
+
+pub fn next_request_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = NEXT_REQUEST_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_REQUEST_ID.save(storage, &(id + 1))?;
+    Ok(id)
+}
+
+/// Returns the request id from the memo of one of our own packets, `None` for
+/// packets sent before requests were tracked.
+pub fn request_id_from_packet(packet: &IbcPacket) -> Option<u64> {
+    let packet_data: InterchainQueryPacketData = from_json(&packet.data).ok()?;
+    packet_data.memo.strip_prefix(REQUEST_MEMO_PREFIX)?.parse().ok()
+}
+
+/// Only packets carrying a single request are coalesced and cached, keyed by
+/// (channel, path, request bytes). Proven and height pinned requests never
+/// are, as a cached result carries neither a proof nor its height.
+pub fn single_request(requests: &[AbciQueryRequest]) -> Option<&AbciQueryRequest> {
+    match requests {
+        [request] if !request.prove && request.height == 0 => Some(request),
+        _ => None,
+    }
+}
+
+/// Returns the cached result for `request` if it is younger than `ttl_seconds`.
+pub fn fresh_cached_result(
+    storage: &dyn Storage,
+    channel: &str,
+    request: &AbciQueryRequest,
+    now: Timestamp,
+    ttl_seconds: u64,
+) -> StdResult<Option<CachedResult>> {
+    if ttl_seconds == 0 {
+        return Ok(None);
+    }
+    let cached = RESULT_CACHE.may_load(storage, (channel, &request.path, &request.data))?;
+    Ok(cached.filter(|cached| cached.time.plus_seconds(ttl_seconds) >= now))
+}
+
+/// Adds `sender` to the waiters of an identical request already in flight and
+/// returns its id, or `None` when a new packet has to be sent.
+pub fn join_in_flight(
+    storage: &mut dyn Storage,
+    channel: &str,
+    request: &AbciQueryRequest,
+    sender: &Addr,
+) -> StdResult<Option<u64>> {
+    let Some(id) = IN_FLIGHT.may_load(storage, (channel, &request.path, &request.data))? else {
+        return Ok(None);
+    };
+    let mut pending = PENDING_REQUESTS.load(storage, id)?;
+    if !pending.waiters.contains(sender) {
+        pending.waiters.push(sender.clone());
+        PENDING_REQUESTS.save(storage, id, &pending)?;
+    }
+    Ok(Some(id))
+}
+
+/// Records a new request before its packet is emitted.
+pub fn register_request(
+    storage: &mut dyn Storage,
+    channel: &str,
+    requests: &[AbciQueryRequest],
+    sender: &Addr,
+    fee: Uint128,
+    now: Timestamp,
+) -> StdResult<u64> {
+    let id = next_request_id(storage)?;
+    PENDING_REQUESTS.save(storage, id, &PendingRequest {
+        channel: channel.to_string(),
+        waiters: vec![sender.clone()],
+        sent_at: now,
+        fee,
+        batch: false,
+    })?;
+    if let Some(request) = single_request(requests) {
+        IN_FLIGHT.save(storage, (channel, &request.path, &request.data), &id)?;
+    }
+    Ok(id)
+}
+
+/// Clears the pending state of the request behind `packet`, settles its fee,
+/// caches a successful single response and returns one `icq_result` event per
+/// waiter.
+pub fn complete_request(
+    storage: &mut dyn Storage,
+    packet: &IbcPacket,
+    responses: Option<&CosmosResponse>,
+    status: &str,
+    now: Timestamp,
+) -> StdResult<Vec<Event>> {
+    let Some(id) = request_id_from_packet(packet) else {
+        return Ok(vec![]);
+    };
+    let Some(pending) = PENDING_REQUESTS.may_load(storage, id)? else {
+        return Ok(vec![]);
+    };
+    PENDING_REQUESTS.remove(storage, id);
+    let refunded = settle(storage, &pending, responses.is_none())?;
+    release_in_flight(storage, &pending.waiters[0], &pending.channel)?;
+
+    let packet_data: InterchainQueryPacketData = from_json(&packet.data)?;
+    let query = CosmosQuery::decode(packet_data.data.as_slice())
+        .map_err(|_| StdError::parse_err("CosmosQuery", "decoding original packet"))?;
+    if let Some(request) = single_request(&query.requests) {
+        let key = (pending.channel.as_str(), request.path.as_str(), request.data.as_slice());
+        IN_FLIGHT.remove(storage, key);
+        let response = responses.and_then(|responses| responses.responses.first());
+        if let Some(response) = response.filter(|response| response.code == 0) {
+            RESULT_CACHE.save(storage, key, &CachedResult {
+                value: Binary::from(response.value.clone()),
+                time: now,
+            })?;
+        }
+    }
+
+    Ok(pending
+        .waiters
+        .iter()
+        .map(|waiter| {
+            Event::new("icq_result")
+                .add_attribute("request_id", id.to_string())
+                .add_attribute("channel", &pending.channel)
+                .add_attribute("waiter", waiter)
+                .add_attribute("status", status)
+                .add_attribute("refunded", refunded.to_string())
+        })
+        .collect())
+}
@@ -0,0 +1,198 @@
+use cosmwasm_std::{Addr, BankMsg, Coin, Deps, DepsMut, Empty, Env, Event, MessageInfo, Order, Response, StdResult, Storage};
+use cw_storage_plus::Bound;
+
+use crate::contract::{build_query_request, ensure_admin, prepare_icq_packet, PacketOutcome};
+use crate::error::ContractError;
+use crate::msg::QuerySpec;
+use crate::state::{BOUNTY_POOL, CHANNEL_INFO, CONFIG, NEXT_SUBSCRIPTION_ID, Subscription, SUBSCRIPTION_QUEUE, SUBSCRIPTIONS};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn subscribe(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel: String,
+    query: QuerySpec,
+    interval_seconds: u64,
+) -> Result<Response, ContractError> {
+    if !CHANNEL_INFO.has(deps.storage, &channel) {
+        return Err(ContractError::NoSuchChannel { id: channel });
+    }
+    if interval_seconds == 0 {
+        return Err(ContractError::InvalidInterval {});
+    }
+    // reject specs that would fail on every poke
+    build_query_request(deps.as_ref(), &env, &channel, &query)?;
+
+    let id = add_subscription(deps.storage, &env, info.sender, channel.clone(), query, interval_seconds)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "subscribe")
+        .add_attribute("subscription_id", id.to_string())
+        .add_attribute("channel", channel))
+}
+
+/// Schedules `query`, the first one being due right away.
+pub fn add_subscription(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: Addr,
+    channel: String,
+    query: QuerySpec,
+    interval_seconds: u64,
+) -> StdResult<u64> {
+    let id = NEXT_SUBSCRIPTION_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_SUBSCRIPTION_ID.save(storage, &(id + 1))?;
+
+    let subscription = Subscription {
+        owner,
+        channel,
+        query,
+        interval_seconds,
+        next_due: env.block.time,
+    };
+    SUBSCRIPTIONS.save(storage, id, &subscription)?;
+    SUBSCRIPTION_QUEUE.save(storage, (subscription.next_due.seconds(), id), &Empty {})?;
+    Ok(id)
+}
+
+pub fn remove_subscription(storage: &mut dyn Storage, id: u64, subscription: &Subscription) {
+    SUBSCRIPTIONS.remove(storage, id);
+    SUBSCRIPTION_QUEUE.remove(storage, (subscription.next_due.seconds(), id));
+}
+
+pub fn unsubscribe(deps: DepsMut, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    let subscription = SUBSCRIPTIONS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::NoSuchSubscription { id })?;
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != subscription.owner && info.sender != config.admin {
+        return Err(ContractError::Unauthorized);
+    }
+
+    remove_subscription(deps.storage, id, &subscription);
+
+    Ok(Response::new()
+        .add_attribute("method", "unsubscribe")
+        .add_attribute("subscription_id", id.to_string()))
+}
+
+/// Sends the queries of up to `poke_batch_size` due subscriptions, oldest due
+/// first. A subscription that fails to send is rescheduled and reported in a
+/// `subscription_failed` event so one broken feed cannot block the others.
+/// When `keeper` is set and at least one packet of a subscription owned by the
+/// admin went out, the configured bounty is paid from the bounty pool if it
+/// can cover it. Subscribing is open to anyone, so the queries of other owners
+/// earn nothing, or subscribing and poking would drain the pool. The pool is
+/// tracked apart from the contract balance, which also holds credits and escrows.
+pub fn poke(mut deps: DepsMut, env: Env, keeper: Option<Addr>) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let now = env.block.time;
+
+    let due: Vec<(u64, u64)> = SUBSCRIPTION_QUEUE
+        .keys(deps.storage, None, Some(Bound::inclusive((now.seconds(), u64::MAX))), Order::Ascending)
+        .take(config.poke_batch_size as usize)
+        .collect::<StdResult<_>>()?;
+
+    let mut res = Response::new().add_attribute("method", "poke");
+    let mut sent = 0u64;
+    let mut rewarded = false;
+    for (due_seconds, id) in due {
+        SUBSCRIPTION_QUEUE.remove(deps.storage, (due_seconds, id));
+        let mut subscription = SUBSCRIPTIONS.load(deps.storage, id)?;
+
+        let outcome = build_query_request(deps.as_ref(), &env, &subscription.channel, &subscription.query)
+            .and_then(|req| prepare_icq_packet(deps.branch(), &env, &subscription.owner, &subscription.channel, vec![req]));
+        match outcome {
+            Ok(PacketOutcome::Sent { request_id, msg }) => {
+                sent += 1;
+                rewarded |= subscription.owner == config.admin;
+                res = res.add_message(msg).add_event(
+                    Event::new("subscription_sent")
+                        .add_attribute("subscription_id", id.to_string())
+                        .add_attribute("request_id", request_id.to_string()),
+                );
+            }
+            Ok(PacketOutcome::Cached(_)) | Ok(PacketOutcome::Coalesced { .. }) => {}
+            Err(err) => {
+                res = res.add_event(
+                    Event::new("subscription_failed")
+                        .add_attribute("subscription_id", id.to_string())
+                        .add_attribute("error", err.to_string()),
+                );
+            }
+        }
+
+        subscription.next_due = now.plus_seconds(subscription.interval_seconds);
+        SUBSCRIPTIONS.save(deps.storage, id, &subscription)?;
+        SUBSCRIPTION_QUEUE.save(deps.storage, (subscription.next_due.seconds(), id), &Empty {})?;
+    }
+
+    if let (Some(keeper), Some(bounty), true) = (keeper, config.poke_bounty, rewarded) {
+        let pool = BOUNTY_POOL.may_load(deps.storage, &bounty.denom)?.unwrap_or_default();
+        if pool >= bounty.amount {
+            BOUNTY_POOL.save(deps.storage, &bounty.denom, &(pool - bounty.amount))?;
+            res = res
+                .add_attribute("bounty", bounty.to_string())
+                .add_message(BankMsg::Send { to_address: keeper.into_string(), amount: vec![bounty] });
+        }
+    }
+
+    Ok(res.add_attribute("sent", sent.to_string()))
+}
+
+pub fn fund_bounty_pool(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+    if info.funds.iter().all(|coin| coin.amount.is_zero()) {
+        return Err(ContractError::NoFunds {});
+    }
+
+    for coin in &info.funds {
+        BOUNTY_POOL.update(deps.storage, &coin.denom, |pool| -> StdResult<_> {
+            Ok(pool.unwrap_or_default() + coin.amount)
+        })?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "fund_bounty_pool")
+        .add_attribute("amount", info.funds.iter().map(Coin::to_string).collect::<Vec<_>>().join(",")))
+}
+
+pub fn withdraw_bounty_pool(deps: DepsMut, info: MessageInfo, amount: Coin) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+    if amount.amount.is_zero() {
+        return Err(ContractError::NoFunds {});
+    }
+
+    let pool = BOUNTY_POOL.may_load(deps.storage, &amount.denom)?.unwrap_or_default();
+    if pool < amount.amount {
+        return Err(ContractError::InsufficientBountyPool { required: amount.amount, available: pool });
+    }
+    BOUNTY_POOL.save(deps.storage, &amount.denom, &(pool - amount.amount))?;
+
+    Ok(Response::new()
+        .add_attribute("method", "withdraw_bounty_pool")
+        .add_attribute("amount", amount.to_string())
+        .add_message(BankMsg::Send { to_address: info.sender.into_string(), amount: vec![amount] }))
+}
+
+pub fn query_subscription(deps: Deps, id: u64) -> StdResult<Subscription> {
+    SUBSCRIPTIONS.load(deps.storage, id)
+}
+
+pub fn query_subscriptions(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<(u64, Subscription)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    SUBSCRIPTIONS
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
+
+pub fn query_bounty_pool(deps: Deps) -> StdResult<Vec<Coin>> {
+    BOUNTY_POOL
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+        .collect()
+}
@@ -1,18 +1,189 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{DepsMut, IbcEndpoint, StdResult, Uint128};
+use cosmwasm_std::{Addr, Binary, Coin, CosmosMsg, Decimal256, DepsMut, Empty, HexBinary, IbcEndpoint, StdResult, Timestamp, Uint128};
 use cw_storage_plus::{Item, Map};
 use serde::{Deserialize, Serialize};
-use crate::msg::{ProtoCoin};
+use crate::msg::{PauseScope, ProtoCoin, QuerySpec};
+
+pub const CONFIG: Item<Config> = Item::new("config");
 
 /// static info on one channel that doesn't change
 pub const CHANNEL_INFO: Map<&str, ChannelInfo> = Map::new("channel_info");
 
+/// bech32 prefix expected for addresses on the chain behind a channel
+pub const CHANNEL_PREFIXES: Map<&str, String> = Map::new("channel_prefixes");
+
+/// ABCI query paths the host behind a channel is known to answer, keyed by (channel, path)
+pub const ALLOWED_QUERY_PATHS: Map<(&str, &str), Empty> = Map::new("allowed_query_paths");
+
 pub const ICQ_RESPONSES: Map<u64, ProtoCoin> = Map::new("icq_responses");
 
 pub const ICQ_PRICE_RESPONSES: Map<u64, String> = Map::new("icq_price_responses");
 
+/// undecoded values of responses to paths this contract has no decoder for
+pub const ICQ_RAW_RESPONSES: Map<u64, Binary> = Map::new("icq_raw_responses");
+
+pub const NEXT_REQUEST_ID: Item<u64> = Item::new("next_request_id");
+
+/// requests whose packet has been sent but not yet acknowledged or timed out
+pub const PENDING_REQUESTS: Map<u64, PendingRequest> = Map::new("pending_requests");
+
+/// id of the pending request for each (channel, path, request bytes)
+pub const IN_FLIGHT: Map<(&str, &str, &[u8]), u64> = Map::new("in_flight");
+
+/// last successful response value for each (channel, path, request bytes)
+pub const RESULT_CACHE: Map<(&str, &str, &[u8]), CachedResult> = Map::new("result_cache");
+
+pub const NEXT_SUBSCRIPTION_ID: Item<u64> = Item::new("next_subscription_id");
+
+pub const SUBSCRIPTIONS: Map<u64, Subscription> = Map::new("subscriptions");
+
+/// subscriptions ordered by the time (in seconds) their next query is due, keyed by (due, id)
+pub const SUBSCRIPTION_QUEUE: Map<(u64, u64), Empty> = Map::new("subscription_queue");
+
+/// funds the admin set aside for poke bounties, per denom
+pub const BOUNTY_POOL: Map<&str, Uint128> = Map::new("bounty_pool");
+
+/// fee charged per request of a query kind, in the configured fee denom
+pub const FEE_SCHEDULE: Map<&str, Uint128> = Map::new("fee_schedule");
+
+/// prepaid balance each sender's queries are paid from
+pub const CREDITS: Map<&Addr, Uint128> = Map::new("credits");
+
+/// fees of completed requests not yet collected by the admin
+pub const FEES_COLLECTED: Item<Uint128> = Item::new("fees_collected");
+
+/// limits shared by all senders on a channel, channels without one are unlimited
+pub const CHANNEL_RATE_LIMITS: Map<&str, RateLimit> = Map::new("channel_rate_limits");
+
+pub const SENDER_BUCKETS: Map<&Addr, Bucket> = Map::new("sender_buckets");
+
+pub const CHANNEL_BUCKETS: Map<&str, Bucket> = Map::new("channel_buckets");
+
+/// number of requests each sender has waiting for an ack or timeout
+pub const SENDER_IN_FLIGHT: Map<&Addr, u64> = Map::new("sender_in_flight");
+
+/// number of requests waiting for an ack or timeout on each channel
+pub const CHANNEL_IN_FLIGHT: Map<&str, u64> = Map::new("channel_in_flight");
+
+/// paused (channel, kind) scopes, "*" standing for any channel or kind
+pub const PAUSED: Map<(&str, &str), PauseScope> = Map::new("paused");
+
+/// current accepted price of each source, keyed by (base, quote, source)
+pub const PRICES: Map<(&str, &str, &str), PriceRecord> = Map::new("prices");
+
+/// feeds combining several sources of the same pair, keyed by (base, quote)
+pub const FEEDS: Map<(&str, &str), PriceFeed> = Map::new("feeds");
+
+/// prices that failed the sanity checks, keyed by packet sequence
+pub const QUARANTINED_PRICES: Map<u64, QuarantinedPrice> = Map::new("quarantined_prices");
+
+/// channel leading to the chain known by each alias
+pub const CHAIN_ALIASES: Map<&str, String> = Map::new("chain_aliases");
+
+/// holdings valued together, keyed by name
+pub const PORTFOLIOS: Map<&str, Portfolio> = Map::new("portfolios");
+
+/// latest balance returned for each (channel, address, denom)
+pub const BALANCES: Map<(&str, &str, &str), BalanceRecord> = Map::new("balances");
+
+/// every balance returned, keyed by ((channel, address, denom), block time in
+/// nanoseconds)
+pub const BALANCE_HISTORY: Map<((&str, &str, &str), u64), BalanceSample> = Map::new("balance_history");
+
+/// number of samples in the history of each (channel, address, denom)
+pub const BALANCE_HISTORY_LENGTHS: Map<(&str, &str, &str), u32> = Map::new("balance_history_lengths");
+
+/// (channel, address, denom) of a remote balance
+pub type BalanceKey<'a> = (&'a str, &'a str, &'a str);
+
+/// samples of a known height, keyed by ((channel, address, denom), height,
+/// block time in nanoseconds)
+pub const BALANCE_HISTORY_BY_HEIGHT: Map<(BalanceKey, u64, u64), Empty> = Map::new("balance_history_by_height");
+
+/// results of queries to remote CosmWasm contracts, keyed by packet sequence
+pub const WASM_RESULTS: Map<u64, WasmQueryResult> = Map::new("wasm_results");
+
+pub const NEXT_CONTRACT_WATCH_ID: Item<u64> = Item::new("next_contract_watch_id");
+
+pub const CONTRACT_WATCHES: Map<u64, ContractWatch> = Map::new("contract_watches");
+
+/// ids of the watches of each remote contract, keyed by (channel, contract)
+pub const CONTRACT_WATCHES_BY_ADDRESS: Map<(&str, &str), Vec<u64>> = Map::new("contract_watches_by_address");
+
+/// ids of the watches of contracts last seen running each code, keyed by
+/// (channel, code id)
+pub const CONTRACT_WATCHES_BY_CODE: Map<(&str, u64), Vec<u64>> = Map::new("contract_watches_by_code");
+
+/// validators whose health is polled, keyed by (channel, operator address)
+pub const VALIDATOR_WATCHES: Map<(&str, &str), ValidatorWatch> = Map::new("validator_watches");
+
+/// validator watches by (channel, consensus address, operator address), so a
+/// signing info only loads the watches of its validator
+pub const VALIDATOR_WATCHES_BY_CONSENSUS: Map<(&str, &str, &str), Empty> = Map::new("validator_watches_by_consensus");
+
+/// local escrow backing each IBC voucher, keyed by (channel, voucher denom)
+pub const ESCROW_TARGETS: Map<(&str, &str), EscrowTarget> = Map::new("escrow_targets");
+
+/// latest reconciliation of each voucher, keyed by (channel, voucher denom)
+pub const RECONCILIATIONS: Map<(&str, &str), Reconciliation> = Map::new("reconciliations");
+
+/// origin of each resolved `ibc/` denom, keyed by (channel, denom)
+pub const DENOM_TRACES: Map<(&str, &str), DenomTrace> = Map::new("denom_traces");
+
+/// latest known state of each remote governance proposal, keyed by (channel, proposal id)
+pub const PROPOSALS: Map<(&str, u64), ProposalRecord> = Map::new("proposals");
+
+/// account number and sequence of remote accounts, keyed by (channel, address)
+pub const REMOTE_ACCOUNTS: Map<(&str, &str), RemoteAccount> = Map::new("remote_accounts");
+
+/// authz grants between two remote accounts, keyed by (channel, granter, grantee)
+pub const AUTHZ_GRANTS: Map<(&str, &str, &str), Vec<AuthzGrant>> = Map::new("authz_grants");
+
+/// fee allowance granted between two remote accounts, keyed by (channel, granter, grantee)
+pub const FEE_ALLOWANCES: Map<(&str, &str, &str), FeeAllowance> = Map::new("fee_allowances");
+
+/// exponent of the display unit of each denom, keyed by (channel, denom)
+pub const DENOM_EXPONENTS: Map<(&str, &str), u32> = Map::new("denom_exponents");
+
+pub const NEXT_WATCH_ID: Item<u64> = Item::new("next_watch_id");
+
+pub const WATCHES: Map<u64, Watch> = Map::new("watches");
+
+/// ids of the watches of each balance, keyed by (channel, address, denom)
+pub const WATCHES_BY_BALANCE: Map<(&str, &str, &str), Vec<u64>> = Map::new("watches_by_balance");
+
+pub const NEXT_CONDITION_ID: Item<u64> = Item::new("next_condition_id");
+
+pub const CONDITIONS: Map<u64, Condition> = Map::new("conditions");
+
+/// conditions still waiting for their price, evaluated after every TWAP ack
+pub const PENDING_CONDITIONS: Map<u64, Empty> = Map::new("pending_conditions");
+
+/// number of pending conditions of each owner
+pub const PENDING_CONDITION_COUNTS: Map<&Addr, u64> = Map::new("pending_condition_counts");
+
+/// id of the last pending condition evaluated, the next evaluation continuing after it
+pub const CONDITION_CURSOR: Item<u64> = Item::new("condition_cursor");
+
 pub const LAST_SEQUENCE_RECEIVE: Item<u64> = Item::new("last_sequence_receive");
 
+/// app hash committing to the state the host behind a channel returns for
+/// queries at a height, keyed by (channel, height). It is the app hash in the
+/// header of the next height.
+pub const APP_HASHES: Map<(&str, u64), Binary> = Map::new("app_hashes");
+
+/// host height of each response of a packet, keyed by packet sequence
+pub const RESPONSE_HEIGHTS: Map<u64, Vec<u64>> = Map::new("response_heights");
+
+/// responses to SendQueryBatch, keyed by packet sequence
+pub const BATCH_RESULTS: Map<u64, BatchResult> = Map::new("batch_results");
+
+/// results of proven queries with their proofs, keyed by packet sequence
+pub const PROVEN_RESULTS: Map<u64, ProvenResult> = Map::new("proven_results");
+
+/// ABCI query paths answered for counterparties when acting as a host
+pub const HOST_QUERY_PATHS: Map<&str, Empty> = Map::new("host_query_paths");
+
 pub const ICQ_ERRORS: Map<u64, String> = Map::new("icq_errors");
 
 pub const LAST_SEQUENCE_ACKNOWLEDGMENT: Item<u64> = Item::new("last_sequence_acknowledgment");
@@ -37,6 +208,485 @@ pub fn get_next_sequence_send(deps: &mut DepsMut) -> StdResult<u64> {
     }
 }
 
+#[cw_serde]
+pub struct Config {
+    /// address allowed to change the contract configuration
+    pub admin: Addr,
+    /// how long a cached result is returned instead of sending a new packet, 0 disables caching
+    pub cache_ttl_seconds: u64,
+    /// maximum number of due subscriptions sent by one poke
+    pub poke_batch_size: u32,
+    /// paid from the bounty pool to the caller of a poke that sent at least one packet
+    pub poke_bounty: Option<Coin>,
+    /// denom credits are deposited in, deposits are rejected while unset
+    pub fee_denom: Option<String>,
+    /// return the fee to the payer when the host errors or the packet times out
+    pub refund_on_failure: bool,
+    /// limit each sender is held to, unlimited when unset
+    pub sender_rate_limit: Option<RateLimit>,
+    /// may pause and unpause sends besides the admin
+    pub guardian: Option<Addr>,
+    pub price_bounds: PriceBounds,
+    /// asset cross rates are triangulated through when no pair quotes them directly
+    pub intermediate_asset: Option<String>,
+    /// contract answering `LightClientQueryMsg::AppHash` for heights without a submitted app hash
+    pub light_client: Option<Addr>,
+    /// currency GetPrice quotes every denom in, GetPrice fails while unset
+    pub quote_denom: Option<String>,
+    /// GetPrice fails once the oldest price it is derived from is older than this
+    pub max_price_age_seconds: u64,
+    /// remote grants expiring within this are reported by a `grant_expiring` event
+    pub grant_expiry_warning_seconds: u64,
+}
+
+/// Checks every incoming TWAP must pass to become the current price, unset
+/// bounds are not checked. Zero prices are always rejected.
+#[cw_serde]
+#[derive(Default)]
+pub struct PriceBounds {
+    /// largest relative change from the last accepted price of the source, 0.1 being 10%
+    pub max_deviation: Option<Decimal256>,
+    /// largest delay between the end of the TWAP window and the ack
+    pub max_age_seconds: Option<u64>,
+}
+
+#[cw_serde]
+pub struct PriceRecord {
+    pub price: Decimal256,
+    /// block time the price was accepted at
+    pub publish_time: Timestamp,
+    pub sequence: u64,
+}
+
+/// One pair quoted by several pools, possibly on different DEX chains
+#[cw_serde]
+pub struct PriceFeed {
+    pub base: String,
+    pub quote: String,
+    pub sources: Vec<FeedSource>,
+    pub aggregation: Aggregation,
+    /// fresh sources required for the feed to have a price
+    pub min_sources: u32,
+    /// sources not updated within this window are ignored
+    pub max_staleness_seconds: u64,
+}
+
+#[cw_serde]
+pub struct FeedSource {
+    pub channel: String,
+    pub pool_id: u64,
+    /// the pair's denoms as known on the source chain, either the feed's base
+    /// and quote or `ibc/` denoms already resolved to them
+    pub base_asset: String,
+    pub quote_asset: String,
+    /// only used by `Aggregation::WeightedMean`
+    pub weight: Decimal256,
+}
+
+#[cw_serde]
+pub enum Aggregation {
+    Median,
+    WeightedMean,
+}
+
+#[cw_serde]
+pub struct QuarantinedPrice {
+    pub base: String,
+    pub quote: String,
+    pub source: String,
+    /// the string returned by the host
+    pub raw: String,
+    /// None when `raw` could not be parsed
+    pub price: Option<Decimal256>,
+    pub reason: String,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct Portfolio {
+    /// may replace, remove and refresh the portfolio
+    pub owner: Addr,
+    pub holdings: Vec<Holding>,
+}
+
+/// Denoms held by one address on the chain behind `chain`, a chain alias
+#[cw_serde]
+pub struct Holding {
+    pub chain: String,
+    pub address: String,
+    pub denoms: Vec<String>,
+}
+
+#[cw_serde]
+pub struct BalanceRecord {
+    pub coin: ProtoCoin,
+    pub time: Timestamp,
+    /// origin of the denom, if it was resolved when the balance arrived
+    #[serde(default)]
+    pub trace: Option<DenomTrace>,
+}
+
+/// `path` is the chain of port/channel hops the denom took, e.g.
+/// `transfer/channel-0`
+#[cw_serde]
+pub struct DenomTrace {
+    pub path: String,
+    pub base_denom: String,
+}
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum ProposalStatus {
+    Unspecified,
+    DepositPeriod,
+    VotingPeriod,
+    Passed,
+    Rejected,
+    Failed,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct ProposalTally {
+    pub yes: Uint128,
+    pub abstain: Uint128,
+    pub no: Uint128,
+    pub no_with_veto: Uint128,
+}
+
+#[cw_serde]
+pub struct ProposalRecord {
+    pub title: String,
+    pub status: ProposalStatus,
+    pub voting_end_time: Option<Timestamp>,
+    /// final tally, all zero until the voting period ended
+    pub tally: ProposalTally,
+    pub height: u64,
+    pub updated_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct RemoteAccount {
+    /// type URL of the account, e.g. `/cosmos.auth.v1beta1.BaseAccount`
+    pub account_type: String,
+    pub account_number: u64,
+    pub sequence: u64,
+    pub height: u64,
+    pub updated_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct AuthzGrant {
+    /// type URL of the authorization, e.g. `/cosmos.authz.v1beta1.GenericAuthorization`
+    pub authorization: String,
+    /// message a generic authorization permits
+    pub msg_type_url: Option<String>,
+    /// None when the grant does not expire
+    pub expiration: Option<Timestamp>,
+    /// expires within the warning window as of `updated_at`
+    pub expiring: bool,
+    pub updated_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct FeeAllowance {
+    /// type URL of the allowance, e.g. `/cosmos.feegrant.v1beta1.BasicAllowance`
+    pub allowance: String,
+    /// empty when the allowance is unlimited
+    pub spend_limit: Vec<Coin>,
+    /// None when the allowance does not expire
+    pub expiration: Option<Timestamp>,
+    /// messages the allowance pays for, empty meaning all of them
+    pub allowed_messages: Vec<String>,
+    /// expires within the warning window as of `updated_at`
+    pub expiring: bool,
+    pub updated_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct WasmQueryResult {
+    pub channel: String,
+    pub contract: String,
+    pub data: WasmQueryData,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub enum WasmQueryData {
+    /// JSON answered by the contract, as returned by the host
+    Smart { msg: Binary, response: Binary },
+    /// value under `key`, empty if the key is not set
+    Raw { key: Binary, value: Binary },
+    ContractInfo(RemoteContractInfo),
+}
+
+#[cw_serde]
+pub struct RemoteContractInfo {
+    pub code_id: u64,
+    pub creator: String,
+    /// empty when the contract has no admin
+    pub admin: String,
+    pub label: String,
+    pub ibc_port_id: String,
+}
+
+/// Remote contract whose info is polled by subscription `subscription_id`.
+/// The first values received are the baseline later ones are compared to.
+#[cw_serde]
+pub struct ContractWatch {
+    pub chain: String,
+    pub channel: String,
+    pub contract: String,
+    /// pays for the queries of the code info
+    pub owner: Addr,
+    pub subscription_id: u64,
+    pub callback: Option<WatchCallback>,
+    pub info: Option<RemoteContractInfo>,
+    /// checksum of the code of `info`, once its code info arrived
+    pub checksum: Option<HexBinary>,
+    pub checked_at: Option<Timestamp>,
+}
+
+/// Remote validator whose staking info is polled by subscription
+/// `subscription_id`, its signing info being queried after each answer. The
+/// first values received are the baseline later ones are compared to.
+#[cw_serde]
+pub struct ValidatorWatch {
+    pub chain: String,
+    pub channel: String,
+    pub operator_address: String,
+    /// pays for the queries of the signing info
+    pub owner: Addr,
+    pub subscription_id: u64,
+    /// derived from the consensus key, None while unknown or not ed25519
+    pub consensus_address: Option<String>,
+    pub validator: Option<ValidatorState>,
+    pub signing_info: Option<SigningState>,
+    pub checked_at: Option<Timestamp>,
+}
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum ValidatorStatus {
+    Unspecified,
+    Unbonded,
+    Unbonding,
+    Bonded,
+}
+
+#[cw_serde]
+pub struct ValidatorState {
+    pub moniker: String,
+    pub status: ValidatorStatus,
+    pub jailed: bool,
+    pub commission_rate: Decimal256,
+    pub max_commission_rate: Decimal256,
+}
+
+#[cw_serde]
+pub struct SigningState {
+    /// blocks missed within the current signing window
+    pub missed_blocks_counter: u64,
+    /// the largest timestamp for a validator jailed forever
+    pub jailed_until: Option<Timestamp>,
+    pub tombstoned: bool,
+}
+
+/// ICS-20 escrow of `denom` on `transfer_channel`, which the remote supply of
+/// its voucher should equal
+#[cw_serde]
+pub struct EscrowTarget {
+    pub transfer_channel: String,
+    pub denom: String,
+    pub escrow: Addr,
+}
+
+#[cw_serde]
+pub struct Reconciliation {
+    pub transfer_channel: String,
+    pub denom: String,
+    pub escrow: Addr,
+    pub escrow_amount: Uint128,
+    pub remote_supply: Uint128,
+    /// host height of the supply, 0 if unknown or answered from the cache
+    pub remote_height: u64,
+    pub matched: bool,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct BalanceSample {
+    pub amount: Uint128,
+    /// host height the balance was read at, 0 if the host did not say
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+/// Alerts when a remote balance crosses `threshold` in `direction`. Once
+/// triggered it only fires again after the balance has moved back past the
+/// threshold by at least `hysteresis`.
+#[cw_serde]
+pub struct Watch {
+    pub chain: String,
+    /// channel the chain alias pointed to when the watch was added
+    pub channel: String,
+    pub address: String,
+    pub denom: String,
+    pub threshold: Uint128,
+    pub direction: WatchDirection,
+    pub hysteresis: Uint128,
+    pub callback: Option<WatchCallback>,
+    pub triggered: bool,
+}
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum WatchDirection {
+    /// fires when the balance drops below the threshold
+    Below,
+    /// fires when the balance rises above the threshold
+    Above,
+}
+
+/// Executed on `contract` without funds whenever the watch fires. A failing
+/// callback does not fail the ack it was triggered by.
+#[cw_serde]
+pub struct WatchCallback {
+    pub contract: String,
+    pub msg: Binary,
+}
+
+/// Executes `msg` once, as soon as an ack brings the price of `base` in
+/// `quote` past `threshold`.
+#[cw_serde]
+pub struct Condition {
+    /// may cancel the condition, receives `escrow` back if it is cancelled or `msg` fails
+    pub owner: Addr,
+    pub base: String,
+    pub quote: String,
+    pub comparison: Comparison,
+    pub threshold: Decimal256,
+    pub msg: CosmosMsg,
+    /// funds attached when the condition was added, spent by `msg`
+    pub escrow: Vec<Coin>,
+    pub status: ConditionStatus,
+}
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum Comparison {
+    Below,
+    Above,
+}
+
+#[cw_serde]
+pub enum ConditionStatus {
+    Pending,
+    /// `msg` was dispatched when the price was `price`
+    Executed { price: Decimal256, time: Timestamp },
+    /// `msg` was dispatched and failed, the escrow was refunded
+    Failed { price: Decimal256, time: Timestamp, error: String },
+    Cancelled,
+}
+
+/// Value of `key` in the store `store` at `height`, as returned by the host
+#[cw_serde]
+pub struct ProvenResult {
+    pub channel: String,
+    pub store: String,
+    pub key: Binary,
+    pub value: Binary,
+    pub height: u64,
+    pub proof_ops: Vec<StoredProofOp>,
+    pub status: ProofStatus,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct StoredProofOp {
+    pub r#type: String,
+    pub key: Binary,
+    pub data: Binary,
+}
+
+#[cw_serde]
+pub enum ProofStatus {
+    /// no app hash is known for the height yet
+    Unverified,
+    Verified,
+    /// the proof does not hold, the value must not be trusted
+    Rejected { reason: String },
+}
+
+#[cw_serde]
+pub struct BatchResult {
+    pub channel: String,
+    /// height the batch was pinned to, if any
+    pub height: Option<u64>,
+    pub responses: Vec<BatchResponse>,
+    /// every response was answered at the same height, the pinned one if any
+    pub consistent: bool,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct BatchResponse {
+    pub path: String,
+    pub height: u64,
+    /// non zero when the host failed to answer, `value` then being empty
+    pub code: u32,
+    pub value: Binary,
+}
+
+#[cw_serde]
+pub struct RateLimit {
+    /// packets allowed per window, which is also the largest burst
+    pub packets_per_window: u64,
+    pub window_blocks: u64,
+    /// requests that may wait for their ack or timeout at the same time
+    pub max_in_flight: u64,
+}
+
+/// Token bucket state, see `rate_limit::refill`
+#[cw_serde]
+pub struct Bucket {
+    pub units: u128,
+    /// block height the units were last refilled at
+    pub height: u64,
+}
+
+#[cw_serde]
+pub struct PendingRequest {
+    pub channel: String,
+    /// addresses that asked for this result, the original sender first
+    pub waiters: Vec<Addr>,
+    pub sent_at: Timestamp,
+    /// taken from the credits of the first waiter when the packet was sent
+    pub fee: Uint128,
+    /// sent by SendQueryBatch, its responses must share one height
+    #[serde(default)]
+    pub batch: bool,
+}
+
+/// A query sent again every `interval_seconds` when the contract is poked
+#[cw_serde]
+pub struct Subscription {
+    /// pays for and is notified of the queries, may cancel the subscription
+    pub owner: Addr,
+    pub channel: String,
+    pub query: QuerySpec,
+    pub interval_seconds: u64,
+    pub next_due: Timestamp,
+}
+
+#[cw_serde]
+pub struct CachedResult {
+    /// proto encoded response value as returned by the host
+    pub value: Binary,
+    pub time: Timestamp,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct SequenceState {
     pub next_sequence_send: u64,
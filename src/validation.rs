@@ -0,0 +1,104 @@
+use cosmwasm_std::{Binary, from_json};
+use serde::de::IgnoredAny;
+
+use crate::error::ContractError;
+
+/// Longest denom accepted by the cosmos-sdk bank module.
+const MAX_DENOM_LENGTH: usize = 128;
+
+/// Longest subdenom accepted by the tokenfactory module.
+const MAX_SUBDENOM_LENGTH: usize = 44;
+
+/// Length of the hex encoded sha256 hash in an `ibc/` denom.
+const IBC_HASH_LENGTH: usize = 64;
+
+/// Decodes a bech32 address of a remote chain and, when the channel has one
+/// configured, checks its human readable prefix.
+pub fn validate_remote_address(address: &str, expected_prefix: Option<&str>) -> Result<(), ContractError> {
+    let (hrp, data) = bech32::decode(address).map_err(|err| ContractError::InvalidAddress {
+        address: address.to_string(),
+        reason: err.to_string(),
+    })?;
+
+    if data.is_empty() {
+        return Err(ContractError::InvalidAddress {
+            address: address.to_string(),
+            reason: "empty address payload".to_string(),
+        });
+    }
+
+    if let Some(expected) = expected_prefix {
+        let actual = hrp.to_lowercase();
+        if actual != expected {
+            return Err(ContractError::AddressPrefixMismatch {
+                address: address.to_string(),
+                expected: expected.to_string(),
+                actual,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the denom against the sdk syntax and the rules of the `ibc/` and
+/// `factory/` namespaces.
+pub fn validate_denom(denom: &str) -> Result<(), ContractError> {
+    let invalid = |reason: &str| ContractError::InvalidDenom {
+        denom: denom.to_string(),
+        reason: reason.to_string(),
+    };
+
+    if denom.len() < 3 || denom.len() > MAX_DENOM_LENGTH {
+        return Err(invalid("length must be between 3 and 128 characters"));
+    }
+    if !denom.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Err(invalid("must start with a letter"));
+    }
+    if !denom.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | ':' | '.' | '_' | '-')) {
+        return Err(invalid("contains characters outside [a-zA-Z0-9/:._-]"));
+    }
+
+    if let Some(hash) = denom.strip_prefix("ibc/") {
+        if hash.len() != IBC_HASH_LENGTH || !hash.chars().all(|c| c.is_ascii_digit() || ('A'..='F').contains(&c)) {
+            return Err(invalid("ibc denoms must be ibc/ followed by an uppercase hex sha256 hash"));
+        }
+    } else if let Some(rest) = denom.strip_prefix("factory/") {
+        let (creator, subdenom) = rest
+            .split_once('/')
+            .ok_or_else(|| invalid("factory denoms must be factory/{creator}/{subdenom}"))?;
+        validate_remote_address(creator, None).map_err(|_| invalid("factory creator is not a bech32 address"))?;
+        if subdenom.is_empty() || subdenom.len() > MAX_SUBDENOM_LENGTH {
+            return Err(invalid("factory subdenom must be between 1 and 44 characters"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates the pool and asset pair of an osmosis TWAP query.
+pub fn validate_twap_query(pool_id: u64, base_asset: &str, quote_asset: &str) -> Result<(), ContractError> {
+    if pool_id == 0 {
+        return Err(ContractError::InvalidPoolId { pool_id });
+    }
+    validate_denom(base_asset)?;
+    validate_denom(quote_asset)?;
+    if base_asset == quote_asset {
+        return Err(ContractError::IdenticalTwapAssets { denom: base_asset.to_string() });
+    }
+    Ok(())
+}
+
+/// A smart query has to be valid JSON for the remote contract to parse it.
+pub fn validate_smart_query(msg: &Binary) -> Result<(), ContractError> {
+    from_json::<IgnoredAny>(msg).map_err(|err| ContractError::InvalidSmartQuery { reason: err.to_string() })?;
+    Ok(())
+}
+
+/// Store names are the keys modules mount their KV stores under, e.g. "bank".
+pub fn validate_store_name(store: &str) -> Result<(), ContractError> {
+    if store.is_empty() || !store.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(ContractError::InvalidStoreName { store: store.to_string() });
+    }
+    Ok(())
+}
@@ -0,0 +1,251 @@
+use std::str::FromStr;
+
+use bech32::{Bech32, Hrp};
+use cosmos_sdk_proto::Any;
+use cosmos_sdk_proto::cosmos::crypto::ed25519::PubKey;
+use cosmos_sdk_proto::cosmos::slashing::v1beta1::{QuerySigningInfoResponse, ValidatorSigningInfo};
+use cosmos_sdk_proto::cosmos::staking::v1beta1::{BondStatus, Validator};
+use cosmwasm_std::{Decimal256, Deps, DepsMut, Empty, Env, Event, MessageInfo, Order, Response, StdError, StdResult, Storage, SubMsg, Timestamp, Uint256};
+use cw_storage_plus::Bound;
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+use crate::contract::{block_time, build_query_request, ensure_admin, PacketOutcome, prepare_icq_packet};
+use crate::error::ContractError;
+use crate::msg::{QuerySpec, WatchValidatorMsg};
+use crate::portfolio::resolve_chain;
+use crate::schedule::{add_subscription, remove_subscription};
+use crate::state::{SigningState, SUBSCRIPTIONS, VALIDATOR_WATCHES, VALIDATOR_WATCHES_BY_CONSENSUS, ValidatorState, ValidatorStatus, ValidatorWatch};
+
+const ED25519_PUBKEY_TYPE: &str = "/cosmos.crypto.ed25519.PubKey";
+
+/// Jail end recorded for a tombstoned validator, whose jail the host sets to
+/// end in the year 9999.
+const JAILED_FOREVER: Timestamp = Timestamp::from_nanos(u64::MAX);
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn watch_validator(deps: DepsMut, env: Env, info: MessageInfo, msg: WatchValidatorMsg) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+    let WatchValidatorMsg { chain, operator_address, interval_seconds } = msg;
+
+    if interval_seconds == 0 {
+        return Err(ContractError::InvalidInterval {});
+    }
+    let channel = resolve_chain(deps.storage, &chain)?;
+    let spec = QuerySpec::StakingValidator { operator_address: operator_address.clone() };
+    build_query_request(deps.as_ref(), &env, &channel, &spec)?;
+
+    // watching again replaces the subscription of the previous watch
+    if let Some(old) = VALIDATOR_WATCHES.may_load(deps.storage, (&channel, &operator_address))? {
+        if let Some(subscription) = SUBSCRIPTIONS.may_load(deps.storage, old.subscription_id)? {
+            remove_subscription(deps.storage, old.subscription_id, &subscription);
+        }
+        unindex_consensus(deps.storage, &old);
+    }
+    let subscription_id = add_subscription(deps.storage, &env, info.sender.clone(), channel.clone(), spec, interval_seconds)?;
+
+    VALIDATOR_WATCHES.save(deps.storage, (&channel, &operator_address), &ValidatorWatch {
+        chain,
+        channel: channel.clone(),
+        operator_address: operator_address.clone(),
+        owner: info.sender,
+        subscription_id,
+        consensus_address: None,
+        validator: None,
+        signing_info: None,
+        checked_at: None,
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("method", "watch_validator")
+        .add_attribute("channel", channel)
+        .add_attribute("operator_address", operator_address)
+        .add_attribute("subscription_id", subscription_id.to_string()))
+}
+
+pub fn unwatch_validator(deps: DepsMut, info: MessageInfo, chain: String, operator_address: String) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+
+    let channel = resolve_chain(deps.storage, &chain)?;
+    let watch = VALIDATOR_WATCHES
+        .may_load(deps.storage, (&channel, &operator_address))?
+        .ok_or(ContractError::NoSuchValidatorWatch { chain, operator_address: operator_address.clone() })?;
+    VALIDATOR_WATCHES.remove(deps.storage, (&channel, &operator_address));
+    unindex_consensus(deps.storage, &watch);
+    if let Some(subscription) = SUBSCRIPTIONS.may_load(deps.storage, watch.subscription_id)? {
+        remove_subscription(deps.storage, watch.subscription_id, &subscription);
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "unwatch_validator")
+        .add_attribute("operator_address", operator_address))
+}
+
+fn unindex_consensus(storage: &mut dyn Storage, watch: &ValidatorWatch) {
+    if let Some(consensus_address) = &watch.consensus_address {
+        VALIDATOR_WATCHES_BY_CONSENSUS.remove(storage, (&watch.channel, consensus_address, &watch.operator_address));
+    }
+}
+
+/// Consensus address of an ed25519 consensus key, under the `valcons`
+/// counterpart of the prefix of the operator address.
+fn consensus_address(operator_address: &str, pubkey: &Any) -> Option<String> {
+    if pubkey.type_url != ED25519_PUBKEY_TYPE {
+        return None;
+    }
+    let key = PubKey::decode(pubkey.value.as_slice()).ok()?;
+    let (hrp, _) = bech32::decode(operator_address).ok()?;
+    let prefix = hrp.as_str().strip_suffix("valoper")?;
+    let hrp = Hrp::parse(&format!("{prefix}valcons")).ok()?;
+    bech32::encode::<Bech32>(hrp, &Sha256::digest(&key.key)[..20]).ok()
+}
+
+/// Rates arrive as the integer form of an 18 decimal fixed point number,
+/// hosts on older sdk versions answering them in decimal notation.
+fn commission_rate(rate: &str) -> StdResult<Decimal256> {
+    if rate.is_empty() {
+        return Ok(Decimal256::zero());
+    }
+    if rate.contains('.') {
+        return Decimal256::from_str(rate);
+    }
+    Decimal256::from_atomics(Uint256::from_str(rate)?, 18).map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+fn validator_status(code: i32) -> ValidatorStatus {
+    match BondStatus::try_from(code) {
+        Ok(BondStatus::Unbonded) => ValidatorStatus::Unbonded,
+        Ok(BondStatus::Unbonding) => ValidatorStatus::Unbonding,
+        Ok(BondStatus::Bonded) => ValidatorStatus::Bonded,
+        _ => ValidatorStatus::Unspecified,
+    }
+}
+
+fn validator_event(ty: &str, watch: &ValidatorWatch) -> Event {
+    Event::new(ty)
+        .add_attribute("chain", &watch.chain)
+        .add_attribute("operator_address", &watch.operator_address)
+}
+
+/// Compares the staking info that just arrived with the last known one of
+/// the watched validator, then queries its signing info, paid by the owner
+/// of the watch. Returns the events to emit and the packet of that query.
+pub fn evaluate_validator(mut deps: DepsMut, env: &Env, channel: &str, validator: &Validator) -> Result<(Vec<Event>, Vec<SubMsg>), ContractError> {
+    let Some(mut watch) = VALIDATOR_WATCHES.may_load(deps.storage, (channel, &validator.operator_address))? else {
+        return Ok((vec![], vec![]));
+    };
+    let rates = validator
+        .commission
+        .as_ref()
+        .and_then(|commission| commission.commission_rates.clone())
+        .unwrap_or_default();
+    let state = ValidatorState {
+        moniker: validator.description.as_ref().map(|description| description.moniker.clone()).unwrap_or_default(),
+        status: validator_status(validator.status),
+        jailed: validator.jailed,
+        commission_rate: commission_rate(&rates.rate)?,
+        max_commission_rate: commission_rate(&rates.max_rate)?,
+    };
+
+    let mut events = vec![];
+    if let Some(old) = &watch.validator {
+        if !old.jailed && state.jailed {
+            events.push(validator_event("validator_jailed", &watch).add_attribute("moniker", &state.moniker));
+        }
+        if old.commission_rate != state.commission_rate {
+            events.push(
+                validator_event("validator_commission_changed", &watch)
+                    .add_attribute("old_rate", old.commission_rate.to_string())
+                    .add_attribute("new_rate", state.commission_rate.to_string()),
+            );
+        }
+    }
+    if let Some(pubkey) = &validator.consensus_pubkey {
+        let address = consensus_address(&watch.operator_address, pubkey);
+        if watch.consensus_address != address {
+            unindex_consensus(deps.storage, &watch);
+            watch.consensus_address = address;
+        }
+    }
+    // also indexes watches stored before the index existed
+    if let Some(consensus_address) = &watch.consensus_address {
+        VALIDATOR_WATCHES_BY_CONSENSUS.save(deps.storage, (channel, consensus_address, &watch.operator_address), &Empty {})?;
+    }
+    watch.validator = Some(state);
+    watch.checked_at = Some(env.block.time);
+    VALIDATOR_WATCHES.save(deps.storage, (channel, &watch.operator_address), &watch)?;
+
+    let Some(consensus_address) = watch.consensus_address.clone() else {
+        return Ok((events, vec![]));
+    };
+    let mut msgs = vec![];
+    let spec = QuerySpec::SigningInfo { consensus_address };
+    let outcome = build_query_request(deps.as_ref(), env, channel, &spec)
+        .and_then(|req| prepare_icq_packet(deps.branch(), env, &watch.owner, channel, vec![req]));
+    match outcome {
+        Ok(PacketOutcome::Sent { msg, .. }) => msgs.push(SubMsg::new(msg)),
+        Ok(PacketOutcome::Cached(cached)) => match QuerySigningInfoResponse::decode(cached.value.as_slice()) {
+            Ok(response) => {
+                if let Some(info) = response.val_signing_info {
+                    events.extend(evaluate_signing_info(deps.storage, env, channel, &info)?);
+                }
+            }
+            // the staking info is already stored, so it must not be failed
+            Err(err) => events.push(validator_event("validator_watch_failed", &watch).add_attribute("error", err.to_string())),
+        },
+        Ok(PacketOutcome::Coalesced { .. }) => {}
+        Err(err) => events.push(validator_event("validator_watch_failed", &watch).add_attribute("error", err.to_string())),
+    }
+    Ok((events, msgs))
+}
+
+/// Records the signing info of the watched validator with consensus address
+/// `info.address`, reporting it once it got tombstoned.
+pub fn evaluate_signing_info(storage: &mut dyn Storage, env: &Env, channel: &str, info: &ValidatorSigningInfo) -> StdResult<Vec<Event>> {
+    let operator_addresses: Vec<String> = VALIDATOR_WATCHES_BY_CONSENSUS
+        .prefix((channel, &info.address))
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+
+    let state = SigningState {
+        missed_blocks_counter: info.missed_blocks_counter.max(0) as u64,
+        jailed_until: info.jailed_until.as_ref().map(|time| block_time(time).unwrap_or(JAILED_FOREVER)),
+        tombstoned: info.tombstoned,
+    };
+    let mut events = vec![];
+    for operator_address in operator_addresses {
+        let mut watch = VALIDATOR_WATCHES.load(storage, (channel, &operator_address))?;
+        if watch.signing_info.as_ref().is_some_and(|old| !old.tombstoned) && state.tombstoned {
+            events.push(validator_event("validator_tombstoned", &watch).add_attribute("consensus_address", &info.address));
+        }
+        watch.signing_info = Some(state.clone());
+        watch.checked_at = Some(env.block.time);
+        VALIDATOR_WATCHES.save(storage, (channel, &operator_address), &watch)?;
+    }
+    Ok(events)
+}
+
+pub fn query_validator_watch(deps: Deps, chain: String, operator_address: String) -> Result<ValidatorWatch, ContractError> {
+    let channel = resolve_chain(deps.storage, &chain)?;
+    VALIDATOR_WATCHES
+        .may_load(deps.storage, (&channel, &operator_address))?
+        .ok_or(ContractError::NoSuchValidatorWatch { chain, operator_address })
+}
+
+pub fn query_validator_watches(
+    deps: Deps,
+    chain: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<(String, ValidatorWatch)>, ContractError> {
+    let channel = resolve_chain(deps.storage, &chain)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let watches = VALIDATOR_WATCHES
+        .prefix(&channel)
+        .range(deps.storage, start_after.as_deref().map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+    Ok(watches)
+}
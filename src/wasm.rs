@@ -0,0 +1,77 @@
+use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::{AbciQueryRequest, AbciQueryResponse};
+use cosmos_sdk_proto::cosmwasm::wasm::v1::{
+    QueryContractInfoResponse, QueryRawContractStateRequest, QueryRawContractStateResponse, QuerySmartContractStateRequest,
+    QuerySmartContractStateResponse,
+};
+use cosmwasm_std::{Deps, Env, Order, StdResult, Storage};
+use cw_storage_plus::Bound;
+use prost::Message;
+
+use crate::error::ContractError;
+use crate::msg::{WASM_RAW_QUERY_PATH, WASM_SMART_QUERY_PATH};
+use crate::state::{RemoteContractInfo, WASM_RESULTS, WasmQueryData, WasmQueryResult};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Decodes the answer of a remote contract query. Smart and raw results are
+/// kept byte for byte, contract info is flattened to its stable fields.
+pub fn decode_wasm_response(request: &AbciQueryRequest, response: &AbciQueryResponse) -> Result<(String, WasmQueryData), ContractError> {
+    match request.path.as_str() {
+        WASM_SMART_QUERY_PATH => {
+            let query = QuerySmartContractStateRequest::decode(request.data.as_slice())?;
+            let answer = QuerySmartContractStateResponse::decode(response.value.as_slice())?;
+            Ok((query.address, WasmQueryData::Smart { msg: query.query_data.into(), response: answer.data.into() }))
+        }
+        WASM_RAW_QUERY_PATH => {
+            let query = QueryRawContractStateRequest::decode(request.data.as_slice())?;
+            let answer = QueryRawContractStateResponse::decode(response.value.as_slice())?;
+            Ok((query.address, WasmQueryData::Raw { key: query.query_data.into(), value: answer.data.into() }))
+        }
+        _ => {
+            let answer = QueryContractInfoResponse::decode(response.value.as_slice())?;
+            let info = answer.contract_info.unwrap_or_default();
+            Ok((answer.address, WasmQueryData::ContractInfo(RemoteContractInfo {
+                code_id: info.code_id,
+                creator: info.creator,
+                admin: info.admin,
+                label: info.label,
+                ibc_port_id: info.ibc_port_id,
+            })))
+        }
+    }
+}
+
+pub fn record_wasm_result(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel: &str,
+    sequence: u64,
+    request: &AbciQueryRequest,
+    response: &AbciQueryResponse,
+) -> Result<WasmQueryResult, ContractError> {
+    let (contract, data) = decode_wasm_response(request, response)?;
+    let result = WasmQueryResult {
+        channel: channel.to_string(),
+        contract,
+        data,
+        height: response.height.max(0) as u64,
+        time: env.block.time,
+    };
+    WASM_RESULTS.save(storage, sequence, &result)?;
+    Ok(result)
+}
+
+pub fn query_wasm_result(deps: Deps, sequence: u64) -> Result<WasmQueryResult, ContractError> {
+    WASM_RESULTS
+        .may_load(deps.storage, sequence)?
+        .ok_or(ContractError::NoSuchWasmResult { sequence })
+}
+
+pub fn query_wasm_results(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<(u64, WasmQueryResult)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    WASM_RESULTS
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
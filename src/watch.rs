@@ -0,0 +1,167 @@
+use cosmwasm_std::{Deps, DepsMut, Env, Event, from_json, MessageInfo, Order, Reply, Response, StdResult, Storage, SubMsg, SubMsgResult, to_json_binary, Uint128, WasmMsg};
+use cw_storage_plus::Bound;
+
+use crate::contract::{build_query_request, ensure_admin};
+use crate::error::ContractError;
+use crate::msg::{AddWatchMsg, QuerySpec};
+use crate::state::{CHAIN_ALIASES, NEXT_WATCH_ID, Watch, WatchDirection, WATCHES, WATCHES_BY_BALANCE};
+
+pub const WATCH_CALLBACK_REPLY_ID: u64 = 1;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn add_watch(deps: DepsMut, env: Env, info: MessageInfo, msg: AddWatchMsg) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+    let AddWatchMsg { chain, address, denom, threshold, direction, hysteresis, callback } = msg;
+
+    let channel = CHAIN_ALIASES
+        .may_load(deps.storage, &chain)?
+        .ok_or(ContractError::NoSuchChainAlias { alias: chain.clone() })?;
+    // the same checks the balance query itself goes through
+    let spec = QuerySpec::Balance { address: address.clone(), denom: denom.clone() };
+    build_query_request(deps.as_ref(), &env, &channel, &spec)?;
+    if let Some(callback) = &callback {
+        deps.api.addr_validate(&callback.contract)?;
+    }
+
+    let id = NEXT_WATCH_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_WATCH_ID.save(deps.storage, &(id + 1))?;
+
+    WATCHES_BY_BALANCE.update(deps.storage, (&channel, &address, &denom), |ids| -> StdResult<_> {
+        let mut ids = ids.unwrap_or_default();
+        ids.push(id);
+        Ok(ids)
+    })?;
+    WATCHES.save(deps.storage, id, &Watch {
+        chain,
+        channel,
+        address,
+        denom,
+        threshold,
+        direction,
+        hysteresis: hysteresis.unwrap_or_default(),
+        callback,
+        triggered: false,
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("method", "add_watch")
+        .add_attribute("watch_id", id.to_string()))
+}
+
+pub fn remove_watch(deps: DepsMut, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info)?;
+
+    let watch = WATCHES.may_load(deps.storage, id)?.ok_or(ContractError::NoSuchWatch { id })?;
+    WATCHES.remove(deps.storage, id);
+    let key = (watch.channel.as_str(), watch.address.as_str(), watch.denom.as_str());
+    let mut ids = WATCHES_BY_BALANCE.may_load(deps.storage, key)?.unwrap_or_default();
+    ids.retain(|watch_id| *watch_id != id);
+    if ids.is_empty() {
+        WATCHES_BY_BALANCE.remove(deps.storage, key);
+    } else {
+        WATCHES_BY_BALANCE.save(deps.storage, key, &ids)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "remove_watch")
+        .add_attribute("watch_id", id.to_string()))
+}
+
+/// Whether `amount` is past the threshold, and whether it is far enough back
+/// on the other side to re-arm a triggered watch.
+fn crossing(watch: &Watch, amount: Uint128) -> (bool, bool) {
+    match watch.direction {
+        WatchDirection::Below => (
+            amount < watch.threshold,
+            amount >= watch.threshold.saturating_add(watch.hysteresis),
+        ),
+        WatchDirection::Above => (
+            amount > watch.threshold,
+            amount <= watch.threshold.saturating_sub(watch.hysteresis),
+        ),
+    }
+}
+
+/// Evaluates the watches of a balance that just arrived. Returns a
+/// `balance_watch` event for every watch that fired or re-armed, and the
+/// callbacks of those that fired.
+pub fn evaluate_watches(
+    storage: &mut dyn Storage,
+    channel: &str,
+    address: &str,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<(Vec<Event>, Vec<SubMsg>)> {
+    let ids = WATCHES_BY_BALANCE.may_load(storage, (channel, address, denom))?.unwrap_or_default();
+
+    let mut events = vec![];
+    let mut callbacks = vec![];
+    for id in ids {
+        let mut watch = WATCHES.load(storage, id)?;
+        let (past, rearmed) = crossing(&watch, amount);
+        let status = if past && !watch.triggered {
+            watch.triggered = true;
+            if let Some(callback) = &watch.callback {
+                let msg = WasmMsg::Execute {
+                    contract_addr: callback.contract.clone(),
+                    msg: callback.msg.clone(),
+                    funds: vec![],
+                };
+                callbacks.push(SubMsg::reply_on_error(msg, WATCH_CALLBACK_REPLY_ID).with_payload(to_json_binary(&id)?));
+            }
+            "triggered"
+        } else if rearmed && watch.triggered {
+            watch.triggered = false;
+            "rearmed"
+        } else {
+            continue;
+        };
+        WATCHES.save(storage, id, &watch)?;
+
+        events.push(
+            Event::new("balance_watch")
+                .add_attribute("watch_id", id.to_string())
+                .add_attribute("status", status)
+                .add_attribute("chain", &watch.chain)
+                .add_attribute("address", &watch.address)
+                .add_attribute("denom", &watch.denom)
+                .add_attribute("amount", amount)
+                .add_attribute("threshold", watch.threshold)
+                .add_attribute("direction", match watch.direction {
+                    WatchDirection::Below => "below",
+                    WatchDirection::Above => "above",
+                }),
+        );
+    }
+    Ok((events, callbacks))
+}
+
+/// Only failed callbacks are replied to. The failure is reported instead of
+/// failing the ack that triggered the watch.
+pub fn handle_callback_reply(msg: Reply) -> Result<Response, ContractError> {
+    let id: u64 = from_json(&msg.payload)?;
+    let error = match msg.result {
+        SubMsgResult::Err(error) => error,
+        SubMsgResult::Ok(_) => return Ok(Response::new()),
+    };
+
+    Ok(Response::new().add_event(
+        Event::new("balance_watch_callback_failed")
+            .add_attribute("watch_id", id.to_string())
+            .add_attribute("error", error),
+    ))
+}
+
+pub fn query_watch(deps: Deps, id: u64) -> Result<Watch, ContractError> {
+    WATCHES.may_load(deps.storage, id)?.ok_or(ContractError::NoSuchWatch { id })
+}
+
+pub fn query_watches(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<(u64, Watch)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    WATCHES
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use cosmos_sdk_proto::cosmos::bank::v1beta1::QueryBalanceRequest;
+use cosmwasm_std::{from_json, Int256, Timestamp, Uint128};
+use icq_sender::contract::{execute, query};
+use icq_sender::msg::{BalanceDeltaResponse, ExecuteMsg, ProtoCoin, QueryMsg, SnapshotPoint};
+use icq_sender::portfolio::{record_balance, MAX_BALANCE_SAMPLES, MAX_COUNTED_SAMPLES};
+use icq_sender::ContractError;
+
+use common::{setup, Deps, CHANNEL};
+
+const ADDRESS: &str = "cosmos1holder";
+
+fn record(deps: &mut Deps, amount: u128, height: u64, seconds: u64) {
+    let request = QueryBalanceRequest { address: ADDRESS.to_string(), denom: "uatom".to_string() };
+    let coin = ProtoCoin { denom: "uatom".to_string(), amount: amount.to_string() };
+    record_balance(deps.as_mut().storage, CHANNEL, &request, coin, height, Timestamp::from_seconds(seconds)).unwrap();
+}
+
+fn delta(deps: &Deps, from: SnapshotPoint, to: SnapshotPoint) -> Result<BalanceDeltaResponse, ContractError> {
+    let msg = QueryMsg::BalanceDelta { chain: "hub".to_string(), address: ADDRESS.to_string(), denom: "uatom".to_string(), from, to };
+    query(deps.as_ref(), common::mock_env(), msg).map(|res| from_json(res).unwrap())
+}
+
+fn setup_history() -> Deps {
+    let (mut deps, env, admin) = setup();
+    execute(deps.as_mut(), env, admin, ExecuteMsg::SetChainAlias { alias: "hub".to_string(), channel: Some(CHANNEL.to_string()) }).unwrap();
+    deps
+}
+
+#[test]
+fn delta_between_heights_uses_the_height_index() {
+    let mut deps = setup_history();
+    record(&mut deps, 100, 10, 1_000);
+    // the host did not say at which height
+    record(&mut deps, 900, 0, 1_500);
+    record(&mut deps, 150, 20, 2_000);
+    record(&mut deps, 130, 30, 3_000);
+
+    let res = delta(&deps, SnapshotPoint::Height(10), SnapshotPoint::Height(25)).unwrap();
+    assert_eq!(res.first_seen.unwrap().amount, Uint128::new(100));
+    assert_eq!(res.last_seen.unwrap().amount, Uint128::new(150));
+    assert_eq!(res.delta, Int256::from(50i128));
+    assert_eq!(res.samples, 2);
+
+    let res = delta(&deps, SnapshotPoint::Time(Timestamp::from_seconds(1_500)), SnapshotPoint::Time(Timestamp::from_seconds(3_000))).unwrap();
+    assert_eq!(res.delta, Int256::from(-770i128));
+    assert_eq!(res.samples, 3);
+
+    let res = delta(&deps, SnapshotPoint::Height(11), SnapshotPoint::Height(19)).unwrap();
+    assert_eq!(res, BalanceDeltaResponse { first_seen: None, last_seen: None, delta: Int256::zero(), samples: 0 });
+
+    let err = delta(&deps, SnapshotPoint::Height(10), SnapshotPoint::Time(Timestamp::from_seconds(3_000))).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidSnapshotWindow { .. }));
+}
+
+#[test]
+fn a_sample_of_the_same_block_replaces_the_previous_one() {
+    let mut deps = setup_history();
+    record(&mut deps, 100, 10, 1_000);
+    record(&mut deps, 120, 11, 1_000);
+
+    let res = delta(&deps, SnapshotPoint::Height(0), SnapshotPoint::Height(100)).unwrap();
+    assert_eq!(res.samples, 1);
+    assert_eq!(res.first_seen.unwrap().amount, Uint128::new(120));
+    // the index no longer points at the replaced sample
+    let res = delta(&deps, SnapshotPoint::Height(10), SnapshotPoint::Height(10)).unwrap();
+    assert_eq!(res.samples, 0);
+}
+
+#[test]
+fn oldest_samples_are_pruned_and_counting_is_capped() {
+    let mut deps = setup_history();
+    let total = MAX_BALANCE_SAMPLES as u64 + 5;
+    for i in 1..=total {
+        record(&mut deps, i as u128, i, i * 6);
+    }
+
+    let res = delta(&deps, SnapshotPoint::Height(0), SnapshotPoint::Height(total)).unwrap();
+    // the first 5 samples are gone from both the history and its index
+    assert_eq!(res.first_seen.unwrap().amount, Uint128::new(6));
+    assert_eq!(res.last_seen.unwrap().amount, Uint128::new(total as u128));
+    assert_eq!(res.samples, MAX_COUNTED_SAMPLES as u32);
+
+    let res = delta(&deps, SnapshotPoint::Time(Timestamp::from_seconds(0)), SnapshotPoint::Time(Timestamp::from_seconds(30))).unwrap();
+    assert_eq!(res.samples, 0);
+}
@@ -0,0 +1,101 @@
+#![allow(dead_code)]
+
+use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::AbciQueryResponse;
+pub use cosmwasm_std::testing::mock_env;
+use cosmwasm_std::testing::{message_info, mock_dependencies, mock_ibc_channel_connect_ack, mock_ibc_packet_ack, MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{Binary, CosmosMsg, Env, IbcAcknowledgement, IbcBasicResponse, IbcMsg, IbcOrder, IbcPacketAckMsg, MessageInfo, OwnedDeps, Response, to_json_binary};
+use icq_sender::ack::Ack;
+use icq_sender::contract::{execute, instantiate};
+use icq_sender::ibc::{ibc_channel_connect, ibc_packet_ack};
+use icq_sender::msg::{ArithmeticTwapToNowResponse, BALANCE_QUERY_PATH, CosmosResponse, CosmosResponsePacket, ExecuteMsg, InstantiateMsg, QueryTwapMsg, TWAP_QUERY_PATH};
+use prost::Message;
+
+pub const CHANNEL: &str = "channel-0";
+
+pub type Deps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+/// A contract with a connected channel that allows balance and TWAP queries,
+/// results being cached for 30 seconds.
+pub fn setup() -> (Deps, Env, MessageInfo) {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let admin = message_info(&deps.api.addr_make("admin"), &[]);
+    let msg = InstantiateMsg {
+        admin: None,
+        cache_ttl_seconds: Some(30),
+        poke_batch_size: None,
+        poke_bounty: None,
+        fee_denom: None,
+        refund_on_failure: None,
+    };
+    instantiate(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+    ibc_channel_connect(deps.as_mut(), env.clone(), mock_ibc_channel_connect_ack(CHANNEL, IbcOrder::Unordered, "icq-1")).unwrap();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::AllowQueryPaths {
+        channel: CHANNEL.to_string(),
+        paths: vec![TWAP_QUERY_PATH.to_string(), BALANCE_QUERY_PATH.to_string()],
+    })
+    .unwrap();
+    (deps, env, admin)
+}
+
+/// A valid bech32 address under `prefix`.
+pub fn remote_address(prefix: &str, byte: u8) -> String {
+    bech32::encode::<bech32::Bech32>(bech32::Hrp::parse(prefix).unwrap(), &[byte; 20]).unwrap()
+}
+
+pub fn twap_msg(pool_id: u64, base_asset: &str, quote_asset: &str) -> QueryTwapMsg {
+    QueryTwapMsg {
+        channel: CHANNEL.to_string(),
+        height: None,
+        pool_id,
+        base_asset: base_asset.to_string(),
+        quote_asset: quote_asset.to_string(),
+    }
+}
+
+/// The data of the packet sent by `res`.
+pub fn packet_of(res: &Response) -> Binary {
+    res.messages
+        .iter()
+        .find_map(|msg| match &msg.msg {
+            CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) => Some(data.clone()),
+            _ => None,
+        })
+        .expect("no packet sent")
+}
+
+/// The data of the packet sent while handling an acknowledgement.
+pub fn sent_packet(res: &IbcBasicResponse) -> Binary {
+    res.messages
+        .iter()
+        .find_map(|msg| match &msg.msg {
+            CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) => Some(data.clone()),
+            _ => None,
+        })
+        .expect("no packet sent")
+}
+
+/// A successful acknowledgement of `packet` answering each request with
+/// the matching value.
+pub fn ack_for(packet: Binary, values: Vec<Vec<u8>>, sequence: u64) -> IbcPacketAckMsg {
+    let responses = values.into_iter().map(|value| AbciQueryResponse { value, ..Default::default() }).collect();
+    ack_with_responses(packet, responses, sequence)
+}
+
+pub fn ack_with_responses(packet: Binary, responses: Vec<AbciQueryResponse>, sequence: u64) -> IbcPacketAckMsg {
+    let response = CosmosResponse { responses };
+    let result = to_json_binary(&CosmosResponsePacket { data: Binary::from(response.encode_to_vec()) }).unwrap();
+    let ack = to_json_binary(&Ack::Result(result)).unwrap();
+    let mut msg = mock_ibc_packet_ack(CHANNEL, &1u8, IbcAcknowledgement::new(ack)).unwrap();
+    msg.original_packet.data = packet;
+    msg.original_packet.sequence = sequence;
+    msg
+}
+
+/// Sends a TWAP query for the pool and acks it with the raw `twap`.
+pub fn feed_twap(deps: &mut Deps, env: &Env, pool_id: u64, base: &str, quote: &str, twap: &str, sequence: u64) -> IbcBasicResponse {
+    let feeder = message_info(&deps.api.addr_make("feeder"), &[]);
+    let res = execute(deps.as_mut(), env.clone(), feeder, ExecuteMsg::SendQueryTwap(twap_msg(pool_id, base, quote))).unwrap();
+    let value = ArithmeticTwapToNowResponse { arithmetic_twap: twap.to_string() }.encode_to_vec();
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![value], sequence)).unwrap()
+}
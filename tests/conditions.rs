@@ -0,0 +1,144 @@
+mod common;
+
+use cosmwasm_std::testing::message_info;
+use cosmwasm_std::{coins, from_json, to_json_binary, BankMsg, CosmosMsg, Decimal256, Env, MessageInfo, Reply, SubMsgResult, WasmMsg};
+use icq_sender::conditions::CONDITION_REPLY_ID;
+use icq_sender::contract::{execute, query, reply};
+use icq_sender::msg::{AddConditionMsg, ExecuteMsg, QueryMsg, UpdateConfigMsg};
+use icq_sender::state::{Comparison, Condition, ConditionStatus};
+use icq_sender::ContractError;
+
+use common::{feed_twap, setup, Deps};
+
+fn condition(comparison: Comparison, threshold: u64, msg: CosmosMsg) -> ExecuteMsg {
+    ExecuteMsg::AddCondition(AddConditionMsg {
+        base: "uosmo".to_string(),
+        quote: "uatom".to_string(),
+        comparison,
+        threshold: Decimal256::percent(threshold),
+        msg,
+    })
+}
+
+fn send(to: &str, amount: u128) -> CosmosMsg {
+    BankMsg::Send { to_address: to.to_string(), amount: coins(amount, "uosmo") }.into()
+}
+
+fn load(deps: &Deps, env: &Env, id: u64) -> Condition {
+    from_json(query(deps.as_ref(), env.clone(), QueryMsg::Condition { id }).unwrap()).unwrap()
+}
+
+/// Every TWAP query goes out instead of being answered from the cache.
+fn uncached() -> (Deps, Env, MessageInfo) {
+    let (mut deps, env, admin) = setup();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        cache_ttl_seconds: Some(0),
+        ..Default::default()
+    }))
+    .unwrap();
+    (deps, env, admin)
+}
+
+#[test]
+fn others_may_only_send_the_funds_they_escrow() {
+    let (mut deps, env, admin) = setup();
+    let target = deps.api.addr_make("target").into_string();
+    let user = message_info(&deps.api.addr_make("user"), &coins(5, "uosmo"));
+    let invalid = ContractError::InvalidCondition { reason: "only a bank send of exactly the attached funds is allowed".to_string() };
+
+    let execute_msg = WasmMsg::Execute { contract_addr: target.clone(), msg: b"{}".into(), funds: coins(5, "uosmo") };
+    let err = execute(deps.as_mut(), env.clone(), user.clone(), condition(Comparison::Below, 120, execute_msg.clone().into())).unwrap_err();
+    assert_eq!(err, invalid);
+    let err = execute(deps.as_mut(), env.clone(), user.clone(), condition(Comparison::Below, 120, send(&target, 6))).unwrap_err();
+    assert_eq!(err, invalid);
+    let unfunded = message_info(&user.sender, &[]);
+    let err = execute(deps.as_mut(), env.clone(), unfunded, condition(Comparison::Below, 120, BankMsg::Send { to_address: target.clone(), amount: vec![] }.into())).unwrap_err();
+    assert_eq!(err, invalid);
+    let err = execute(deps.as_mut(), env.clone(), user.clone(), condition(Comparison::Below, 0, send(&target, 5))).unwrap_err();
+    assert_eq!(err, ContractError::InvalidCondition { reason: "zero threshold".to_string() });
+
+    execute(deps.as_mut(), env.clone(), user.clone(), condition(Comparison::Below, 120, send(&target, 5))).unwrap();
+    assert_eq!(load(&deps, &env, 0).escrow, coins(5, "uosmo"));
+    execute(deps.as_mut(), env.clone(), admin, condition(Comparison::Below, 120, execute_msg.into())).unwrap();
+
+    // cancelling refunds the escrow
+    let stranger = message_info(&deps.api.addr_make("stranger"), &[]);
+    let err = execute(deps.as_mut(), env.clone(), stranger, ExecuteMsg::CancelCondition { id: 0 }).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+    let res = execute(deps.as_mut(), env.clone(), user.clone(), ExecuteMsg::CancelCondition { id: 0 }).unwrap();
+    assert_eq!(res.messages[0].msg, send(user.sender.as_str(), 5));
+    assert_eq!(load(&deps, &env, 0).status, ConditionStatus::Cancelled);
+}
+
+#[test]
+fn pending_conditions_are_capped_per_owner() {
+    let (mut deps, env, _) = setup();
+    let target = deps.api.addr_make("target").into_string();
+    let alice = message_info(&deps.api.addr_make("alice"), &coins(1, "uosmo"));
+    let bob = message_info(&deps.api.addr_make("bob"), &coins(1, "uosmo"));
+
+    for _ in 0..10 {
+        execute(deps.as_mut(), env.clone(), alice.clone(), condition(Comparison::Below, 120, send(&target, 1))).unwrap();
+    }
+    let err = execute(deps.as_mut(), env.clone(), alice.clone(), condition(Comparison::Below, 120, send(&target, 1))).unwrap_err();
+    assert_eq!(err, ContractError::TooManyConditions { max: 10 });
+    execute(deps.as_mut(), env.clone(), bob, condition(Comparison::Below, 120, send(&target, 1))).unwrap();
+
+    execute(deps.as_mut(), env.clone(), alice.clone(), ExecuteMsg::CancelCondition { id: 3 }).unwrap();
+    execute(deps.as_mut(), env, alice, condition(Comparison::Below, 120, send(&target, 1))).unwrap();
+}
+
+#[test]
+fn conditions_execute_once_and_refund_when_their_message_fails() {
+    let (mut deps, env, _) = uncached();
+    let target = deps.api.addr_make("target").into_string();
+    let user = message_info(&deps.api.addr_make("user"), &coins(5, "uosmo"));
+    execute(deps.as_mut(), env.clone(), user.clone(), condition(Comparison::Below, 120, send(&target, 5))).unwrap();
+
+    let res = feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "1.5", 1);
+    assert!(res.messages.is_empty());
+    assert_eq!(load(&deps, &env, 0).status, ConditionStatus::Pending);
+
+    let res = feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "1.0", 2);
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].msg, send(&target, 5));
+    assert_eq!(res.messages[0].id, CONDITION_REPLY_ID);
+    assert!(res.events.iter().any(|event| event.ty == "condition_executed"));
+
+    let res = feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "0.9", 3);
+    assert!(res.messages.is_empty());
+
+    let failed = Reply {
+        id: CONDITION_REPLY_ID,
+        payload: to_json_binary(&0u64).unwrap(),
+        gas_used: 0,
+        result: SubMsgResult::Err("insufficient funds".to_string()),
+    };
+    let res = reply(deps.as_mut(), env.clone(), failed).unwrap();
+    assert_eq!(res.messages[0].msg, send(user.sender.as_str(), 5));
+    assert!(matches!(load(&deps, &env, 0).status, ConditionStatus::Failed { .. }));
+    let err = execute(deps.as_mut(), env, user, ExecuteMsg::CancelCondition { id: 0 }).unwrap_err();
+    assert_eq!(err, ContractError::ConditionNotPending { id: 0 });
+}
+
+#[test]
+fn each_ack_evaluates_a_bounded_number_of_conditions() {
+    let (mut deps, env, _) = uncached();
+    let target = deps.api.addr_make("target").into_string();
+    for owner in 0..6 {
+        let owner = message_info(&deps.api.addr_make(&format!("owner{owner}")), &coins(1, "uosmo"));
+        for _ in 0..10 {
+            execute(deps.as_mut(), env.clone(), owner.clone(), condition(Comparison::Above, 50, send(&target, 1))).unwrap();
+        }
+    }
+
+    let res = feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "1.0", 1);
+    assert_eq!(res.messages.len(), 50);
+    assert_eq!(load(&deps, &env, 49).status, ConditionStatus::Executed { price: Decimal256::one(), time: env.block.time });
+    assert_eq!(load(&deps, &env, 50).status, ConditionStatus::Pending);
+
+    let res = feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "1.0", 2);
+    assert_eq!(res.messages.len(), 10);
+    let res = feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "1.0", 3);
+    assert!(res.messages.is_empty());
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use cosmos_sdk_proto::cosmwasm::wasm::v1::{ContractInfo, QueryContractInfoResponse};
+use cosmwasm_std::{from_json, Env, HexBinary, IbcBasicResponse, MessageInfo};
+use icq_sender::contract::{execute, query};
+use icq_sender::ibc::ibc_packet_ack;
+use icq_sender::msg::{
+    ExecuteMsg, QueryCodeInfoResponse, QueryContractInfoMsg, QueryMsg, QuerySpec, WASM_CODE_INFO_QUERY_PATH, WASM_CONTRACT_INFO_QUERY_PATH,
+    WatchContractMsg,
+};
+use icq_sender::state::ContractWatch;
+use prost::Message;
+
+use common::{ack_for, packet_of, remote_address, sent_packet, setup, Deps, CHANNEL};
+
+fn contract_info(contract: &str, code_id: u64) -> Vec<u8> {
+    let info = ContractInfo { code_id, label: "vault".to_string(), ..Default::default() };
+    QueryContractInfoResponse { address: contract.to_string(), contract_info: Some(info) }.encode_to_vec()
+}
+
+fn code_info(code_id: u64, checksum: u8) -> Vec<u8> {
+    QueryCodeInfoResponse { code_id, creator: String::new(), checksum: vec![checksum; 32] }.encode_to_vec()
+}
+
+/// Answers a contract info query for `contract`, returning the ack response.
+fn answer_contract_info(deps: &mut Deps, env: &Env, admin: &MessageInfo, contract: &str, code_id: u64, sequence: u64) -> IbcBasicResponse {
+    let msg = QueryContractInfoMsg { channel: CHANNEL.to_string(), contract: contract.to_string(), height: None };
+    let res = execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::SendQueryContractInfo(msg)).unwrap();
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![contract_info(contract, code_id)], sequence)).unwrap()
+}
+
+fn checksum(deps: &Deps, id: u64) -> Option<HexBinary> {
+    let watch: ContractWatch = from_json(query(deps.as_ref(), common::mock_env(), QueryMsg::ContractWatch { id }).unwrap()).unwrap();
+    watch.checksum
+}
+
+fn changes(res: &IbcBasicResponse) -> Vec<String> {
+    res.events
+        .iter()
+        .filter(|event| event.ty == "contract_changed")
+        .map(|event| event.attributes.iter().find(|attr| attr.key == "watch_id").unwrap().value.clone())
+        .collect()
+}
+
+#[test]
+fn code_info_only_reaches_the_watches_of_contracts_running_the_code() {
+    let (mut deps, mut env, admin) = setup();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::AllowQueryPaths {
+        channel: CHANNEL.to_string(),
+        paths: vec![WASM_CONTRACT_INFO_QUERY_PATH.to_string(), WASM_CODE_INFO_QUERY_PATH.to_string()],
+    })
+    .unwrap();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::SetChainAlias { alias: "hub".to_string(), channel: Some(CHANNEL.to_string()) }).unwrap();
+    let (first, second) = (remote_address("wasm", 1), remote_address("wasm", 2));
+    for contract in [&first, &second] {
+        let msg = WatchContractMsg { chain: "hub".to_string(), contract: contract.clone(), interval_seconds: 3_600, callback: None };
+        execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::WatchContract(msg)).unwrap();
+    }
+
+    // the first info of each contract queries its code
+    let res = answer_contract_info(&mut deps, &env, &admin, &first, 1, 1);
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(sent_packet(&res), vec![code_info(1, 1)], 2)).unwrap();
+    let res = answer_contract_info(&mut deps, &env, &admin, &second, 2, 3);
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(sent_packet(&res), vec![code_info(2, 2)], 4)).unwrap();
+    assert_eq!(checksum(&deps, 0), Some(HexBinary::from(vec![1; 32])));
+    assert_eq!(checksum(&deps, 1), Some(HexBinary::from(vec![2; 32])));
+
+    // once migrated, the first contract follows its new code
+    env.block.time = env.block.time.plus_seconds(60);
+    let res = answer_contract_info(&mut deps, &env, &admin, &first, 2, 5);
+    assert_eq!(changes(&res), vec!["0"]);
+    let res = ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(sent_packet(&res), vec![code_info(2, 2)], 6)).unwrap();
+    assert_eq!(changes(&res), vec!["0"]);
+    assert_eq!(checksum(&deps, 0), Some(HexBinary::from(vec![2; 32])));
+
+    // and no longer hears of its old one
+    let subscribe = ExecuteMsg::Subscribe { channel: CHANNEL.to_string(), query: QuerySpec::WasmCodeInfo { code_id: 1 }, interval_seconds: 3_600 };
+    execute(deps.as_mut(), env.clone(), admin.clone(), subscribe).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::Poke {}).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![code_info(1, 9)], 7)).unwrap();
+    assert!(changes(&res).is_empty());
+    assert_eq!(checksum(&deps, 0), Some(HexBinary::from(vec![2; 32])));
+
+    // an unwatched contract leaves the index of its code
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::UnwatchContract { id: 1 }).unwrap();
+    let subscribe = ExecuteMsg::Subscribe { channel: CHANNEL.to_string(), query: QuerySpec::WasmCodeInfo { code_id: 2 }, interval_seconds: 60 };
+    execute(deps.as_mut(), env.clone(), admin.clone(), subscribe).unwrap();
+    env.block.time = env.block.time.plus_seconds(60);
+    let res = execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::Poke {}).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), env, ack_for(packet_of(&res), vec![code_info(2, 7)], 8)).unwrap();
+    assert_eq!(changes(&res), vec!["0"]);
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use cosmwasm_std::from_json;
+use icq_sender::contract::{execute, query};
+use icq_sender::ibc::ibc_packet_ack;
+use icq_sender::msg::{DENOM_TRACE_QUERY_PATH, ExecuteMsg, ProtoDenomTrace, QueryDenomTraceResponse, QueryMsg};
+use icq_sender::state::DenomTrace;
+use prost::Message;
+
+use common::{ack_for, packet_of, setup, CHANNEL};
+
+#[test]
+fn an_undecodable_trace_leaves_the_denom_unresolved_until_asked_again() {
+    let (mut deps, env, admin) = setup();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::AllowQueryPaths {
+        channel: CHANNEL.to_string(),
+        paths: vec![DENOM_TRACE_QUERY_PATH.to_string()],
+    })
+    .unwrap();
+    let voucher = format!("ibc/{}", "B".repeat(64));
+    let resolve = ExecuteMsg::ResolveDenom { channel: CHANNEL.to_string(), denom: voucher.clone() };
+    let traces = |deps: &common::Deps| -> Vec<(String, DenomTrace)> {
+        from_json(query(deps.as_ref(), common::mock_env(), QueryMsg::DenomTraces { channel: CHANNEL.to_string() }).unwrap()).unwrap()
+    };
+
+    let res = execute(deps.as_mut(), env.clone(), admin.clone(), resolve.clone()).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![vec![0x0a, 0xff]], 1)).unwrap();
+    assert!(res.attributes.iter().any(|attr| attr.key == "error"));
+    assert!(traces(&deps).is_empty());
+
+    let res = execute(deps.as_mut(), env.clone(), admin, resolve).unwrap();
+    let trace = QueryDenomTraceResponse {
+        denom_trace: Some(ProtoDenomTrace { path: "transfer/channel-1".to_string(), base_denom: "uatom".to_string() }),
+    };
+    ibc_packet_ack(deps.as_mut(), env, ack_for(packet_of(&res), vec![trace.encode_to_vec()], 2)).unwrap();
+    assert_eq!(traces(&deps), vec![(voucher, DenomTrace { path: "transfer/channel-1".to_string(), base_denom: "uatom".to_string() })]);
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use cosmwasm_std::{from_json, Decimal256, Env, MessageInfo};
+use icq_sender::contract::{execute, query};
+use icq_sender::ibc::ibc_packet_ack;
+use icq_sender::msg::{AggregatedPriceResponse, DENOM_TRACE_QUERY_PATH, ExecuteMsg, ProtoDenomTrace, QueryDenomTraceResponse, QueryMsg};
+use icq_sender::state::{Aggregation, FeedSource, PriceFeed};
+use icq_sender::ContractError;
+use prost::Message;
+
+use common::{ack_for, feed_twap, packet_of, setup, Deps, CHANNEL};
+
+fn source(pool_id: u64, weight: u64) -> FeedSource {
+    FeedSource {
+        channel: CHANNEL.to_string(),
+        pool_id,
+        base_asset: "uosmo".to_string(),
+        quote_asset: "uatom".to_string(),
+        weight: Decimal256::from_atomics(weight, 0).unwrap(),
+    }
+}
+
+fn feed(sources: Vec<FeedSource>, aggregation: Aggregation, min_sources: u32) -> PriceFeed {
+    PriceFeed {
+        base: "uosmo".to_string(),
+        quote: "uatom".to_string(),
+        sources,
+        aggregation,
+        min_sources,
+        max_staleness_seconds: 60,
+    }
+}
+
+fn set_feed(deps: &mut Deps, env: &Env, admin: &MessageInfo, feed: PriceFeed) -> Result<(), ContractError> {
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::SetFeed(feed)).map(|_| ())
+}
+
+fn aggregated(deps: &Deps, env: &Env) -> Result<AggregatedPriceResponse, ContractError> {
+    query(deps.as_ref(), env.clone(), QueryMsg::AggregatedPrice { base: "uosmo".to_string(), quote: "uatom".to_string() })
+        .map(|res| from_json(res).unwrap())
+}
+
+fn prices_from_three_pools(raw: [&str; 3]) -> (Deps, Env, MessageInfo) {
+    let (mut deps, env, admin) = setup();
+    for (pool, twap) in raw.into_iter().enumerate() {
+        feed_twap(&mut deps, &env, pool as u64 + 1, "uosmo", "uatom", twap, pool as u64 + 1);
+    }
+    (deps, env, admin)
+}
+
+#[test]
+fn the_median_ignores_stale_sources() {
+    let (mut deps, mut env, admin) = prices_from_three_pools(["1.5", "1.7", "1.0"]);
+    set_feed(&mut deps, &env, &admin, feed(vec![source(1, 1), source(2, 1), source(3, 1)], Aggregation::Median, 2)).unwrap();
+    assert_eq!(aggregated(&deps, &env).unwrap().price, Decimal256::percent(150));
+
+    // pool 1 is refreshed, the others go stale
+    env.block.time = env.block.time.plus_seconds(50);
+    feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "1.6", 4);
+    feed_twap(&mut deps, &env, 2, "uosmo", "uatom", "1.8", 5);
+    env.block.time = env.block.time.plus_seconds(20);
+    let res = aggregated(&deps, &env).unwrap();
+    assert_eq!(res.price, Decimal256::percent(170));
+    assert_eq!(res.sources.len(), 2);
+
+    env.block.time = env.block.time.plus_seconds(60);
+    let err = aggregated(&deps, &env).unwrap_err();
+    assert_eq!(err, ContractError::InsufficientSources {
+        base: "uosmo".to_string(),
+        quote: "uatom".to_string(),
+        fresh: 0,
+        required: 2,
+    });
+}
+
+#[test]
+fn the_weighted_mean_weighs_each_source() {
+    let (mut deps, env, admin) = prices_from_three_pools(["1.0", "2.0", "4.0"]);
+    set_feed(&mut deps, &env, &admin, feed(vec![source(1, 2), source(2, 1), source(3, 1)], Aggregation::WeightedMean, 3)).unwrap();
+    assert_eq!(aggregated(&deps, &env).unwrap().price, Decimal256::percent(200));
+}
+
+#[test]
+fn overflowing_aggregates_fail_instead_of_panicking() {
+    let max = Decimal256::MAX.atomics().to_string();
+    let (mut deps, env, admin) = prices_from_three_pools([&max, &max, "1.0"]);
+    let overflow = ContractError::FeedOverflow { base: "uosmo".to_string(), quote: "uatom".to_string() };
+
+    set_feed(&mut deps, &env, &admin, feed(vec![source(1, 1), source(2, 1)], Aggregation::Median, 2)).unwrap();
+    assert_eq!(aggregated(&deps, &env).unwrap_err(), overflow);
+    set_feed(&mut deps, &env, &admin, feed(vec![source(1, 1), source(3, 1)], Aggregation::WeightedMean, 2)).unwrap();
+    assert_eq!(aggregated(&deps, &env).unwrap_err(), overflow);
+    set_feed(&mut deps, &env, &admin, feed(vec![source(1, 2)], Aggregation::WeightedMean, 1)).unwrap();
+    assert_eq!(aggregated(&deps, &env).unwrap_err(), overflow);
+}
+
+#[test]
+fn sources_must_quote_the_feed_pair() {
+    let (mut deps, env, admin) = setup();
+    let invalid = |reason: &str| ContractError::InvalidFeed { reason: reason.to_string() };
+
+    let mut inverted = source(1, 1);
+    std::mem::swap(&mut inverted.base_asset, &mut inverted.quote_asset);
+    let err = set_feed(&mut deps, &env, &admin, feed(vec![source(2, 1), inverted], Aggregation::Median, 1)).unwrap_err();
+    assert_eq!(err, invalid(&format!("source {CHANNEL}/1 quotes uatom/uosmo instead of uosmo/uatom")));
+
+    let mut voucher = source(1, 1);
+    voucher.quote_asset = format!("ibc/{}", "A".repeat(64));
+    assert!(set_feed(&mut deps, &env, &admin, feed(vec![voucher.clone()], Aggregation::Median, 1)).is_err());
+
+    // once resolved, the voucher stands for the feed's quote
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::AllowQueryPaths {
+        channel: CHANNEL.to_string(),
+        paths: vec![DENOM_TRACE_QUERY_PATH.to_string()],
+    })
+    .unwrap();
+    let res = execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::ResolveDenom {
+        channel: CHANNEL.to_string(),
+        denom: voucher.quote_asset.clone(),
+    })
+    .unwrap();
+    let trace = QueryDenomTraceResponse {
+        denom_trace: Some(ProtoDenomTrace { path: "transfer/channel-1".to_string(), base_denom: "uatom".to_string() }),
+    };
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![trace.encode_to_vec()], 1)).unwrap();
+    set_feed(&mut deps, &env, &admin, feed(vec![voucher], Aggregation::Median, 1)).unwrap();
+
+    let err = set_feed(&mut deps, &env, &admin, feed(vec![source(1, 0)], Aggregation::Median, 1)).unwrap_err();
+    assert_eq!(err, invalid("source weights must be positive"));
+    let err = set_feed(&mut deps, &env, &admin, feed(vec![source(1, 1)], Aggregation::Median, 2)).unwrap_err();
+    assert_eq!(err, invalid("min_sources must be between 1 and the number of sources"));
+}
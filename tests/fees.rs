@@ -0,0 +1,152 @@
+mod common;
+
+use cosmwasm_std::testing::{message_info, mock_ibc_packet_timeout};
+use cosmwasm_std::{coins, from_json, BankMsg, CosmosMsg, Env, MessageInfo, Uint128};
+use icq_sender::contract::{execute, query};
+use icq_sender::ibc::{ibc_packet_ack, ibc_packet_timeout};
+use icq_sender::msg::{ArithmeticTwapToNowResponse, ExecuteMsg, FeeScheduleResponse, QueryBalanceMsg, QueryKind, QueryMsg, UpdateConfigMsg};
+use icq_sender::ContractError;
+use prost::Message;
+
+use common::{ack_for, packet_of, remote_address, setup, twap_msg, Deps, CHANNEL};
+
+fn enable_fees(deps: &mut Deps, env: &Env, admin: &MessageInfo, refund_on_failure: bool) {
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        fee_denom: Some("untrn".to_string()),
+        refund_on_failure: Some(refund_on_failure),
+        ..Default::default()
+    }))
+    .unwrap();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::SetFee { kind: QueryKind::Twap, amount: Uint128::new(5) }).unwrap();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::SetFee { kind: QueryKind::Balance, amount: Uint128::new(3) }).unwrap();
+}
+
+fn credits(deps: &Deps, env: &Env, address: &str) -> u128 {
+    let credits: Uint128 = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Credits { address: address.to_string() }).unwrap()).unwrap();
+    credits.u128()
+}
+
+fn fee_schedule(deps: &Deps, env: &Env) -> FeeScheduleResponse {
+    from_json(query(deps.as_ref(), env.clone(), QueryMsg::FeeSchedule {}).unwrap()).unwrap()
+}
+
+#[test]
+fn fees_need_a_fee_denom() {
+    let (mut deps, env, admin) = setup();
+    let set_fee = |amount: u128| ExecuteMsg::SetFee { kind: QueryKind::Twap, amount: Uint128::new(amount) };
+    let err = execute(deps.as_mut(), env.clone(), admin.clone(), set_fee(5)).unwrap_err();
+    assert_eq!(err, ContractError::FeesDisabled {});
+    // making a kind free needs no denom
+    execute(deps.as_mut(), env.clone(), admin.clone(), set_fee(0)).unwrap();
+
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+    execute(deps.as_mut(), env, user, ExecuteMsg::SendQueryTwap(twap_msg(1, "uosmo", "uatom"))).unwrap();
+}
+
+#[test]
+fn sends_are_paid_per_kind_from_credits() {
+    let (mut deps, env, admin) = setup();
+    enable_fees(&mut deps, &env, &admin, false);
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+
+    let err = execute(deps.as_mut(), env.clone(), user.clone(), ExecuteMsg::SendQueryTwap(twap_msg(1, "uosmo", "uatom"))).unwrap_err();
+    assert_eq!(err, ContractError::InsufficientCredits { required: Uint128::new(5), available: Uint128::zero() });
+
+    let err = execute(deps.as_mut(), env.clone(), message_info(&user.sender, &coins(10, "uatom")), ExecuteMsg::Deposit {}).unwrap_err();
+    assert_eq!(err, ContractError::WrongDenom { expected: "untrn".to_string() });
+    execute(deps.as_mut(), env.clone(), message_info(&user.sender, &coins(10, "untrn")), ExecuteMsg::Deposit {}).unwrap();
+
+    execute(deps.as_mut(), env.clone(), user.clone(), ExecuteMsg::SendQueryTwap(twap_msg(1, "uosmo", "uatom"))).unwrap();
+    assert_eq!(credits(&deps, &env, user.sender.as_str()), 5);
+    execute(deps.as_mut(), env.clone(), user.clone(), ExecuteMsg::SendQueryBalance(QueryBalanceMsg {
+        channel: CHANNEL.to_string(),
+        height: None,
+        address: remote_address("cosmos", 1),
+        denom: "uatom".to_string(),
+    }))
+    .unwrap();
+    assert_eq!(credits(&deps, &env, user.sender.as_str()), 2);
+
+    // joining a query already in flight is free
+    let other = message_info(&deps.api.addr_make("other"), &[]);
+    execute(deps.as_mut(), env.clone(), other, ExecuteMsg::SendQueryTwap(twap_msg(1, "uosmo", "uatom"))).unwrap();
+
+    let res = execute(deps.as_mut(), env.clone(), user.clone(), ExecuteMsg::Withdraw { amount: Uint128::new(2) }).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send { to_address: user.sender.to_string(), amount: coins(2, "untrn") }));
+    assert_eq!(credits(&deps, &env, user.sender.as_str()), 0);
+}
+
+#[test]
+fn failed_requests_are_refunded_when_enabled() {
+    let (mut deps, env, admin) = setup();
+    enable_fees(&mut deps, &env, &admin, true);
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+    execute(deps.as_mut(), env.clone(), message_info(&user.sender, &coins(10, "untrn")), ExecuteMsg::Deposit {}).unwrap();
+
+    let res = execute(deps.as_mut(), env.clone(), user.clone(), ExecuteMsg::SendQueryTwap(twap_msg(1, "uosmo", "uatom"))).unwrap();
+    let mut timeout = mock_ibc_packet_timeout(CHANNEL, &1u8).unwrap();
+    timeout.packet.data = packet_of(&res);
+    ibc_packet_timeout(deps.as_mut(), env.clone(), timeout).unwrap();
+    assert_eq!(credits(&deps, &env, user.sender.as_str()), 10);
+    assert!(fee_schedule(&deps, &env).collected.is_zero());
+
+    let res = execute(deps.as_mut(), env.clone(), user.clone(), ExecuteMsg::SendQueryTwap(twap_msg(1, "uosmo", "uatom"))).unwrap();
+    let value = ArithmeticTwapToNowResponse { arithmetic_twap: "1.5".to_string() }.encode_to_vec();
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![value], 2)).unwrap();
+    assert_eq!(credits(&deps, &env, user.sender.as_str()), 5);
+    assert_eq!(fee_schedule(&deps, &env).collected, Uint128::new(5));
+}
+
+#[test]
+fn failed_requests_are_kept_as_fees_without_refunds() {
+    let (mut deps, env, admin) = setup();
+    enable_fees(&mut deps, &env, &admin, false);
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+    execute(deps.as_mut(), env.clone(), message_info(&user.sender, &coins(10, "untrn")), ExecuteMsg::Deposit {}).unwrap();
+
+    let res = execute(deps.as_mut(), env.clone(), user.clone(), ExecuteMsg::SendQueryTwap(twap_msg(1, "uosmo", "uatom"))).unwrap();
+    let mut timeout = mock_ibc_packet_timeout(CHANNEL, &1u8).unwrap();
+    timeout.packet.data = packet_of(&res);
+    ibc_packet_timeout(deps.as_mut(), env.clone(), timeout).unwrap();
+    assert_eq!(credits(&deps, &env, user.sender.as_str()), 5);
+    assert_eq!(fee_schedule(&deps, &env).collected, Uint128::new(5));
+}
+
+#[test]
+fn only_earned_fees_are_collected() {
+    let (mut deps, env, admin) = setup();
+    enable_fees(&mut deps, &env, &admin, false);
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+    execute(deps.as_mut(), env.clone(), message_info(&user.sender, &coins(100, "untrn")), ExecuteMsg::Deposit {}).unwrap();
+
+    // fees of requests still in flight are not earned yet
+    let res = execute(deps.as_mut(), env.clone(), user.clone(), ExecuteMsg::SendQueryTwap(twap_msg(1, "uosmo", "uatom"))).unwrap();
+    let err = execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::CollectFees { to: None }).unwrap_err();
+    assert_eq!(err, ContractError::NoFunds {});
+
+    let value = ArithmeticTwapToNowResponse { arithmetic_twap: "1.5".to_string() }.encode_to_vec();
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![value], 1)).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::CollectFees { to: None }).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send { to_address: admin.sender.to_string(), amount: coins(5, "untrn") }));
+    assert!(fee_schedule(&deps, &env).collected.is_zero());
+    assert_eq!(credits(&deps, &env, user.sender.as_str()), 95);
+
+    // credits are held in the fee denom, which therefore cannot change
+    let err = execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        fee_denom: Some("uatom".to_string()),
+        ..Default::default()
+    }))
+    .unwrap_err();
+    assert_eq!(err, ContractError::FeeDenomAlreadySet { denom: "untrn".to_string() });
+}
+
+#[test]
+fn every_query_path_has_its_own_kind() {
+    use icq_sender::msg::{GOV_PROPOSALS_QUERY_PATH, SUPPLY_OF_QUERY_PATH, WASM_SMART_QUERY_PATH};
+
+    assert_eq!(QueryKind::from_path(SUPPLY_OF_QUERY_PATH), QueryKind::Supply);
+    assert_eq!(QueryKind::from_path(WASM_SMART_QUERY_PATH), QueryKind::WasmSmart);
+    assert_eq!(QueryKind::from_path(GOV_PROPOSALS_QUERY_PATH), QueryKind::GovProposals);
+    assert_eq!(QueryKind::from_path("/store/bank/key"), QueryKind::Proven);
+    assert_eq!(QueryKind::from_path("/custom.v1.Query/Thing"), QueryKind::Raw);
+}
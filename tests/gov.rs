@@ -0,0 +1,57 @@
+mod common;
+
+use cosmos_sdk_proto::cosmos::gov::v1::{Proposal, ProposalStatus as ProtoProposalStatus, QueryProposalsResponse, TallyResult};
+use cosmwasm_std::from_json;
+use icq_sender::contract::{execute, query};
+use icq_sender::ibc::ibc_packet_ack;
+use icq_sender::msg::{ExecuteMsg, GOV_PROPOSALS_QUERY_PATH, QueryMsg};
+use icq_sender::state::{ProposalRecord, ProposalStatus};
+use prost::Message;
+
+use common::{ack_for, packet_of, setup, Deps, CHANNEL};
+
+fn proposal(id: u64, status: ProtoProposalStatus, yes: &str) -> Proposal {
+    Proposal {
+        id,
+        title: format!("proposal {id}"),
+        status: status as i32,
+        final_tally_result: Some(TallyResult { yes_count: yes.to_string(), ..Default::default() }),
+        ..Default::default()
+    }
+}
+
+fn proposals(deps: &Deps) -> Vec<(u64, ProposalRecord)> {
+    let msg = QueryMsg::Proposals { chain: "hub".to_string(), status: None, start_after: None, limit: None };
+    from_json(query(deps.as_ref(), common::mock_env(), msg).unwrap()).unwrap()
+}
+
+#[test]
+fn a_page_with_an_unparsable_tally_stores_nothing() {
+    let (mut deps, env, admin) = setup();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::AllowQueryPaths {
+        channel: CHANNEL.to_string(),
+        paths: vec![GOV_PROPOSALS_QUERY_PATH.to_string()],
+    })
+    .unwrap();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::SetChainAlias { alias: "hub".to_string(), channel: Some(CHANNEL.to_string()) }).unwrap();
+    let send = ExecuteMsg::SendQueryProposals { channel: CHANNEL.to_string(), status: None };
+
+    let page = QueryProposalsResponse {
+        proposals: vec![proposal(1, ProtoProposalStatus::VotingPeriod, "10"), proposal(2, ProtoProposalStatus::Passed, "ten")],
+        pagination: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), admin.clone(), send.clone()).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![page.encode_to_vec()], 1)).unwrap();
+    assert!(res.attributes.iter().any(|attr| attr.key == "error"));
+    assert!(res.events.iter().all(|event| event.ty != "proposal_voting_started"));
+    assert!(proposals(&deps).is_empty());
+
+    // the voting period is still reported once the host answers properly
+    let page = QueryProposalsResponse { proposals: vec![proposal(1, ProtoProposalStatus::VotingPeriod, "10")], pagination: None };
+    let res = execute(deps.as_mut(), env.clone(), admin, send).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), env, ack_for(packet_of(&res), vec![page.encode_to_vec()], 2)).unwrap();
+    assert!(res.events.iter().any(|event| event.ty == "proposal_voting_started"));
+    let stored = proposals(&deps);
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].1.status, ProposalStatus::VotingPeriod);
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use cosmos_sdk_proto::cosmos::authz::v1beta1::{GenericAuthorization, GrantAuthorization, QueryGranterGrantsRequest, QueryGranterGrantsResponse};
+use cosmos_sdk_proto::cosmos::base::query::v1beta1::PageResponse;
+use cosmos_sdk_proto::{Any, Timestamp};
+use cosmwasm_std::from_json;
+use icq_sender::contract::{execute, query};
+use icq_sender::grants::GRANTER_GRANTS_PAGE_LIMIT;
+use icq_sender::ibc::ibc_packet_ack;
+use icq_sender::msg::{CosmosQuery, ExecuteMsg, InterchainQueryPacketData, QueryMsg, AUTHZ_GRANTER_GRANTS_QUERY_PATH};
+use icq_sender::state::AuthzGrant;
+use prost::Message;
+
+use common::{ack_for, packet_of, remote_address, setup, Deps, CHANNEL};
+
+fn grant(granter: &str, grantee: &str) -> GrantAuthorization {
+    let authorization = GenericAuthorization { msg: "/cosmos.bank.v1beta1.MsgSend".to_string() };
+    GrantAuthorization {
+        granter: granter.to_string(),
+        grantee: grantee.to_string(),
+        authorization: Some(Any { type_url: "/cosmos.authz.v1beta1.GenericAuthorization".to_string(), value: authorization.encode_to_vec() }),
+        expiration: None,
+    }
+}
+
+fn grantees(deps: &Deps, granter: &str) -> Vec<String> {
+    let msg = QueryMsg::AuthzGrants { chain: "hub".to_string(), granter: granter.to_string(), grantee: None };
+    let grants: Vec<(String, Vec<AuthzGrant>)> = from_json(query(deps.as_ref(), common::mock_env(), msg).unwrap()).unwrap();
+    grants.into_iter().map(|(grantee, _)| grantee).collect()
+}
+
+#[test]
+fn grantees_missing_from_a_partial_page_are_kept() {
+    let (mut deps, mut env, admin) = setup();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::AllowQueryPaths {
+        channel: CHANNEL.to_string(),
+        paths: vec![AUTHZ_GRANTER_GRANTS_QUERY_PATH.to_string()],
+    })
+    .unwrap();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::SetChainAlias { alias: "hub".to_string(), channel: Some(CHANNEL.to_string()) }).unwrap();
+    let granter = remote_address("cosmos", 1);
+    let (first, second) = (remote_address("cosmos", 2), remote_address("cosmos", 3));
+    let send = ExecuteMsg::SendQueryGrants { channel: CHANNEL.to_string(), granter: granter.clone(), grantee: None };
+
+    let res = execute(deps.as_mut(), env.clone(), admin.clone(), send.clone()).unwrap();
+    let packet = packet_of(&res);
+    let data: InterchainQueryPacketData = from_json(&packet).unwrap();
+    let request = QueryGranterGrantsRequest::decode(CosmosQuery::decode(data.data.as_slice()).unwrap().requests[0].data.as_slice()).unwrap();
+    assert_eq!(request.pagination.unwrap().limit, GRANTER_GRANTS_PAGE_LIMIT);
+    let answer = QueryGranterGrantsResponse { grants: vec![grant(&granter, &first), grant(&granter, &second)], pagination: None };
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet, vec![answer.encode_to_vec()], 1)).unwrap();
+    let mut both = vec![first.clone(), second.clone()];
+    both.sort();
+    assert_eq!(grantees(&deps, &granter), both);
+
+    // more grants follow, so the second grantee may only be on another page
+    env.block.time = env.block.time.plus_seconds(60);
+    let res = execute(deps.as_mut(), env.clone(), admin.clone(), send.clone()).unwrap();
+    let page = PageResponse { next_key: vec![1], total: 0 };
+    let answer = QueryGranterGrantsResponse { grants: vec![grant(&granter, &first)], pagination: Some(page) };
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![answer.encode_to_vec()], 2)).unwrap();
+    assert_eq!(grantees(&deps, &granter), both);
+
+    // a complete answer without it means its grant is gone
+    env.block.time = env.block.time.plus_seconds(60);
+    let res = execute(deps.as_mut(), env.clone(), admin, send).unwrap();
+    let answer = QueryGranterGrantsResponse { grants: vec![grant(&granter, &first)], pagination: Some(PageResponse::default()) };
+    ibc_packet_ack(deps.as_mut(), env, ack_for(packet_of(&res), vec![answer.encode_to_vec()], 3)).unwrap();
+    assert_eq!(grantees(&deps, &granter), vec![first]);
+}
+
+#[test]
+fn a_grant_expiring_past_what_a_timestamp_holds_never_expires() {
+    let (mut deps, env, admin) = setup();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::AllowQueryPaths {
+        channel: CHANNEL.to_string(),
+        paths: vec![AUTHZ_GRANTER_GRANTS_QUERY_PATH.to_string()],
+    })
+    .unwrap();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::SetChainAlias { alias: "hub".to_string(), channel: Some(CHANNEL.to_string()) }).unwrap();
+    let (granter, grantee) = (remote_address("cosmos", 1), remote_address("cosmos", 2));
+    let send = ExecuteMsg::SendQueryGrants { channel: CHANNEL.to_string(), granter: granter.clone(), grantee: None };
+
+    let res = execute(deps.as_mut(), env.clone(), admin, send).unwrap();
+    let mut far = grant(&granter, &grantee);
+    far.expiration = Some(Timestamp { seconds: 253_402_300_799, nanos: 0 });
+    let answer = QueryGranterGrantsResponse { grants: vec![far], pagination: None };
+    ibc_packet_ack(deps.as_mut(), env, ack_for(packet_of(&res), vec![answer.encode_to_vec()], 1)).unwrap();
+
+    let msg = QueryMsg::AuthzGrants { chain: "hub".to_string(), granter, grantee: None };
+    let grants: Vec<(String, Vec<AuthzGrant>)> = from_json(query(deps.as_ref(), common::mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(grants.len(), 1);
+    assert_eq!(grants[0].1[0].expiration, None);
+    assert!(!grants[0].1[0].expiring);
+}
@@ -0,0 +1,219 @@
+//! Store proofs of a four leaf IAVL tree committed to by an app hash through
+//! a two store Tendermint simple merkle tree. The fixture is built here with
+//! the IAVL node encoding rather than recorded from a chain.
+
+use icq_sender::ics23::{
+    commitment_proof, CommitmentProof, ExistenceProof, IAVL_PROOF_TYPE, InnerOp, LeafOp, NonExistenceProof, ProofOpRef, SIMPLE_PROOF_TYPE,
+    verify_store_proof,
+};
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+const STORE: &str = "bank";
+const KEYS: [&[u8]; 4] = [b"a", b"c", b"e", b"g"];
+const VERSION: i64 = 7;
+
+fn sha256(data: &[u8]) -> Vec<u8> {
+    Sha256::digest(data).to_vec()
+}
+
+fn zigzag(value: i64, out: &mut Vec<u8>) {
+    prost::encoding::encode_varint(((value << 1) ^ (value >> 63)) as u64, out);
+}
+
+/// Height, size and version of an IAVL node.
+fn header(height: i64, size: i64) -> Vec<u8> {
+    let mut header = vec![];
+    zigzag(height, &mut header);
+    zigzag(size, &mut header);
+    zigzag(VERSION, &mut header);
+    header
+}
+
+fn value_of(key: &[u8]) -> Vec<u8> {
+    [b"value of ".as_slice(), key].concat()
+}
+
+fn iavl_leaf() -> LeafOp {
+    LeafOp { hash: 1, prehash_key: 0, prehash_value: 1, length: 1, prefix: header(0, 1) }
+}
+
+fn leaf_hash(leaf: &LeafOp, key: &[u8], value: &[u8]) -> Vec<u8> {
+    let mut preimage = leaf.prefix.clone();
+    prost::encoding::encode_varint(key.len() as u64, &mut preimage);
+    preimage.extend(key);
+    let value = sha256(value);
+    prost::encoding::encode_varint(value.len() as u64, &mut preimage);
+    preimage.extend(value);
+    sha256(&preimage)
+}
+
+/// The op hashing a node with its sibling into their parent.
+fn iavl_inner(height: i64, size: i64, sibling: &[u8], node_is_left: bool) -> InnerOp {
+    let mut prefix = header(height, size);
+    prefix.push(32);
+    if node_is_left {
+        InnerOp { hash: 1, prefix, suffix: [&[32u8][..], sibling].concat() }
+    } else {
+        prefix.extend(sibling);
+        prefix.push(32);
+        InnerOp { hash: 1, prefix, suffix: vec![] }
+    }
+}
+
+fn apply(inner: &InnerOp, node: &[u8]) -> Vec<u8> {
+    sha256(&[inner.prefix.as_slice(), node, &inner.suffix].concat())
+}
+
+fn leaves() -> Vec<Vec<u8>> {
+    KEYS.iter().map(|key| leaf_hash(&iavl_leaf(), key, &value_of(key))).collect()
+}
+
+/// Existence proof of the leaf at `index`, from the leaf up to the root.
+fn exist(index: usize) -> ExistenceProof {
+    let leaves = leaves();
+    let pair = index / 2 * 2;
+    let lower = iavl_inner(1, 2, &leaves[index ^ 1], index.is_multiple_of(2));
+    let sibling_pair = pair ^ 2;
+    let sibling = apply(&iavl_inner(1, 2, &leaves[sibling_pair + 1], true), &leaves[sibling_pair]);
+    let upper = iavl_inner(2, 4, &sibling, pair == 0);
+    ExistenceProof { key: KEYS[index].to_vec(), value: value_of(KEYS[index]), leaf: Some(iavl_leaf()), path: vec![lower, upper] }
+}
+
+fn root_of(proof: &ExistenceProof) -> Vec<u8> {
+    let leaf = leaf_hash(proof.leaf.as_ref().unwrap(), &proof.key, &proof.value);
+    proof.path.iter().fold(leaf, |node, inner| apply(inner, &node))
+}
+
+fn encode(proof: commitment_proof::Proof) -> Vec<u8> {
+    CommitmentProof { proof: Some(proof) }.encode_to_vec()
+}
+
+/// Simple proof of `store_root` as the second of two stores, with the app
+/// hash it commits to.
+fn simple_proof(store_root: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let leaf = LeafOp { hash: 1, prehash_key: 0, prehash_value: 1, length: 1, prefix: vec![0] };
+    let other = leaf_hash(&leaf, b"acc", b"root of acc");
+    let inner = InnerOp { hash: 1, prefix: [&[1u8][..], &other].concat(), suffix: vec![] };
+    let app_hash = apply(&inner, &leaf_hash(&leaf, STORE.as_bytes(), store_root));
+    let proof = ExistenceProof { key: STORE.as_bytes().to_vec(), value: store_root.to_vec(), leaf: Some(leaf), path: vec![inner] };
+    (encode(commitment_proof::Proof::Exist(proof)), app_hash)
+}
+
+fn store_root() -> Vec<u8> {
+    root_of(&exist(0))
+}
+
+fn verify(iavl_data: &[u8], key: &[u8], value: &[u8]) -> Result<(), String> {
+    let (simple_data, app_hash) = simple_proof(&store_root());
+    let ops = [
+        ProofOpRef { r#type: IAVL_PROOF_TYPE, key, data: iavl_data },
+        ProofOpRef { r#type: SIMPLE_PROOF_TYPE, key: STORE.as_bytes(), data: &simple_data },
+    ];
+    verify_store_proof(&ops, STORE, key, value, &app_hash)
+}
+
+fn absence(key: &[u8], left: Option<usize>, right: Option<usize>) -> Vec<u8> {
+    encode(commitment_proof::Proof::Nonexist(NonExistenceProof { key: key.to_vec(), left: left.map(exist), right: right.map(exist) }))
+}
+
+#[test]
+fn fixture_paths_share_one_root() {
+    let root = store_root();
+    for index in 0..KEYS.len() {
+        assert_eq!(root_of(&exist(index)), root);
+    }
+}
+
+#[test]
+fn existence_is_verified_against_the_app_hash() {
+    for (index, key) in KEYS.into_iter().enumerate() {
+        let data = encode(commitment_proof::Proof::Exist(exist(index)));
+        assert_eq!(verify(&data, key, &value_of(key)), Ok(()));
+    }
+
+    let data = encode(commitment_proof::Proof::Exist(exist(1)));
+    assert_eq!(verify(&data, b"c", b"forged"), Err("proof is for another value".to_string()));
+
+    let (simple_data, _) = simple_proof(&store_root());
+    let ops = [
+        ProofOpRef { r#type: IAVL_PROOF_TYPE, key: b"c", data: &data },
+        ProofOpRef { r#type: SIMPLE_PROOF_TYPE, key: STORE.as_bytes(), data: &simple_data },
+    ];
+    assert_eq!(
+        verify_store_proof(&ops, STORE, b"c", &value_of(b"c"), &[0; 32]),
+        Err("proof does not match the app hash".to_string())
+    );
+
+    // a tampered sibling changes the store root the simple proof commits to
+    let mut tampered = exist(1);
+    tampered.path[1].suffix[5] ^= 1;
+    let data = encode(commitment_proof::Proof::Exist(tampered));
+    assert_eq!(verify(&data, b"c", &value_of(b"c")), Err("proof is for another value".to_string()));
+}
+
+#[test]
+fn absence_is_verified_between_adjacent_keys() {
+    // neighbors under the same inner node
+    assert_eq!(verify(&absence(b"b", Some(0), Some(1)), b"b", b""), Ok(()));
+    // neighbors parting at the root
+    assert_eq!(verify(&absence(b"d", Some(1), Some(2)), b"d", b""), Ok(()));
+    // before the first and after the last key
+    assert_eq!(verify(&absence(b"0", None, Some(0)), b"0", b""), Ok(()));
+    assert_eq!(verify(&absence(b"z", Some(3), None), b"z", b""), Ok(()));
+
+    // an empty value is only proven by a non-existence proof
+    let data = encode(commitment_proof::Proof::Exist(exist(1)));
+    assert_eq!(verify(&data, b"c", b""), Err("expected a non-existence proof".to_string()));
+}
+
+#[test]
+fn absence_proofs_with_wrong_neighbors_are_rejected() {
+    // skipping over "c"
+    assert_eq!(verify(&absence(b"b", Some(0), Some(2)), b"b", b""), Err("neighbors are not adjacent".to_string()));
+    assert_eq!(verify(&absence(b"f", Some(1), Some(3)), b"f", b""), Err("neighbors are not adjacent".to_string()));
+    // "a" is not at the edge of the tree
+    assert_eq!(verify(&absence(b"b", Some(0), None), b"b", b""), Err("neighbors are not adjacent".to_string()));
+    assert_eq!(verify(&absence(b"b", None, Some(1)), b"b", b""), Err("neighbors are not adjacent".to_string()));
+    // the key itself is in the tree
+    assert_eq!(verify(&absence(b"c", Some(0), Some(1)), b"c", b""), Err("right neighbor is not after the key".to_string()));
+    assert_eq!(verify(&absence(b"b", Some(1), Some(2)), b"b", b""), Err("left neighbor is not before the key".to_string()));
+    assert_eq!(verify(&absence(b"b", None, None), b"b", b""), Err("non-existence proof without neighbors".to_string()));
+    assert_eq!(verify(&absence(b"x", Some(0), Some(1)), b"b", b""), Err("proof is for another key".to_string()));
+
+    // neighbors from another tree
+    let mut right = exist(1);
+    right.path[1].suffix[5] ^= 1;
+    let data = encode(commitment_proof::Proof::Nonexist(NonExistenceProof { key: b"b".to_vec(), left: Some(exist(0)), right: Some(right) }));
+    assert_eq!(verify(&data, b"b", b""), Err("neighbors are proven against different roots".to_string()));
+}
+
+#[test]
+fn proofs_out_of_spec_are_rejected() {
+    let check = |proof: ExistenceProof| verify(&encode(commitment_proof::Proof::Exist(proof)), b"a", &value_of(b"a"));
+
+    let mut proof = exist(0);
+    proof.leaf.as_mut().unwrap().prehash_key = 1;
+    assert_eq!(check(proof), Err("leaf ops do not match the proof spec".to_string()));
+
+    let mut proof = exist(0);
+    proof.leaf.as_mut().unwrap().prefix.push(0);
+    assert_eq!(check(proof), Err("IAVL leaf header followed by 1 bytes".to_string()));
+
+    let mut proof = exist(0);
+    proof.leaf.as_mut().unwrap().prefix = header(1, 1);
+    assert_eq!(check(proof), Err("leaf prefix does not match the proof spec".to_string()));
+
+    // the root claims to be at the height of the layer below it
+    let mut proof = exist(0);
+    proof.path[1] = iavl_inner(1, 4, &proof.path[1].suffix[1..], true);
+    assert_eq!(check(proof), Err("IAVL node of height 1 at layer 2".to_string()));
+
+    let mut proof = exist(0);
+    proof.path[0].prefix.push(32);
+    assert_eq!(check(proof), Err("IAVL inner node header followed by 2 bytes".to_string()));
+
+    let mut proof = exist(0);
+    proof.path[0].suffix.push(0);
+    assert_eq!(check(proof), Err("inner suffix of 34 bytes is out of spec".to_string()));
+}
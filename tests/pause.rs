@@ -0,0 +1,97 @@
+mod common;
+
+use cosmwasm_std::testing::message_info;
+use cosmwasm_std::from_json;
+use icq_sender::contract::{execute, query};
+use icq_sender::ibc::ibc_packet_ack;
+use icq_sender::msg::{ArithmeticTwapToNowResponse, ExecuteMsg, PauseScope, QueryBalanceMsg, QueryKind, QueryMsg, UpdateConfigMsg};
+use icq_sender::ContractError;
+use prost::Message;
+
+use common::{ack_for, packet_of, remote_address, setup, twap_msg, CHANNEL};
+
+fn balance() -> ExecuteMsg {
+    ExecuteMsg::SendQueryBalance(QueryBalanceMsg {
+        channel: CHANNEL.to_string(),
+        height: None,
+        address: remote_address("cosmos", 1),
+        denom: "uatom".to_string(),
+    })
+}
+
+fn twap() -> ExecuteMsg {
+    ExecuteMsg::SendQueryTwap(twap_msg(1, "uosmo", "uatom"))
+}
+
+#[test]
+fn pausing_a_kind_only_stops_that_kind() {
+    let (mut deps, env, admin) = setup();
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+    let scope = PauseScope { channel: Some(CHANNEL.to_string()), kind: Some(QueryKind::Twap) };
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::Pause(scope.clone())).unwrap();
+
+    let err = execute(deps.as_mut(), env.clone(), user.clone(), twap()).unwrap_err();
+    assert_eq!(err, ContractError::Paused { channel: CHANNEL.to_string(), kind: "twap".to_string() });
+    execute(deps.as_mut(), env.clone(), user.clone(), balance()).unwrap();
+
+    let paused: Vec<PauseScope> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Paused {}).unwrap()).unwrap();
+    assert_eq!(paused, vec![scope.clone()]);
+    execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::Unpause(scope)).unwrap();
+    execute(deps.as_mut(), env, user, twap()).unwrap();
+}
+
+#[test]
+fn the_empty_scope_pauses_everything_but_acks() {
+    let (mut deps, env, admin) = setup();
+    let guardian = message_info(&deps.api.addr_make("guardian"), &[]);
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+    let everything = PauseScope { channel: None, kind: None };
+
+    let err = execute(deps.as_mut(), env.clone(), guardian.clone(), ExecuteMsg::Pause(everything.clone())).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+    execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        guardian: Some(guardian.sender.to_string()),
+        ..Default::default()
+    }))
+    .unwrap();
+
+    let in_flight = execute(deps.as_mut(), env.clone(), user.clone(), twap()).unwrap();
+    execute(deps.as_mut(), env.clone(), guardian.clone(), ExecuteMsg::Pause(everything.clone())).unwrap();
+    let err = execute(deps.as_mut(), env.clone(), user.clone(), balance()).unwrap_err();
+    assert_eq!(err, ContractError::Paused { channel: "*".to_string(), kind: "*".to_string() });
+
+    // packets already sent are still settled
+    let value = ArithmeticTwapToNowResponse { arithmetic_twap: "1.5".to_string() }.encode_to_vec();
+    let res = ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&in_flight), vec![value], 1)).unwrap();
+    assert!(res.events.iter().any(|event| event.ty == "icq_result"));
+
+    // lifting a narrower scope leaves the global pause in place
+    execute(deps.as_mut(), env.clone(), guardian.clone(), ExecuteMsg::Unpause(PauseScope { channel: Some(CHANNEL.to_string()), kind: None })).unwrap();
+    assert!(execute(deps.as_mut(), env.clone(), user.clone(), balance()).is_err());
+    execute(deps.as_mut(), env.clone(), guardian, ExecuteMsg::Unpause(everything)).unwrap();
+    execute(deps.as_mut(), env, user, balance()).unwrap();
+}
+
+#[test]
+fn query_kinds_added_later_are_paused_apart_from_raw_queries() {
+    use icq_sender::msg::{QueryRawMsg, SUPPLY_OF_QUERY_PATH};
+
+    let (mut deps, env, admin) = setup();
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::AllowQueryPaths {
+        channel: CHANNEL.to_string(),
+        paths: vec![SUPPLY_OF_QUERY_PATH.to_string(), "/custom.v1.Query/Thing".to_string()],
+    })
+    .unwrap();
+    execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::Pause(PauseScope { channel: None, kind: Some(QueryKind::Raw) })).unwrap();
+
+    let raw = |path: &str| ExecuteMsg::SendQueryRaw(QueryRawMsg {
+        channel: CHANNEL.to_string(),
+        height: None,
+        path: path.to_string(),
+        data: vec![1].into(),
+    });
+    let err = execute(deps.as_mut(), env.clone(), user.clone(), raw("/custom.v1.Query/Thing")).unwrap_err();
+    assert_eq!(err, ContractError::Paused { channel: "*".to_string(), kind: "raw".to_string() });
+    execute(deps.as_mut(), env, user, raw(SUPPLY_OF_QUERY_PATH)).unwrap();
+}
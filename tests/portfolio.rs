@@ -0,0 +1,74 @@
+mod common;
+
+use cosmos_sdk_proto::cosmos::bank::v1beta1::{DenomUnit, Metadata, QueryBalanceResponse, QueryDenomMetadataResponse};
+use cosmos_sdk_proto::cosmos::base::v1beta1::Coin as ProtoCoinMsg;
+use cosmwasm_std::{from_json, Decimal256};
+use icq_sender::contract::{execute, query};
+use icq_sender::ibc::ibc_packet_ack;
+use icq_sender::msg::{
+    DENOM_METADATA_QUERY_PATH, DENOM_TRACE_QUERY_PATH, ExecuteMsg, PortfolioValueResponse, ProtoDenomTrace, QueryDenomTraceResponse, QueryMsg,
+    UpdateConfigMsg,
+};
+use icq_sender::state::Holding;
+use prost::Message;
+
+use common::{ack_for, feed_twap, packet_of, remote_address, setup, CHANNEL};
+
+fn balance(denom: &str, amount: &str) -> Vec<u8> {
+    QueryBalanceResponse { balance: Some(ProtoCoinMsg { denom: denom.to_string(), amount: amount.to_string() }) }.encode_to_vec()
+}
+
+fn metadata(denom: &str) -> Vec<u8> {
+    let units = vec![
+        DenomUnit { denom: denom.to_string(), exponent: 0, aliases: vec![] },
+        DenomUnit { denom: "atom".to_string(), exponent: 6, aliases: vec![] },
+    ];
+    let metadata = Metadata { base: denom.to_string(), display: "atom".to_string(), denom_units: units, ..Default::default() };
+    QueryDenomMetadataResponse { metadata: Some(metadata) }.encode_to_vec()
+}
+
+#[test]
+fn vouchers_are_priced_as_the_base_denom_of_their_trace() {
+    let (mut deps, env, admin) = setup();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::AllowQueryPaths {
+        channel: CHANNEL.to_string(),
+        paths: vec![DENOM_METADATA_QUERY_PATH.to_string(), DENOM_TRACE_QUERY_PATH.to_string()],
+    })
+    .unwrap();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::SetChainAlias { alias: "hub".to_string(), channel: Some(CHANNEL.to_string()) }).unwrap();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        quote_denom: Some("uosmo".to_string()),
+        ..Default::default()
+    }))
+    .unwrap();
+    feed_twap(&mut deps, &env, 1, "uatom", "uosmo", "10.0", 1);
+
+    let voucher = format!("ibc/{}", "A".repeat(64));
+    let holding = Holding { chain: "hub".to_string(), address: remote_address("cosmos", 1), denoms: vec![voucher.clone()] };
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::SetPortfolio { name: "fund".to_string(), holdings: vec![holding] }).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::RefreshPortfolio { name: "fund".to_string() }).unwrap();
+    // the refresh asks for the trace of the voucher, which the host does not know yet
+    let answers = vec![QueryDenomTraceResponse { denom_trace: None }.encode_to_vec(), balance(&voucher, "2000000"), metadata(&voucher)];
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), answers, 2)).unwrap();
+    let value = |deps: &common::Deps| -> PortfolioValueResponse {
+        from_json(query(deps.as_ref(), env.clone(), QueryMsg::PortfolioValue { name: "fund".to_string() }).unwrap()).unwrap()
+    };
+
+    // the hash alone says nothing of the asset
+    let unresolved = value(&deps);
+    assert_eq!(unresolved.missing.len(), 1);
+    assert_eq!(unresolved.missing[0].reason, "denom trace not resolved yet");
+
+    let res = execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::ResolveDenom { channel: CHANNEL.to_string(), denom: voucher.clone() }).unwrap();
+    let trace = QueryDenomTraceResponse {
+        denom_trace: Some(ProtoDenomTrace { path: "transfer/channel-1".to_string(), base_denom: "uatom".to_string() }),
+    };
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![trace.encode_to_vec()], 3)).unwrap();
+
+    let resolved = value(&deps);
+    assert!(resolved.missing.is_empty(), "{:?}", resolved.missing);
+    let holding = &resolved.chains[0].holdings[0];
+    assert_eq!(holding.denom, voucher);
+    assert_eq!(holding.price, Decimal256::from_ratio(10u32, 1u32));
+    assert_eq!(resolved.total, Decimal256::from_ratio(20u32, 1u32));
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use cosmwasm_std::testing::message_info;
+use cosmwasm_std::{from_json, Decimal256, Env, MessageInfo};
+use icq_sender::contract::{execute, query};
+use icq_sender::ibc::ibc_packet_ack;
+use icq_sender::msg::{ArithmeticTwapToNowResponse, DerivedPriceResponse, ExecuteMsg, QueryMsg, QueryTwapMsg, UpdateConfigMsg};
+use icq_sender::price::parse_twap;
+use icq_sender::state::{PriceBounds, PriceRecord, QuarantinedPrice};
+use icq_sender::ContractError;
+use prost::Message;
+
+use common::{ack_for, feed_twap, packet_of, setup, twap_msg, Deps, CHANNEL};
+
+/// Disables the result cache so every TWAP query goes out.
+fn configure(deps: &mut Deps, env: &Env, admin: &MessageInfo, price_bounds: PriceBounds) {
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        cache_ttl_seconds: Some(0),
+        price_bounds: Some(price_bounds),
+        ..Default::default()
+    }))
+    .unwrap();
+}
+
+fn status(res: &cosmwasm_std::IbcBasicResponse) -> String {
+    let event = res.events.iter().find(|event| event.ty == "price_update").unwrap();
+    event.attributes.iter().find(|attr| attr.key == "status").unwrap().value.clone()
+}
+
+fn prices(deps: &Deps, env: &Env) -> Vec<(String, PriceRecord)> {
+    from_json(query(deps.as_ref(), env.clone(), QueryMsg::Prices { base: "uosmo".to_string(), quote: "uatom".to_string() }).unwrap()).unwrap()
+}
+
+fn quarantined(deps: &Deps, env: &Env) -> Vec<(u64, QuarantinedPrice)> {
+    from_json(query(deps.as_ref(), env.clone(), QueryMsg::QuarantinedPrices { start_after: None, limit: None }).unwrap()).unwrap()
+}
+
+#[test]
+fn twaps_parse_in_both_encodings() {
+    assert_eq!(parse_twap("1500000000000000000").unwrap(), Decimal256::percent(150));
+    assert_eq!(parse_twap("1.5").unwrap(), Decimal256::percent(150));
+    assert_eq!(parse_twap("abc"), Err(ContractError::InvalidPrice { raw: "abc".to_string() }));
+}
+
+#[test]
+fn prices_deviating_too_far_are_quarantined_until_released() {
+    let (mut deps, env, admin) = setup();
+    configure(&mut deps, &env, &admin, PriceBounds { max_deviation: Some(Decimal256::percent(10)), max_age_seconds: None });
+
+    assert_eq!(status(&feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "1.0", 1)), "accepted");
+    assert_eq!(status(&feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "1.1", 2)), "accepted");
+    assert_eq!(status(&feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "1.5", 3)), "quarantined");
+    // each source is compared with its own last price
+    assert_eq!(status(&feed_twap(&mut deps, &env, 2, "uosmo", "uatom", "1.5", 4)), "accepted");
+
+    let stored = prices(&deps, &env);
+    assert_eq!(stored[0].0, format!("{CHANNEL}/1"));
+    assert_eq!(stored[0].1.price, Decimal256::percent(110));
+
+    let quarantined = quarantined(&deps, &env);
+    assert_eq!(quarantined.len(), 1);
+    assert_eq!(quarantined[0].0, 3);
+    assert_eq!(quarantined[0].1.price, Some(Decimal256::percent(150)));
+
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+    let err = execute(deps.as_mut(), env.clone(), user, ExecuteMsg::ReleaseQuarantinedPrice { sequence: 3 }).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::ReleaseQuarantinedPrice { sequence: 3 }).unwrap();
+    assert_eq!(prices(&deps, &env)[0].1.price, Decimal256::percent(150));
+    let err = execute(deps.as_mut(), env, admin, ExecuteMsg::ReleaseQuarantinedPrice { sequence: 3 }).unwrap_err();
+    assert_eq!(err, ContractError::NoSuchQuarantinedPrice { sequence: 3 });
+}
+
+#[test]
+fn a_jump_too_large_to_measure_is_quarantined() {
+    let (mut deps, env, admin) = setup();
+    configure(&mut deps, &env, &admin, PriceBounds { max_deviation: Some(Decimal256::percent(10)), max_age_seconds: None });
+
+    assert_eq!(status(&feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "0.000000000000000001", 1)), "accepted");
+    let huge = Decimal256::MAX.to_string();
+    let res = feed_twap(&mut deps, &env, 1, "uosmo", "uatom", &huge, 2);
+    assert_eq!(status(&res), "quarantined");
+    assert_eq!(quarantined(&deps, &env)[0].1.reason, "deviation from 0.000000000000000001 overflows");
+    assert_eq!(prices(&deps, &env)[0].1.price, Decimal256::from_atomics(1u8, 18).unwrap());
+}
+
+#[test]
+fn zero_unparsable_and_late_prices_are_quarantined() {
+    let (mut deps, env, admin) = setup();
+    configure(&mut deps, &env, &admin, PriceBounds { max_deviation: None, max_age_seconds: Some(90) });
+
+    assert_eq!(status(&feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "0", 1)), "quarantined");
+    assert_eq!(status(&feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "one", 2)), "quarantined");
+    let err = execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::ReleaseQuarantinedPrice { sequence: 2 }).unwrap_err();
+    assert_eq!(err, ContractError::InvalidPrice { raw: "one".to_string() });
+
+    // the ack arrives long after the end of the TWAP window
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+    let res = execute(deps.as_mut(), env.clone(), user, ExecuteMsg::SendQueryTwap(twap_msg(1, "uosmo", "uatom"))).unwrap();
+    let mut later = env.clone();
+    later.block.time = later.block.time.plus_seconds(120);
+    let value = ArithmeticTwapToNowResponse { arithmetic_twap: "1.0".to_string() }.encode_to_vec();
+    let res = ibc_packet_ack(deps.as_mut(), later.clone(), ack_for(packet_of(&res), vec![value], 3)).unwrap();
+    assert_eq!(status(&res), "quarantined");
+
+    assert!(prices(&deps, &later).is_empty());
+    let reasons: Vec<String> = quarantined(&deps, &later).into_iter().map(|(_, price)| price.reason).collect();
+    assert_eq!(reasons[0], "zero price");
+    assert!(reasons[2].starts_with("age "), "{}", reasons[2]);
+}
+
+#[test]
+fn a_twap_pinned_to_a_past_height_leaves_the_price_alone() {
+    let (mut deps, env, admin) = setup();
+    configure(&mut deps, &env, &admin, PriceBounds::default());
+    feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "1.0", 1);
+
+    let pinned = QueryTwapMsg { height: Some(100), ..twap_msg(1, "uosmo", "uatom") };
+    let res = execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::SendQueryTwap(pinned)).unwrap();
+    let value = ArithmeticTwapToNowResponse { arithmetic_twap: "7.0".to_string() }.encode_to_vec();
+    let res = ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![value], 2)).unwrap();
+    assert!(res.events.iter().all(|event| event.ty != "price_update"));
+
+    let stored = prices(&deps, &env);
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].1.price, Decimal256::one());
+    // the answer is still kept under its sequence
+    let feeds: Vec<(u64, String)> = from_json(query(deps.as_ref(), env, QueryMsg::AllPriceFeeds {}).unwrap()).unwrap();
+    assert_eq!(feeds[1], (2, "7.0".to_string()));
+}
+
+fn derived(deps: &Deps, env: &Env, base: &str, quote: &str) -> Result<DerivedPriceResponse, ContractError> {
+    query(deps.as_ref(), env.clone(), QueryMsg::DerivedPrice { base: base.to_string(), quote: quote.to_string() }).map(|res| from_json(res).unwrap())
+}
+
+#[test]
+fn inverse_and_cross_rates_are_derived_from_stored_pairs() {
+    let (mut deps, env, admin) = setup();
+    configure(&mut deps, &env, &admin, PriceBounds::default());
+    feed_twap(&mut deps, &env, 1, "uosmo", "uatom", "0.5", 1);
+    feed_twap(&mut deps, &env, 2, "ustrd", "uosmo", "3.0", 2);
+
+    let inverse = derived(&deps, &env, "uatom", "uosmo").unwrap();
+    assert_eq!(inverse.price, Decimal256::percent(200));
+    assert!(inverse.components[0].inverted);
+
+    // crossing needs the intermediate asset to be configured
+    let err = derived(&deps, &env, "ustrd", "uatom").unwrap_err();
+    assert_eq!(err, ContractError::NoPrice { base: "ustrd".to_string(), quote: "uatom".to_string() });
+    execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        intermediate_asset: Some("uosmo".to_string()),
+        ..Default::default()
+    }))
+    .unwrap();
+
+    let cross = derived(&deps, &env, "ustrd", "uatom").unwrap();
+    assert_eq!(cross.price, Decimal256::percent(150));
+    assert_eq!(cross.components.len(), 2);
+    let cross = derived(&deps, &env, "uatom", "ustrd").unwrap();
+    assert_eq!(cross.price, Decimal256::from_ratio(2u32, 3u32));
+    assert!(cross.components.iter().all(|component| component.inverted));
+
+    assert!(derived(&deps, &env, "ujuno", "uatom").is_err());
+    assert_eq!(
+        derived(&deps, &env, "uatom", "uatom").unwrap_err(),
+        ContractError::IdenticalTwapAssets { denom: "uatom".to_string() },
+    );
+}
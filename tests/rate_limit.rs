@@ -0,0 +1,86 @@
+mod common;
+
+use cosmwasm_std::testing::{message_info, mock_ibc_packet_timeout};
+use cosmwasm_std::{from_json, Env};
+use icq_sender::contract::{execute, query};
+use icq_sender::ibc::ibc_packet_timeout;
+use icq_sender::msg::{ExecuteMsg, QueryMsg, QuotaResponse};
+use icq_sender::state::RateLimit;
+use icq_sender::ContractError;
+
+use common::{packet_of, setup, twap_msg, Deps, CHANNEL};
+
+fn twap(pool_id: u64) -> ExecuteMsg {
+    ExecuteMsg::SendQueryTwap(twap_msg(pool_id, "uosmo", "uatom"))
+}
+
+fn sender_quota(deps: &Deps, env: &Env, sender: &str) -> QuotaResponse {
+    from_json(query(deps.as_ref(), env.clone(), QueryMsg::SenderQuota { sender: sender.to_string() }).unwrap()).unwrap()
+}
+
+#[test]
+fn senders_refill_one_window_at_a_time() {
+    let (mut deps, mut env, admin) = setup();
+    let limit = RateLimit { packets_per_window: 2, window_blocks: 10, max_in_flight: 10 };
+    execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::SetSenderRateLimit { limit: Some(limit) }).unwrap();
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+
+    execute(deps.as_mut(), env.clone(), user.clone(), twap(1)).unwrap();
+    execute(deps.as_mut(), env.clone(), user.clone(), twap(2)).unwrap();
+    let err = execute(deps.as_mut(), env.clone(), user.clone(), twap(3)).unwrap_err();
+    assert_eq!(err, ContractError::RateLimited { scope: format!("sender {}", user.sender) });
+
+    // other senders have their own bucket
+    let other = message_info(&deps.api.addr_make("other"), &[]);
+    execute(deps.as_mut(), env.clone(), other, twap(3)).unwrap();
+
+    // half a window refills one packet
+    env.block.height += 5;
+    let quota = sender_quota(&deps, &env, user.sender.as_str());
+    assert_eq!(quota.packets_remaining, Some(1));
+    assert_eq!(quota.in_flight, 2);
+    execute(deps.as_mut(), env.clone(), user.clone(), twap(4)).unwrap();
+    assert!(execute(deps.as_mut(), env.clone(), user.clone(), twap(5)).is_err());
+
+    env.block.height += 100;
+    assert_eq!(sender_quota(&deps, &env, user.sender.as_str()).packets_remaining, Some(2));
+}
+
+#[test]
+fn in_flight_requests_are_capped_until_answered() {
+    let (mut deps, env, admin) = setup();
+    let limit = RateLimit { packets_per_window: 10, window_blocks: 1, max_in_flight: 1 };
+    execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::SetSenderRateLimit { limit: Some(limit) }).unwrap();
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+
+    let res = execute(deps.as_mut(), env.clone(), user.clone(), twap(1)).unwrap();
+    let err = execute(deps.as_mut(), env.clone(), user.clone(), twap(2)).unwrap_err();
+    assert_eq!(err, ContractError::TooManyInFlight { scope: format!("sender {}", user.sender), max: 1 });
+
+    let mut timeout = mock_ibc_packet_timeout(CHANNEL, &1u8).unwrap();
+    timeout.packet.data = packet_of(&res);
+    ibc_packet_timeout(deps.as_mut(), env.clone(), timeout).unwrap();
+    execute(deps.as_mut(), env, user, twap(2)).unwrap();
+}
+
+#[test]
+fn channels_share_one_bucket_across_senders() {
+    let (mut deps, env, admin) = setup();
+    let limit = RateLimit { packets_per_window: 1, window_blocks: 10, max_in_flight: 10 };
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::SetChannelRateLimit { channel: CHANNEL.to_string(), limit: Some(limit) }).unwrap();
+    let alice = message_info(&deps.api.addr_make("alice"), &[]);
+    let bob = message_info(&deps.api.addr_make("bob"), &[]);
+
+    execute(deps.as_mut(), env.clone(), alice, twap(1)).unwrap();
+    let err = execute(deps.as_mut(), env.clone(), bob.clone(), twap(2)).unwrap_err();
+    assert_eq!(err, ContractError::RateLimited { scope: format!("channel {CHANNEL}") });
+    // a rejected send consumes nothing
+    let quota: QuotaResponse = from_json(query(deps.as_ref(), env.clone(), QueryMsg::ChannelQuota { channel: CHANNEL.to_string() }).unwrap()).unwrap();
+    assert_eq!(quota.in_flight, 1);
+
+    let invalid = RateLimit { packets_per_window: 0, window_blocks: 10, max_in_flight: 10 };
+    let err = execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::SetChannelRateLimit { channel: CHANNEL.to_string(), limit: Some(invalid) }).unwrap_err();
+    assert_eq!(err, ContractError::InvalidRateLimit {});
+    execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::SetChannelRateLimit { channel: CHANNEL.to_string(), limit: None }).unwrap();
+    execute(deps.as_mut(), env, bob, twap(2)).unwrap();
+}
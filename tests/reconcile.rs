@@ -0,0 +1,72 @@
+mod common;
+
+use cosmos_sdk_proto::cosmos::bank::v1beta1::QuerySupplyOfResponse;
+use cosmos_sdk_proto::cosmos::base::tendermint::v1beta1::AbciQueryResponse;
+use cosmos_sdk_proto::cosmos::base::v1beta1::Coin as ProtoCoinMsg;
+use cosmwasm_std::{from_json, MessageInfo, Uint128};
+use icq_sender::contract::{execute, query};
+use icq_sender::ibc::ibc_packet_ack;
+use icq_sender::msg::{ExecuteMsg, QueryBatchMsg, QueryMsg, QuerySpec, ReconcileEscrowMsg, SUPPLY_OF_QUERY_PATH};
+use icq_sender::state::Reconciliation;
+use prost::Message;
+
+use common::{ack_with_responses, packet_of, remote_address, setup, Deps, CHANNEL};
+
+const VOUCHER: &str = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+fn allow_supply(deps: &mut Deps, admin: &MessageInfo) {
+    let msg = ExecuteMsg::AllowQueryPaths { channel: CHANNEL.to_string(), paths: vec![SUPPLY_OF_QUERY_PATH.to_string()] };
+    execute(deps.as_mut(), common::mock_env(), admin.clone(), msg).unwrap();
+}
+
+fn reconcile_msg() -> ExecuteMsg {
+    ExecuteMsg::ReconcileEscrow(ReconcileEscrowMsg {
+        channel: CHANNEL.to_string(),
+        transfer_channel: "channel-7".to_string(),
+        denom: "uosmo".to_string(),
+        voucher_denom: VOUCHER.to_string(),
+    })
+}
+
+fn answer(value: Vec<u8>) -> AbciQueryResponse {
+    AbciQueryResponse { value, height: 100, ..Default::default() }
+}
+
+#[test]
+fn an_undecodable_answer_is_recorded_as_an_error_without_failing_the_ack() {
+    let (mut deps, env, admin) = setup();
+    allow_supply(&mut deps, &admin);
+    let res = execute(deps.as_mut(), env.clone(), admin.clone(), reconcile_msg()).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), env.clone(), ack_with_responses(packet_of(&res), vec![answer(vec![0xff, 0xff])], 5)).unwrap();
+
+    assert!(res.attributes.iter().any(|attr| attr.key == "error"), "{:?}", res.attributes);
+    let errors: Vec<(u64, String)> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::AllErrors {}).unwrap()).unwrap();
+    assert_eq!(errors.iter().map(|(sequence, _)| *sequence).collect::<Vec<_>>(), vec![5]);
+    let msg = QueryMsg::Reconciliation { channel: CHANNEL.to_string(), voucher_denom: VOUCHER.to_string() };
+    assert!(query(deps.as_ref(), env.clone(), msg.clone()).is_err());
+    // the undecodable answer is not cached
+    let res = execute(deps.as_mut(), env.clone(), admin.clone(), reconcile_msg()).unwrap();
+    assert!(!res.attributes.iter().any(|attr| attr.key == "cache_hit"));
+    assert_eq!(res.messages.len(), 1);
+
+    // the request completed, so the next one is sent, and the answers after
+    // an undecodable one are still recorded
+    let batch = QueryBatchMsg {
+        channel: CHANNEL.to_string(),
+        queries: vec![
+            QuerySpec::Balance { address: remote_address("cosmos", 1), denom: "uatom".to_string() },
+            QuerySpec::SupplyOf { denom: VOUCHER.to_string() },
+        ],
+        height: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::SendQueryBatch(batch)).unwrap();
+    let supply = QuerySupplyOfResponse { amount: Some(ProtoCoinMsg { denom: VOUCHER.to_string(), amount: "0".to_string() }) };
+    let ack = ack_with_responses(packet_of(&res), vec![answer(vec![0xff]), answer(supply.encode_to_vec())], 6);
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack).unwrap();
+
+    let errors: Vec<(u64, String)> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::AllErrors {}).unwrap()).unwrap();
+    assert_eq!(errors.iter().map(|(sequence, _)| *sequence).collect::<Vec<_>>(), vec![5, 6]);
+    let reconciliation: Reconciliation = from_json(query(deps.as_ref(), env, msg).unwrap()).unwrap();
+    assert!(reconciliation.matched);
+    assert_eq!(reconciliation.remote_supply, Uint128::zero());
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use cosmwasm_std::testing::{message_info, mock_ibc_packet_timeout};
+use cosmwasm_std::{from_json, Binary};
+use icq_sender::contract::{execute, query};
+use icq_sender::ibc::{ibc_packet_ack, ibc_packet_timeout};
+use icq_sender::msg::{ArithmeticTwapToNowResponse, ExecuteMsg, QueryMsg};
+use icq_sender::state::PendingRequest;
+use prost::Message;
+
+use common::{ack_for, packet_of, setup, twap_msg, CHANNEL};
+
+fn twap_value(raw: &str) -> Vec<u8> {
+    ArithmeticTwapToNowResponse { arithmetic_twap: raw.to_string() }.encode_to_vec()
+}
+
+fn pending_requests(deps: &common::Deps) -> Vec<(u64, PendingRequest)> {
+    from_json(query(deps.as_ref(), common::mock_env(), QueryMsg::PendingRequests {}).unwrap()).unwrap()
+}
+
+#[test]
+fn identical_queries_in_flight_share_one_packet() {
+    let (mut deps, env, _) = setup();
+    let alice = message_info(&deps.api.addr_make("alice"), &[]);
+    let bob = message_info(&deps.api.addr_make("bob"), &[]);
+    let msg = ExecuteMsg::SendQueryTwap(twap_msg(1, "uosmo", "uatom"));
+
+    let res = execute(deps.as_mut(), env.clone(), alice.clone(), msg.clone()).unwrap();
+    let packet = packet_of(&res);
+    let joined = execute(deps.as_mut(), env.clone(), bob.clone(), msg.clone()).unwrap();
+    assert!(joined.messages.is_empty());
+    assert!(joined.attributes.iter().any(|attr| attr.key == "coalesced" && attr.value == "true"));
+
+    let pending = pending_requests(&deps);
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].1.waiters, vec![alice.sender.clone(), bob.sender.clone()]);
+
+    // another pool is a different request and gets its own packet
+    let other = execute(deps.as_mut(), env.clone(), bob.clone(), ExecuteMsg::SendQueryTwap(twap_msg(2, "uosmo", "uatom"))).unwrap();
+    assert_eq!(other.messages.len(), 1);
+
+    let res = ibc_packet_ack(deps.as_mut(), env, ack_for(packet, vec![twap_value("1.5")], 1)).unwrap();
+    let waiters: Vec<_> = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "icq_result")
+        .flat_map(|event| event.attributes.iter().filter(|attr| attr.key == "waiter").map(|attr| attr.value.clone()))
+        .collect();
+    assert_eq!(waiters, vec![alice.sender.to_string(), bob.sender.to_string()]);
+    assert_eq!(pending_requests(&deps).len(), 1);
+}
+
+#[test]
+fn answered_queries_are_served_from_the_cache_until_the_ttl_passes() {
+    let (mut deps, mut env, _) = setup();
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+    let msg = ExecuteMsg::SendQueryTwap(twap_msg(1, "uosmo", "uatom"));
+
+    let res = execute(deps.as_mut(), env.clone(), user.clone(), msg.clone()).unwrap();
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![twap_value("1.5")], 1)).unwrap();
+
+    let cached = execute(deps.as_mut(), env.clone(), user.clone(), msg.clone()).unwrap();
+    assert!(cached.messages.is_empty());
+    assert_eq!(cached.data, Some(Binary::from(twap_value("1.5"))));
+
+    env.block.time = env.block.time.plus_seconds(31);
+    let res = execute(deps.as_mut(), env, user, msg).unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert!(res.data.is_none());
+}
+
+#[test]
+fn failed_queries_are_not_cached() {
+    let (mut deps, env, _) = setup();
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+    let msg = ExecuteMsg::SendQueryTwap(twap_msg(1, "uosmo", "uatom"));
+
+    let res = execute(deps.as_mut(), env.clone(), user.clone(), msg.clone()).unwrap();
+    let mut timeout = mock_ibc_packet_timeout(CHANNEL, &1u8).unwrap();
+    timeout.packet.data = packet_of(&res);
+    ibc_packet_timeout(deps.as_mut(), env.clone(), timeout).unwrap();
+    assert!(pending_requests(&deps).is_empty());
+
+    let res = execute(deps.as_mut(), env, user, msg).unwrap();
+    assert_eq!(res.messages.len(), 1);
+}
+
+#[test]
+fn height_pinned_queries_are_never_coalesced() {
+    let (mut deps, env, _) = setup();
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+    let mut twap = twap_msg(1, "uosmo", "uatom");
+    twap.height = Some(100);
+
+    let first = execute(deps.as_mut(), env.clone(), user.clone(), ExecuteMsg::SendQueryTwap(twap.clone())).unwrap();
+    let second = execute(deps.as_mut(), env, user, ExecuteMsg::SendQueryTwap(twap)).unwrap();
+    assert_eq!(first.messages.len(), 1);
+    assert_eq!(second.messages.len(), 1);
+    assert_eq!(pending_requests(&deps).len(), 2);
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use cosmwasm_std::testing::message_info;
+use cosmwasm_std::{coin, coins, from_json, BankMsg, Coin, CosmosMsg};
+use icq_sender::contract::{execute, query};
+use icq_sender::msg::{ExecuteMsg, QueryMsg, QuerySpec, UpdateConfigMsg};
+use icq_sender::ContractError;
+
+use common::{remote_address, setup, CHANNEL};
+
+fn bounty_paid(res: &cosmwasm_std::Response) -> Option<Vec<Coin>> {
+    res.messages.iter().find_map(|msg| match &msg.msg {
+        CosmosMsg::Bank(BankMsg::Send { amount, .. }) => Some(amount.clone()),
+        _ => None,
+    })
+}
+
+#[test]
+fn poke_bounties_are_paid_from_the_pool_only() {
+    let (mut deps, env, admin) = setup();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        poke_bounty: Some(coin(10, "untrn")),
+        ..Default::default()
+    }))
+    .unwrap();
+    // credits and escrows held by the contract must never pay a bounty
+    deps.querier.bank.update_balance(&env.contract.address, coins(1_000, "untrn"));
+
+    let keeper = message_info(&deps.api.addr_make("keeper"), &[]);
+    let subscribe = |pool: u8| ExecuteMsg::Subscribe {
+        channel: CHANNEL.to_string(),
+        query: QuerySpec::Balance { address: remote_address("cosmos", pool), denom: "uatom".to_string() },
+        interval_seconds: 60,
+    };
+    execute(deps.as_mut(), env.clone(), admin.clone(), subscribe(1)).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::Poke {}).unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(bounty_paid(&res), None);
+
+    let err = execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::FundBountyPool {}).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+    let funding = message_info(&admin.sender, &coins(15, "untrn"));
+    execute(deps.as_mut(), env.clone(), funding, ExecuteMsg::FundBountyPool {}).unwrap();
+
+    execute(deps.as_mut(), env.clone(), admin.clone(), subscribe(2)).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::Poke {}).unwrap();
+    assert_eq!(bounty_paid(&res), Some(coins(10, "untrn")));
+    let pool: Vec<Coin> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::BountyPool {}).unwrap()).unwrap();
+    assert_eq!(pool, coins(5, "untrn"));
+
+    // the pool no longer covers a bounty
+    execute(deps.as_mut(), env.clone(), admin.clone(), subscribe(3)).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), keeper, ExecuteMsg::Poke {}).unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(bounty_paid(&res), None);
+
+    let err = execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::WithdrawBountyPool { amount: coin(6, "untrn") }).unwrap_err();
+    assert!(matches!(err, ContractError::InsufficientBountyPool { .. }));
+    let res = execute(deps.as_mut(), env, admin, ExecuteMsg::WithdrawBountyPool { amount: coin(5, "untrn") }).unwrap();
+    assert_eq!(bounty_paid(&res), Some(coins(5, "untrn")));
+}
+
+#[test]
+fn poking_subscriptions_of_ones_own_earns_nothing() {
+    let (mut deps, env, admin) = setup();
+    execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        poke_bounty: Some(coin(10, "untrn")),
+        ..Default::default()
+    }))
+    .unwrap();
+    execute(deps.as_mut(), env.clone(), message_info(&admin.sender, &coins(100, "untrn")), ExecuteMsg::FundBountyPool {}).unwrap();
+
+    let keeper = message_info(&deps.api.addr_make("keeper"), &[]);
+    let subscribe = |pool: u8| ExecuteMsg::Subscribe {
+        channel: CHANNEL.to_string(),
+        query: QuerySpec::Balance { address: remote_address("cosmos", pool), denom: "uatom".to_string() },
+        interval_seconds: 1,
+    };
+    for pool in 1..=3 {
+        execute(deps.as_mut(), env.clone(), keeper.clone(), subscribe(pool)).unwrap();
+    }
+    let res = execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::Poke {}).unwrap();
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(bounty_paid(&res), None);
+    let pool: Vec<Coin> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::BountyPool {}).unwrap()).unwrap();
+    assert_eq!(pool, coins(100, "untrn"));
+
+    // sending a query of the admin along with them is what earns the bounty
+    let mut later = env.clone();
+    later.block.time = later.block.time.plus_seconds(1);
+    execute(deps.as_mut(), later.clone(), admin, subscribe(4)).unwrap();
+    let res = execute(deps.as_mut(), later, keeper, ExecuteMsg::Poke {}).unwrap();
+    assert_eq!(bounty_paid(&res), Some(coins(10, "untrn")));
+}
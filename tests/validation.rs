@@ -0,0 +1,104 @@
+mod common;
+
+use cosmwasm_std::testing::message_info;
+use cosmwasm_std::Binary;
+use icq_sender::contract::execute;
+use icq_sender::msg::{ExecuteMsg, QueryBalanceMsg, QueryRawMsg, TWAP_QUERY_PATH};
+use icq_sender::validation::{validate_denom, validate_remote_address, validate_smart_query, validate_store_name, validate_twap_query};
+use icq_sender::ContractError;
+
+use common::{remote_address, setup, twap_msg, CHANNEL};
+
+#[test]
+fn remote_addresses_must_be_bech32_with_the_channel_prefix() {
+    let address = remote_address("cosmos", 1);
+    validate_remote_address(&address, None).unwrap();
+    validate_remote_address(&address, Some("cosmos")).unwrap();
+
+    let err = validate_remote_address(&address, Some("osmo")).unwrap_err();
+    assert_eq!(err, ContractError::AddressPrefixMismatch {
+        address: address.clone(),
+        expected: "osmo".to_string(),
+        actual: "cosmos".to_string(),
+    });
+    assert!(matches!(validate_remote_address("cosmos1abc", None), Err(ContractError::InvalidAddress { .. })));
+    assert!(matches!(validate_remote_address("", None), Err(ContractError::InvalidAddress { .. })));
+}
+
+#[test]
+fn denoms_follow_the_sdk_syntax_and_namespaces() {
+    for denom in ["uatom", "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2", "gamm/pool/1", "a.b_c-d:e"] {
+        validate_denom(denom).unwrap();
+    }
+    validate_denom(&format!("factory/{}/ufoo", remote_address("osmo", 2))).unwrap();
+
+    let invalid = [
+        "ua",
+        "1atom",
+        "u atom",
+        "ibc/27394fb092d2eccd56123c74f36e4c1f926001ceada9ca97ea622b25f41e5eb2",
+        "ibc/ABC",
+        "factory/notanaddress/ufoo",
+        "factory/creator",
+    ];
+    for denom in invalid {
+        assert!(matches!(validate_denom(denom), Err(ContractError::InvalidDenom { .. })), "{denom}");
+    }
+    let long_subdenom = format!("factory/{}/{}", remote_address("osmo", 2), "u".repeat(45));
+    assert!(validate_denom(&long_subdenom).is_err());
+    assert!(validate_denom(&"u".repeat(129)).is_err());
+}
+
+#[test]
+fn twap_queries_need_a_pool_and_distinct_assets() {
+    validate_twap_query(1, "uosmo", "uatom").unwrap();
+    assert_eq!(validate_twap_query(0, "uosmo", "uatom"), Err(ContractError::InvalidPoolId { pool_id: 0 }));
+    assert_eq!(
+        validate_twap_query(1, "uosmo", "uosmo"),
+        Err(ContractError::IdenticalTwapAssets { denom: "uosmo".to_string() }),
+    );
+    assert!(validate_twap_query(1, "uosmo", "u").is_err());
+}
+
+#[test]
+fn smart_queries_and_store_names() {
+    validate_smart_query(&Binary::from(br#"{"config":{}}"#)).unwrap();
+    assert!(matches!(validate_smart_query(&Binary::from(b"{config")), Err(ContractError::InvalidSmartQuery { .. })));
+
+    validate_store_name("bank").unwrap();
+    validate_store_name("ibc_transfer-2").unwrap();
+    assert!(validate_store_name("").is_err());
+    assert!(validate_store_name("bank/balances").is_err());
+}
+
+#[test]
+fn invalid_queries_are_rejected_before_sending() {
+    let (mut deps, env, admin) = setup();
+    let user = message_info(&deps.api.addr_make("user"), &[]);
+    execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::SetChannelPrefix {
+        channel: CHANNEL.to_string(),
+        prefix: Some("osmo".to_string()),
+    })
+    .unwrap();
+
+    let balance = |address: String, denom: &str| ExecuteMsg::SendQueryBalance(QueryBalanceMsg {
+        channel: CHANNEL.to_string(),
+        height: None,
+        address,
+        denom: denom.to_string(),
+    });
+    let err = execute(deps.as_mut(), env.clone(), user.clone(), balance(remote_address("cosmos", 1), "uatom")).unwrap_err();
+    assert!(matches!(err, ContractError::AddressPrefixMismatch { .. }));
+    let err = execute(deps.as_mut(), env.clone(), user.clone(), balance(remote_address("osmo", 1), "1atom")).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidDenom { .. }));
+    let res = execute(deps.as_mut(), env.clone(), user.clone(), balance(remote_address("osmo", 1), "uatom")).unwrap();
+    assert_eq!(res.messages.len(), 1);
+
+    let err = execute(deps.as_mut(), env.clone(), user.clone(), ExecuteMsg::SendQueryTwap(twap_msg(0, "uosmo", "uatom"))).unwrap_err();
+    assert_eq!(err, ContractError::InvalidPoolId { pool_id: 0 });
+
+    // a TWAP over a window of the sender's choosing is not taken as a price
+    let raw = QueryRawMsg { channel: CHANNEL.to_string(), path: TWAP_QUERY_PATH.to_string(), data: Binary::default(), height: None };
+    let err = execute(deps.as_mut(), env, user, ExecuteMsg::SendQueryRaw(raw)).unwrap_err();
+    assert_eq!(err, ContractError::RawQueryPathNotAllowed { path: TWAP_QUERY_PATH.to_string() });
+}
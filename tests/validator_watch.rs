@@ -0,0 +1,137 @@
+mod common;
+
+use cosmos_sdk_proto::cosmos::crypto::ed25519::PubKey;
+use cosmos_sdk_proto::cosmos::slashing::v1beta1::{QuerySigningInfoResponse, ValidatorSigningInfo};
+use cosmos_sdk_proto::cosmos::staking::v1beta1::{BondStatus, Commission, CommissionRates, QueryValidatorResponse, Validator};
+use cosmos_sdk_proto::{Any, Timestamp};
+use cosmwasm_std::{from_json, Binary, CosmosMsg, Decimal256, IbcMsg, MessageInfo};
+use icq_sender::contract::{execute, query};
+use icq_sender::ibc::ibc_packet_ack;
+use icq_sender::msg::{ExecuteMsg, QueryMsg, WatchValidatorMsg, SLASHING_SIGNING_INFO_QUERY_PATH, STAKING_VALIDATOR_QUERY_PATH};
+use icq_sender::state::ValidatorWatch;
+use prost::Message;
+
+use common::{ack_for, packet_of, remote_address, sent_packet, setup, Deps, CHANNEL};
+
+fn validator(operator_address: &str, rate: &str, jailed: bool) -> Vec<u8> {
+    validator_with_key(operator_address, rate, jailed, 3)
+}
+
+fn validator_with_key(operator_address: &str, rate: &str, jailed: bool, key: u8) -> Vec<u8> {
+    let pubkey = Any { type_url: "/cosmos.crypto.ed25519.PubKey".to_string(), value: PubKey { key: vec![key; 32] }.encode_to_vec() };
+    let validator = Validator {
+        operator_address: operator_address.to_string(),
+        consensus_pubkey: Some(pubkey),
+        jailed,
+        status: BondStatus::Bonded as i32,
+        commission: Some(Commission {
+            commission_rates: Some(CommissionRates { rate: rate.to_string(), max_rate: "0.2".to_string(), max_change_rate: String::new() }),
+            update_time: None,
+        }),
+        ..Default::default()
+    };
+    QueryValidatorResponse { validator: Some(validator) }.encode_to_vec()
+}
+
+fn watch(deps: &Deps, operator_address: &str) -> ValidatorWatch {
+    let msg = QueryMsg::ValidatorWatch { chain: "hub".to_string(), operator_address: operator_address.to_string() };
+    from_json(query(deps.as_ref(), common::mock_env(), msg).unwrap()).unwrap()
+}
+
+fn signing_info(address: &str, tombstoned: bool, jailed_until: Option<Timestamp>) -> Vec<u8> {
+    let info = ValidatorSigningInfo { address: address.to_string(), tombstoned, jailed_until, ..Default::default() };
+    QuerySigningInfoResponse { val_signing_info: Some(info) }.encode_to_vec()
+}
+
+/// Watches `operator_address` on the "hub" alias of the test channel.
+fn watch_validator(deps: &mut Deps, admin: &MessageInfo, operator_address: &str) {
+    execute(deps.as_mut(), common::mock_env(), admin.clone(), ExecuteMsg::AllowQueryPaths {
+        channel: CHANNEL.to_string(),
+        paths: vec![STAKING_VALIDATOR_QUERY_PATH.to_string(), SLASHING_SIGNING_INFO_QUERY_PATH.to_string()],
+    })
+    .unwrap();
+    let alias = ExecuteMsg::SetChainAlias { alias: "hub".to_string(), channel: Some(CHANNEL.to_string()) };
+    execute(deps.as_mut(), common::mock_env(), admin.clone(), alias).unwrap();
+    let msg = WatchValidatorMsg { chain: "hub".to_string(), operator_address: operator_address.to_string(), interval_seconds: 60 };
+    execute(deps.as_mut(), common::mock_env(), admin.clone(), ExecuteMsg::WatchValidator(msg)).unwrap();
+}
+
+#[test]
+fn an_unparsable_commission_is_recorded_as_an_error() {
+    let (mut deps, mut env, admin) = setup();
+    let operator_address = remote_address("cosmosvaloper", 9);
+    watch_validator(&mut deps, &admin, &operator_address);
+
+    let res = execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::Poke {}).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![validator(&operator_address, "5%", true)], 1)).unwrap();
+    assert!(res.attributes.iter().any(|attr| attr.key == "error"));
+    // no signing info query is sent for a validator that was not recorded
+    assert!(res.messages.is_empty());
+    assert_eq!(watch(&deps, &operator_address).validator, None);
+
+    env.block.time = env.block.time.plus_seconds(60);
+    let res = execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::Poke {}).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), env, ack_for(packet_of(&res), vec![validator(&operator_address, "0.05", true)], 2)).unwrap();
+    assert!(!res.attributes.iter().any(|attr| attr.key == "error"));
+    assert_eq!(res.messages.len(), 1);
+    let state = watch(&deps, &operator_address).validator.unwrap();
+    assert!(state.jailed);
+    assert_eq!(state.commission_rate, "0.05".parse::<Decimal256>().unwrap());
+}
+
+#[test]
+fn a_tombstoned_validator_is_recorded_as_jailed_forever() {
+    let (mut deps, mut env, admin) = setup();
+    let operator_address = remote_address("cosmosvaloper", 9);
+    watch_validator(&mut deps, &admin, &operator_address);
+
+    let res = execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::Poke {}).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![validator(&operator_address, "0.05", false)], 1)).unwrap();
+    let consensus_address = watch(&deps, &operator_address).consensus_address.unwrap();
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(sent_packet(&res), vec![signing_info(&consensus_address, false, None)], 2)).unwrap();
+
+    // the host jails a tombstoned validator until 9999-12-31T23:59:59Z
+    env.block.time = env.block.time.plus_seconds(60);
+    let res = execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::Poke {}).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packet_of(&res), vec![validator(&operator_address, "0.05", true)], 3)).unwrap();
+    let forever = Timestamp { seconds: 253_402_300_799, nanos: 0 };
+    let res = ibc_packet_ack(deps.as_mut(), env, ack_for(sent_packet(&res), vec![signing_info(&consensus_address, true, Some(forever))], 4)).unwrap();
+    assert!(res.events.iter().any(|event| event.ty == "validator_tombstoned"));
+    let signing = watch(&deps, &operator_address).signing_info.unwrap();
+    assert!(signing.tombstoned);
+    assert_eq!(signing.jailed_until, Some(cosmwasm_std::Timestamp::from_nanos(u64::MAX)));
+}
+
+#[test]
+fn a_signing_info_only_reaches_the_watch_of_its_validator() {
+    let (mut deps, env, admin) = setup();
+    let (first, second) = (remote_address("cosmosvaloper", 1), remote_address("cosmosvaloper", 2));
+    watch_validator(&mut deps, &admin, &first);
+    watch_validator(&mut deps, &admin, &second);
+
+    let res = execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::Poke {}).unwrap();
+    let packets: Vec<Binary> = res
+        .messages
+        .iter()
+        .filter_map(|msg| match &msg.msg {
+            CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) => Some(data.clone()),
+            _ => None,
+        })
+        .collect();
+    let res = ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packets[0].clone(), vec![validator_with_key(&first, "0.05", false, 1)], 1)).unwrap();
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(packets[1].clone(), vec![validator_with_key(&second, "0.05", false, 2)], 2)).unwrap();
+    let consensus_address = watch(&deps, &first).consensus_address.unwrap();
+    assert_ne!(watch(&deps, &second).consensus_address, Some(consensus_address.clone()));
+
+    let signing_packet = sent_packet(&res);
+    ibc_packet_ack(deps.as_mut(), env.clone(), ack_for(signing_packet.clone(), vec![signing_info(&consensus_address, false, None)], 3)).unwrap();
+    assert!(watch(&deps, &first).signing_info.is_some());
+    assert!(watch(&deps, &second).signing_info.is_none());
+
+    // an unwatched validator leaves the index along with its watch
+    let unwatch = ExecuteMsg::UnwatchValidator { chain: "hub".to_string(), operator_address: first };
+    execute(deps.as_mut(), env.clone(), admin, unwatch).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), env, ack_for(signing_packet, vec![signing_info(&consensus_address, true, None)], 4)).unwrap();
+    assert!(!res.attributes.iter().any(|attr| attr.key == "error"), "{:?}", res.attributes);
+    assert!(res.events.iter().all(|event| event.ty != "validator_tombstoned"));
+}